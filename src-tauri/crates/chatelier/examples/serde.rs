@@ -1,6 +1,7 @@
 use std::error::Error;
 
-use chatelier::{Adjustment, Direction};
+use chatelier::{Adjustment, Direction, InertGas, System};
+use chem_eq::Equation;
 
 fn main() -> Result<(), Box<dyn Error>> {
     println!(
@@ -15,10 +16,19 @@ fn main() -> Result<(), Box<dyn Error>> {
         "Adjust: {}",
         serde_json::to_string(&Adjustment::Volume(1.0))?
     );
+    println!("Adjust: {}", serde_json::to_string(&Adjustment::Catalyst)?);
+    println!(
+        "Adjust: {}",
+        serde_json::to_string(&Adjustment::InertGas(InertGas::AtConstantPressure(0.5)))?
+    );
 
     println!("Direction {}", serde_json::to_string(&Direction::Forward)?);
     println!("Direction {}", serde_json::to_string(&Direction::Reverse)?);
     println!("Direction {}", serde_json::to_string(&Direction::None)?);
 
+    let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)")?;
+    eq.set_concentrations(&[1.0, 0.5])?;
+    println!("System {}", serde_json::to_string(&System::new(eq)?)?);
+
     Ok(())
 }
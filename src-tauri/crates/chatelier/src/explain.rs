@@ -0,0 +1,509 @@
+//! Explanations of why a [`System`] shifts
+
+use std::fmt::{self, Display};
+
+use chem_eq::Compound;
+use float_cmp::approx_eq;
+
+use crate::{is_gas, AdjustError, Adjustment, Direction, InertGas, System};
+
+/// Why a [`System`] would shift after an [`Adjustment`], returned by [`System::explain`].
+///
+/// Render it with [`Display`] for a plain-English sentence.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::{Adjustment, Direction, System};
+///
+/// let mut eq = Equation::new("SO2 + NO2 <-> NO + SO3").unwrap();
+/// eq.set_concentrations(&[2.0, 1.0, 2.0, 2.0]).unwrap();
+/// let system = System::new(eq).unwrap();
+///
+/// let explanation = system.explain(Adjustment::Concentration("SO3", 3.0)).unwrap();
+/// assert_eq!(explanation.direction, Direction::Reverse);
+/// assert_eq!(explanation.increases, vec!["SO2", "NO2"]);
+/// assert_eq!(
+///     explanation.to_string(),
+///     "Changing the concentration of SO3 to 3M makes Q (3) greater than K (2), so the \
+///      equilibrium shifts left. SO2 and NO2 increase while NO and SO3 decrease."
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    /// The rule that decided the shift
+    pub rule: Rule,
+    /// The direction the equilibrium shifts in
+    pub direction: Direction,
+    /// The reaction quotient before the adjustment, [`None`] if a side has no concentration
+    pub q_before: Option<f32>,
+    /// The reaction quotient right after the adjustment, before the system reacts
+    pub q_after: Option<f32>,
+    /// The k expression before the adjustment
+    pub k_before: f32,
+    /// The k expression after the adjustment
+    pub k_after: f32,
+    /// Names of compounds the shift produces
+    pub increases: Vec<String>,
+    /// Names of compounds the shift consumes
+    pub decreases: Vec<String>,
+}
+
+/// The rule deciding how an equilibrium shifts
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rule {
+    /// A concentration changed, so the reaction quotient is compared to the k expression
+    ReactionQuotient {
+        /// Name of the compound that changed
+        compound: String,
+        /// Its new concentration in M (mol/L)
+        concentration: f32,
+    },
+    /// The pressure of the reacting gases changed, favouring the side with fewer moles of gas
+    /// when it rises and more moles of gas when it falls
+    GasMoles {
+        /// Moles of gas on the left
+        reactants: usize,
+        /// Moles of gas on the right
+        products: usize,
+        /// How the pressure of the reacting gases changed
+        pressure: Change,
+    },
+    /// The temperature changed, favouring the endothermic direction when it rises and the
+    /// exothermic direction when it falls
+    Enthalpy {
+        /// ΔH of the forward reaction in kJ
+        delta_h: f32,
+        /// How the temperature changed
+        temperature: Change,
+    },
+    /// A catalyst speeds up both directions equally
+    Catalyst,
+    /// Inert gas at constant volume doesn't change any partial pressure
+    InertGasConstantVolume,
+}
+
+/// Which way a condition like temperature or pressure changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Change {
+    /// It went up
+    Increased,
+    /// It went down
+    Decreased,
+    /// It stayed the same
+    Unchanged,
+}
+
+impl Change {
+    fn between(before: f32, after: f32) -> Self {
+        if after > before {
+            Self::Increased
+        } else if after < before {
+            Self::Decreased
+        } else {
+            Self::Unchanged
+        }
+    }
+}
+
+impl System {
+    /// Explain how the system would shift after an adjustment without changing it. This
+    /// includes the rule applied, Q and K before and after, and which compounds change.
+    pub fn explain(&self, adjust: Adjustment) -> Result<Explanation, AdjustError> {
+        let q_before = self.eq.equilibrium_constant();
+        let k_before = self.k_expr;
+
+        let (rule, direction, q_after, k_after) = match adjust {
+            Adjustment::Concentration(cmp, conc) => {
+                if conc == 0.0 {
+                    return Err(AdjustError::ZeroConcentration);
+                }
+                let mut sys = self.clone();
+                sys.eq.set_concentration_by_name(cmp, conc)?;
+                let rule = Rule::ReactionQuotient {
+                    compound: without_coefficient(cmp).to_string(),
+                    concentration: conc,
+                };
+
+                (
+                    rule,
+                    sys.direction_to_favour()?,
+                    sys.eq.equilibrium_constant(),
+                    k_before,
+                )
+            }
+            Adjustment::Temperature(temperature) => {
+                let current = self.eq.temperature().unwrap_or(25.0);
+                // heat is a product of exothermic reactions, so adding it pushes them back,
+                // which shows up as K moving away from Q
+                let rule = Rule::Enthalpy {
+                    delta_h: self.eq.delta_h(),
+                    temperature: Change::between(current, temperature),
+                };
+                let mut sys = self.clone();
+                sys.change_temperature(temperature)?;
+
+                (rule, sys.direction_to_favour()?, q_before, sys.k_expr)
+            }
+            Adjustment::Volume(volume) => {
+                let mut sys = self.clone();
+                sys.change_volume(volume)?;
+
+                (
+                    self.gas_moles(&sys),
+                    sys.direction_to_favour()?,
+                    sys.eq.equilibrium_constant(),
+                    k_before,
+                )
+            }
+            Adjustment::Catalyst => (Rule::Catalyst, Direction::None, q_before, k_before),
+            Adjustment::InertGas(gas @ InertGas::AtConstantPressure(_)) => {
+                let mut sys = self.clone();
+                sys.add_inert_gas(gas)?;
+
+                (
+                    self.gas_moles(&sys),
+                    sys.direction_to_favour()?,
+                    sys.eq.equilibrium_constant(),
+                    k_before,
+                )
+            }
+            Adjustment::InertGas(gas @ InertGas::AtConstantVolume(_)) => {
+                // still check the amount is valid
+                self.clone().add_inert_gas(gas)?;
+
+                (
+                    Rule::InertGasConstantVolume,
+                    Direction::None,
+                    q_before,
+                    k_before,
+                )
+            }
+        };
+
+        let names = self
+            .eq
+            .compound_names()
+            .map(|name| without_coefficient(name).to_string())
+            .collect::<Vec<_>>();
+        let (left, right) = names.split_at(self.eq.left().len());
+        let (increases, decreases) = match direction {
+            Direction::Forward => (right.to_vec(), left.to_vec()),
+            Direction::Reverse => (left.to_vec(), right.to_vec()),
+            Direction::None => (vec![], vec![]),
+        };
+
+        Ok(Explanation {
+            rule,
+            direction,
+            q_before,
+            q_after,
+            k_before,
+            k_after,
+            increases,
+            decreases,
+        })
+    }
+
+    /// The gas moles rule for changing the volume of this system to that of `after`
+    fn gas_moles(&self, after: &Self) -> Rule {
+        let count = |side: &[Compound]| {
+            side.iter()
+                .filter(|c| is_gas(c))
+                .map(|c| c.coefficient)
+                .sum::<usize>()
+        };
+
+        Rule::GasMoles {
+            reactants: count(self.eq.left()),
+            products: count(self.eq.right()),
+            // pressure goes up as the volume goes down
+            pressure: Change::between(
+                after.eq.volume().unwrap_or(1.0),
+                self.eq.volume().unwrap_or(1.0),
+            ),
+        }
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shift = match self.direction {
+            Direction::Forward => "the equilibrium shifts right",
+            Direction::Reverse => "the equilibrium shifts left",
+            Direction::None => "the equilibrium doesn't shift",
+        };
+
+        match &self.rule {
+            Rule::ReactionQuotient {
+                compound,
+                concentration,
+            } => {
+                let q = self
+                    .q_after
+                    .map_or_else(|| "Q".to_string(), |q| format!("Q ({})", q));
+                let comparison = match self.q_after {
+                    Some(q) if approx_eq!(f32, q, self.k_before, ulps = 4) => "equal to",
+                    Some(q) if q > self.k_before => "greater than",
+                    Some(_) => "less than",
+                    // a side with nothing in it can only be made
+                    None if self.direction == Direction::Forward => "less than",
+                    None => "greater than",
+                };
+                write!(
+                    f,
+                    "Changing the concentration of {} to {}M makes {} {} K ({}), so {}.",
+                    compound, concentration, q, comparison, self.k_before, shift
+                )?;
+            }
+            Rule::GasMoles {
+                reactants,
+                products,
+                pressure,
+            } => {
+                let change = match pressure {
+                    Change::Increased => "Increasing the pressure",
+                    Change::Decreased => "Decreasing the pressure",
+                    Change::Unchanged => {
+                        return write!(f, "The pressure doesn't change, so {}.", shift);
+                    }
+                };
+                if reactants == products {
+                    write!(
+                        f,
+                        "{} has no effect because both sides have {} moles of gas, so {}.",
+                        change, reactants, shift
+                    )?;
+                } else {
+                    let side = if *pressure == Change::Increased {
+                        "fewer"
+                    } else {
+                        "more"
+                    };
+                    write!(
+                        f,
+                        "{} favours the side with {} moles of gas ({} on the left, {} on the \
+                         right), so {}.",
+                        change, side, reactants, products, shift
+                    )?;
+                }
+            }
+            Rule::Enthalpy {
+                delta_h,
+                temperature,
+            } => {
+                let change = match temperature {
+                    Change::Increased => "Increasing the temperature",
+                    Change::Decreased => "Decreasing the temperature",
+                    Change::Unchanged => {
+                        return write!(f, "The temperature doesn't change, so {}.", shift);
+                    }
+                };
+                if *delta_h == 0.0 {
+                    write!(f, "{} has no effect because ΔH is 0, so {}.", change, shift)?;
+                } else {
+                    let kind = if *delta_h > 0.0 {
+                        "endothermic"
+                    } else {
+                        "exothermic"
+                    };
+                    let favoured = if (*delta_h > 0.0) == (*temperature == Change::Increased) {
+                        "forward"
+                    } else {
+                        "reverse"
+                    };
+                    write!(
+                        f,
+                        "The forward reaction is {} (ΔH = {}kJ). {} favours the {} reaction, so \
+                         {}.",
+                        kind, delta_h, change, favoured, shift
+                    )?;
+                }
+            }
+            Rule::Catalyst => write!(
+                f,
+                "A catalyst speeds up the forward and reverse reactions equally, so {}.",
+                shift
+            )?,
+            Rule::InertGasConstantVolume => write!(
+                f,
+                "Adding an inert gas at constant volume raises the total pressure but not the \
+                 partial pressures, so {}.",
+                shift
+            )?,
+        }
+
+        if !self.increases.is_empty() {
+            write!(
+                f,
+                " {} increase while {} decrease.",
+                join(&self.increases),
+                join(&self.decreases)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A compound's name without its coefficient, so "2NH3(g)" reads as "NH3(g)"
+fn without_coefficient(name: &str) -> &str {
+    name.trim_start_matches(|c: char| c.is_ascii_digit())
+}
+
+/// Join names into a list like "A, B and C"
+fn join(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chem_eq::Equation;
+
+    use super::*;
+    use crate::fixtures::dinitrogen_tetroxide;
+
+    fn ammonia() -> System {
+        let mut eq = Equation::new("N2(g) + 3H2(g) <-> 2NH3(g)").unwrap();
+        eq.set_concentrations(&[1.0, 1.0, 1.0]).unwrap();
+        eq.set_delta_h(-92.0);
+        System::new(eq).unwrap()
+    }
+
+    #[test]
+    fn explain_volume() {
+        let explanation = ammonia().explain(Adjustment::Volume(0.5)).unwrap();
+        assert_eq!(
+            explanation.rule,
+            Rule::GasMoles {
+                reactants: 4,
+                products: 2,
+                pressure: Change::Increased
+            }
+        );
+        assert_eq!(explanation.direction, Direction::Forward);
+        assert_eq!(explanation.q_after, Some(0.25));
+        assert_eq!(
+            explanation.to_string(),
+            "Increasing the pressure favours the side with fewer moles of gas (4 on the left, 2 \
+             on the right), so the equilibrium shifts right. NH3(g) increase while N2(g) and \
+             H2(g) decrease."
+        );
+    }
+
+    #[test]
+    fn explain_temperature() {
+        let explanation = ammonia().explain(Adjustment::Temperature(100.0)).unwrap();
+        assert_eq!(explanation.direction, Direction::Reverse);
+        // exothermic reactions have a smaller k expression when hot
+        assert!(explanation.k_after < explanation.k_before);
+        assert_eq!(
+            explanation.to_string(),
+            "The forward reaction is exothermic (ΔH = -92kJ). Increasing the temperature \
+             favours the reverse reaction, so the equilibrium shifts left. N2(g) and H2(g) \
+             increase while NH3(g) decrease."
+        );
+    }
+
+    #[test]
+    fn temperature_out_of_equilibrium() {
+        // Q is well above K, so warming an endothermic reaction a little still shifts left
+        let mut system = dinitrogen_tetroxide();
+        system.set_k_expr(0.01).unwrap();
+
+        let explanation = system.explain(Adjustment::Temperature(26.0)).unwrap();
+        assert!(explanation.k_after > explanation.k_before);
+        assert_eq!(explanation.direction, Direction::Reverse);
+    }
+
+    #[test]
+    fn names_without_coefficients() {
+        let explanation = ammonia()
+            .explain(Adjustment::Concentration("2NH3(g)", 0.5))
+            .unwrap();
+        assert_eq!(
+            explanation.rule,
+            Rule::ReactionQuotient {
+                compound: "NH3(g)".to_string(),
+                concentration: 0.5,
+            }
+        );
+        assert_eq!(explanation.increases, vec!["NH3(g)"]);
+        assert_eq!(explanation.decreases, vec!["N2(g)", "H2(g)"]);
+    }
+
+    #[test]
+    fn unchanged_conditions() {
+        let system = ammonia();
+        let explanation = system.explain(Adjustment::Temperature(25.0)).unwrap();
+        assert_eq!(explanation.direction, Direction::None);
+        assert_eq!(
+            explanation.to_string(),
+            "The temperature doesn't change, so the equilibrium doesn't shift."
+        );
+
+        let explanation = system.explain(Adjustment::Volume(1.0)).unwrap();
+        assert_eq!(
+            explanation.to_string(),
+            "The pressure doesn't change, so the equilibrium doesn't shift."
+        );
+        let gas = InertGas::AtConstantPressure(0.0);
+        let explanation = system.explain(Adjustment::InertGas(gas)).unwrap();
+        assert_eq!(
+            explanation.to_string(),
+            "The pressure doesn't change, so the equilibrium doesn't shift."
+        );
+    }
+
+    #[test]
+    fn explain_catalyst() {
+        let explanation = ammonia().explain(Adjustment::Catalyst).unwrap();
+        assert_eq!(explanation.direction, Direction::None);
+        assert!(explanation.increases.is_empty());
+        assert_eq!(
+            explanation.to_string(),
+            "A catalyst speeds up the forward and reverse reactions equally, so the equilibrium \
+             doesn't shift."
+        );
+    }
+
+    #[test]
+    fn explain_matches_shift_direction() {
+        for adjust in [
+            Adjustment::Concentration("2NH3(g)", 0.5),
+            Adjustment::Concentration("N2(g)", 0.5),
+            Adjustment::Volume(2.0),
+            Adjustment::Volume(0.5),
+            Adjustment::Temperature(0.0),
+            Adjustment::Temperature(100.0),
+            Adjustment::InertGas(InertGas::AtConstantPressure(1.0)),
+            Adjustment::InertGas(InertGas::AtConstantVolume(1.0)),
+            Adjustment::Catalyst,
+        ] {
+            // how much ammonia the system makes or uses up reacting to the change
+            let mut changed = ammonia();
+            changed.change(adjust).unwrap();
+            let mut reacted = ammonia();
+            reacted.adjust(adjust).unwrap();
+            let made = reacted.eq.get_concentrations()[2] - changed.eq.get_concentrations()[2];
+            let shifted = if made > 1e-4 {
+                Direction::Forward
+            } else if made < -1e-4 {
+                Direction::Reverse
+            } else {
+                Direction::None
+            };
+
+            let explanation = ammonia().explain(adjust).unwrap();
+            assert_eq!(explanation.direction, shifted, "{:?}", adjust);
+        }
+    }
+}
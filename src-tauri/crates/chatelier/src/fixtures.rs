@@ -0,0 +1,14 @@
+//! Systems shared by the tests of each module
+
+use chem_eq::Equation;
+
+use crate::System;
+
+/// N2O4 and NO2 at equilibrium in 1 L, with ΔH of 57.2 kJ
+pub(crate) fn dinitrogen_tetroxide() -> System {
+    let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+    eq.set_concentrations(&[1.0, 0.5]).unwrap();
+    eq.set_volume(1.0);
+    eq.set_delta_h(57.2);
+    System::new(eq).unwrap()
+}
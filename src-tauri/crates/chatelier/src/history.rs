@@ -0,0 +1,300 @@
+//! Undo and redo for [`System`]
+
+use chem_eq::Equation;
+
+use crate::{AdjustError, OwnedAdjustment, System};
+
+/// The most adjustments a [`System`] remembers, older ones are forgotten
+pub const MAX_HISTORY: usize = 100;
+
+/// The adjustments applied to a [`System`], with the state around each one so they can be
+/// undone and redone
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct History {
+    /// Each adjustment with the state before it
+    undo: Vec<(OwnedAdjustment, Snapshot)>,
+    /// Each undone adjustment with the state after it
+    redo: Vec<(OwnedAdjustment, Snapshot)>,
+}
+
+impl History {
+    /// Remember an adjustment made from the `before` state, forgetting anything undone
+    pub(crate) fn push(&mut self, adjust: OwnedAdjustment, before: Snapshot) {
+        if self.undo.len() == MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.undo.push((adjust, before));
+        self.redo.clear();
+    }
+}
+
+/// Everything about a [`System`] except its history
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Snapshot {
+    eq: Equation,
+    k_expr: f32,
+    inert: f32,
+    catalysed: bool,
+}
+
+/// The state of a [`System`] after an adjustment in its history, from [`System::timeline`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    /// The adjustment made, [`None`] for the state before the first one
+    pub adjustment: Option<OwnedAdjustment>,
+    /// The concentration of each compound after reacting, in order
+    pub concentrations: Vec<f32>,
+    /// The k expression after the adjustment
+    pub k: f32,
+    /// The volume in L, if it's set
+    pub volume: Option<f32>,
+    /// The temperature in °C, if it's set
+    pub temperature: Option<f32>,
+}
+
+impl Step {
+    fn new(adjustment: Option<OwnedAdjustment>, eq: &Equation, k: f32) -> Self {
+        Self {
+            adjustment,
+            concentrations: eq.get_concentrations(),
+            k,
+            volume: eq.volume(),
+            temperature: eq.temperature(),
+        }
+    }
+}
+
+/// A saved [`System`], including its history, from [`System::checkpoint`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint(System);
+
+impl Checkpoint {
+    /// Get the saved system
+    pub fn system(&self) -> &System {
+        &self.0
+    }
+}
+
+impl System {
+    /// Undo the last adjustment, returning it. Returns [`None`] if there was nothing to undo.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Equation;
+    /// use chatelier::{Adjustment, System};
+    ///
+    /// let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+    /// eq.set_concentrations(&[1.0, 0.5]).unwrap();
+    /// let mut system = System::new(eq).unwrap();
+    ///
+    /// system.adjust(Adjustment::Concentration("N2O4(g)", 2.0)).unwrap();
+    /// system.undo().unwrap();
+    /// assert_eq!(system.equation().get_concentrations(), vec![1.0, 0.5]);
+    ///
+    /// system.redo().unwrap();
+    /// assert_ne!(system.equation().get_concentrations(), vec![1.0, 0.5]);
+    /// ```
+    pub fn undo(&mut self) -> Option<OwnedAdjustment> {
+        let (adjust, before) = self.history.undo.pop()?;
+        let after = self.snapshot();
+        self.load(before);
+        self.history.redo.push((adjust.clone(), after));
+
+        Some(adjust)
+    }
+
+    /// Redo the last undone adjustment, returning it. Returns [`None`] if there was nothing to
+    /// redo.
+    pub fn redo(&mut self) -> Option<OwnedAdjustment> {
+        let (adjust, after) = self.history.redo.pop()?;
+        let before = self.snapshot();
+        self.load(after);
+        self.history.undo.push((adjust.clone(), before));
+
+        Some(adjust)
+    }
+
+    /// Whether there's an adjustment to undo
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    /// Whether there's an adjustment to redo
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Get an iterator over the adjustments that can be undone, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &OwnedAdjustment> {
+        self.history.undo.iter().map(|(a, _)| a)
+    }
+
+    /// Get the state before the oldest adjustment that can be undone, and after each one
+    /// since, ending with the current state. Undone adjustments aren't included.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Equation;
+    /// use chatelier::{Adjustment, OwnedAdjustment, System};
+    ///
+    /// let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+    /// eq.set_concentrations(&[1.0, 0.5]).unwrap();
+    /// let mut system = System::new(eq).unwrap();
+    /// system.adjust(Adjustment::Volume(2.0)).unwrap();
+    ///
+    /// let timeline = system.timeline();
+    /// assert_eq!(timeline.len(), 2);
+    /// assert_eq!(timeline[0].adjustment, None);
+    /// assert_eq!(timeline[0].concentrations, vec![1.0, 0.5]);
+    /// assert_eq!(timeline[1].adjustment, Some(OwnedAdjustment::Volume(2.0)));
+    /// assert_eq!(timeline[1].concentrations, system.equation().get_concentrations());
+    /// ```
+    pub fn timeline(&self) -> Vec<Step> {
+        let undo = &self.history.undo;
+        let mut steps = Vec::with_capacity(undo.len() + 1);
+        // each snapshot is the state after the adjustment before it
+        for (i, (_, before)) in undo.iter().enumerate() {
+            let adjustment = i.checked_sub(1).map(|i| undo[i].0.clone());
+            steps.push(Step::new(adjustment, &before.eq, before.k_expr));
+        }
+        let last = undo.last().map(|(a, _)| a.clone());
+        steps.push(Step::new(last, &self.eq, self.k_expr));
+
+        steps
+    }
+
+    /// Save the system so it can be restored later
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.clone())
+    }
+
+    /// Restore the system and its history from a checkpoint
+    pub fn restore(&mut self, checkpoint: &Checkpoint) {
+        *self = checkpoint.0.clone();
+    }
+
+    /// Get what the system would be after an adjustment, without changing it
+    pub fn simulate(&self, adjust: &OwnedAdjustment) -> Result<System, AdjustError> {
+        let mut sys = self.clone();
+        sys.adjust_owned(adjust)?;
+
+        Ok(sys)
+    }
+
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            eq: self.eq.clone(),
+            k_expr: self.k_expr,
+            inert: self.inert,
+            catalysed: self.catalysed,
+        }
+    }
+
+    fn load(&mut self, snapshot: Snapshot) {
+        self.eq = snapshot.eq;
+        self.k_expr = snapshot.k_expr;
+        self.inert = snapshot.inert;
+        self.catalysed = snapshot.catalysed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixtures::dinitrogen_tetroxide, Adjustment, InertGas};
+
+    use super::*;
+
+    #[test]
+    fn undo_restores_everything() {
+        let mut system = dinitrogen_tetroxide();
+        let start = system.clone();
+
+        system.adjust(Adjustment::Temperature(100.0)).unwrap();
+        system.adjust(Adjustment::Volume(0.5)).unwrap();
+        system
+            .adjust(Adjustment::InertGas(InertGas::AtConstantVolume(1.0)))
+            .unwrap();
+        let end = system.clone();
+        assert_eq!(system.history().count(), 3);
+
+        while system.undo().is_some() {}
+        assert_eq!(system.snapshot(), start.snapshot());
+        assert!(!system.can_undo());
+
+        while system.redo().is_some() {}
+        assert_eq!(system, end);
+    }
+
+    #[test]
+    fn adjusting_clears_redo() {
+        let mut system = dinitrogen_tetroxide();
+        system.adjust(Adjustment::Volume(0.5)).unwrap();
+        assert_eq!(system.undo(), Some(OwnedAdjustment::Volume(0.5)));
+        assert!(system.can_redo());
+
+        system.adjust(Adjustment::Catalyst).unwrap();
+        assert!(!system.can_redo());
+        assert_eq!(system.redo(), None);
+    }
+
+    #[test]
+    fn failed_adjustment_not_recorded() {
+        let mut system = dinitrogen_tetroxide();
+        assert!(system.adjust(Adjustment::Volume(-1.0)).is_err());
+        assert!(!system.can_undo());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut system = dinitrogen_tetroxide();
+        for i in 0..MAX_HISTORY + 5 {
+            system.adjust(Adjustment::Volume(1.0 + i as f32)).unwrap();
+        }
+        assert_eq!(system.history().count(), MAX_HISTORY);
+        assert_eq!(system.history().next(), Some(&OwnedAdjustment::Volume(6.0)));
+    }
+
+    #[test]
+    fn timeline_follows_undo() {
+        let mut system = dinitrogen_tetroxide();
+        system.adjust(Adjustment::Temperature(100.0)).unwrap();
+        let hot = system.clone();
+        system.adjust(Adjustment::Volume(0.5)).unwrap();
+        system.undo();
+
+        let timeline = system.timeline();
+        assert_eq!(
+            timeline
+                .iter()
+                .map(|s| s.adjustment.clone())
+                .collect::<Vec<_>>(),
+            vec![None, Some(OwnedAdjustment::Temperature(100.0))]
+        );
+        assert_eq!(timeline[0].k, 0.25);
+        assert_eq!(timeline[0].temperature, None);
+        assert_eq!(timeline[1].k, hot.get_k_expr());
+        assert_eq!(timeline[1].temperature, Some(100.0));
+    }
+
+    #[test]
+    fn checkpoint_and_simulate() {
+        let mut system = dinitrogen_tetroxide();
+        let checkpoint = system.checkpoint();
+
+        let adjust = OwnedAdjustment::Temperature(100.0);
+        let simulated = system.simulate(&adjust).unwrap();
+        assert_eq!(system, *checkpoint.system());
+
+        system.adjust_owned(&adjust).unwrap();
+        assert_eq!(system, simulated);
+
+        system.restore(&checkpoint);
+        assert_eq!(system, *checkpoint.system());
+    }
+}
@@ -2,18 +2,58 @@
 //!
 //! Types to simulate Le Chatelier's Principle
 
-use chem_eq::{error::ConcentrationNameError, Equation, ReactionQuotient};
+use chem_eq::{error::ConcentrationNameError, Compound, Equation, ReactionQuotient, State};
 use float_cmp::approx_eq;
 use thiserror::Error;
 
+use history::History;
+
+pub use explain::{Change, Explanation, Rule};
+pub use history::{Checkpoint, Step, MAX_HISTORY};
+pub use network::{Network, NetworkError};
+pub use owned::{OwnedAdjustment, Target};
+pub use trajectory::{Recorder, Series, Trajectory, MAX_SAMPLES, OBSERVED_SAMPLES};
+
+mod explain;
+mod history;
+mod network;
+mod owned;
+pub mod solubility;
+mod trajectory;
+
+#[cfg(test)]
+mod fixtures;
+
+/// The ideal gas constant in L·atm/(mol·K)
+pub const GAS_CONSTANT: f32 = 0.082_057;
+
+/// How many times faster a catalysed system approaches equilibrium
+pub const CATALYST_RATE: f32 = 10.0;
+
+/// The ideal gas constant in kJ/(mol·K), used with ΔH
+const GAS_CONSTANT_KJ: f32 = 8.314_462e-3;
+
+/// Give up reacting after this many steps
+const MAX_ITERATIONS: usize = 1_000_000;
+
 /// A simulation of Le Chatelier's Principle.
 ///
 /// It will produce how the system should react to certain changes
 #[derive(Debug, Default, Clone, PartialEq)]
-#[cfg_attr(featue = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct System {
     eq: Equation,
     k_expr: f32,
+    /// Concentration of inert gas in the container in M (mol/L)
+    inert: f32,
+    /// Whether a catalyst has been added
+    catalysed: bool,
+    /// Adjustments that can be undone or redone
+    history: History,
+    /// How to record reacting, if at all
+    recorder: Option<Recorder>,
+    /// The last recording
+    trajectory: Option<Trajectory>,
 }
 
 impl System {
@@ -25,50 +65,173 @@ impl System {
         let k_expr = eq
             .equilibrium_constant()
             .ok_or(SystemError::ConcentrationIsZero)?;
-        Ok(Self { eq, k_expr })
+        Ok(Self {
+            eq,
+            k_expr,
+            ..Default::default()
+        })
     }
 
-    /// Take a transformation to the reaction, return time to reach new values
+    /// Construct a [`System`] with a known k expression. The equation doesn't have to be at
+    /// equilibrium, it will react until it matches `k_expr`.
+    pub fn with_k_expr(eq: Equation, k_expr: f32) -> Result<Self, SystemError> {
+        if !matches!(eq.direction(), chem_eq::Direction::Reversible) {
+            return Err(SystemError::NotReversible);
+        }
+        if k_expr <= 0.0 || !k_expr.is_finite() {
+            return Err(SystemError::InvalidK);
+        }
+        let mut system = Self {
+            eq,
+            k_expr,
+            ..Default::default()
+        };
+        system.react_to_match_k()?;
+        Ok(system)
+    }
+
+    /// Take a transformation to the reaction, return time to reach new values. The system is
+    /// left unchanged on an error.
     pub fn adjust(&mut self, adjust: Adjustment) -> Result<(), AdjustError> {
+        let mut sys = self.clone();
+        sys.change(adjust)?;
+
+        // shift until the k-expr matches again
+        sys.react_to_match_k()?;
+        sys.history.push(adjust.into(), self.snapshot());
+        *self = sys;
+
+        Ok(())
+    }
+
+    /// Make a change to the system without reacting
+    fn change(&mut self, adjust: Adjustment) -> Result<(), AdjustError> {
         match adjust {
-            Adjustment::Temperature(_tmp) => {
-                // change time-per-reaction
-                // - increase = lower time-per-reaction
-                // - decrease = higher time-per-reaction
-                //
-                // a change of around 10 degrees Celsius doubles reactionr rates
-                todo!("adjust temperature")
+            Adjustment::Temperature(tmp) => {
+                // the k expression changes with temperature
+                self.change_temperature(tmp)
             }
-            Adjustment::Volume(_vol) => {
-                // if mol ratio is 1:1 or equivalent do nothing otherwise have
-                // to figure out how much to shift to either side
-                todo!("adjust volume")
+            Adjustment::Volume(vol) => {
+                // gases are compressed or expanded, changing their concentration
+                self.change_volume(vol)
             }
             Adjustment::Concentration(cmp, conc) => {
                 if conc == 0.0 {
                     return Err(AdjustError::ZeroConcentration);
                 }
                 // update the one concentration
-                self.eq.set_concentration_by_name(cmp, conc)?;
+                Ok(self.eq.set_concentration_by_name(cmp, conc)?)
+            }
+            Adjustment::Catalyst => {
+                // a catalyst speeds up both directions equally, so equilibrium doesn't move
+                self.catalysed = true;
+                Ok(())
+            }
+            Adjustment::InertGas(gas) => self.add_inert_gas(gas),
+        }
+    }
 
-                // shift until the k-expr matches again
-                self.react_to_match_k();
+    /// Set the temperature in degrees Celsius, changing the k expression using the van 't Hoff
+    /// equation: `ln(K2/K1) = -ΔH/R * (1/T2 - 1/T1)`. Uses 25°C if the temperature wasn't set.
+    fn change_temperature(&mut self, temperature: f32) -> Result<(), AdjustError> {
+        if !temperature.is_finite() || temperature <= -273.15 {
+            return Err(AdjustError::InvalidTemperature);
+        }
+        let current = self.eq.temperature().unwrap_or(25.0) + 273.15;
+        let new = temperature + 273.15;
 
-                Ok(())
+        let exponent = -self.eq.delta_h() / GAS_CONSTANT_KJ * (new.recip() - current.recip());
+        let k_expr = self.k_expr * exponent.exp();
+        if k_expr <= 0.0 || !k_expr.is_finite() {
+            return Err(SystemError::InvalidK.into());
+        }
+        self.k_expr = k_expr;
+        self.eq.set_temperature(temperature);
+
+        Ok(())
+    }
+
+    /// Set the volume in litres, compressing or expanding every gas. Uses 1L if the volume
+    /// wasn't set.
+    fn change_volume(&mut self, volume: f32) -> Result<(), AdjustError> {
+        if volume <= 0.0 || !volume.is_finite() {
+            return Err(AdjustError::InvalidVolume);
+        }
+        let factor = self.eq.volume().unwrap_or(1.0) / volume;
+        for cmp in self.eq.iter_compounds_mut().filter(|c| is_gas(c)) {
+            cmp.concentration *= factor;
+        }
+        self.inert *= factor;
+        self.eq.set_volume(volume);
+
+        Ok(())
+    }
+
+    /// Add inert gas to the container. At constant volume partial pressures don't change, but at
+    /// constant pressure the container expands, diluting every gas.
+    fn add_inert_gas(&mut self, gas: InertGas) -> Result<(), AdjustError> {
+        let (InertGas::AtConstantVolume(amount) | InertGas::AtConstantPressure(amount)) = gas;
+        if amount < 0.0 || !amount.is_finite() {
+            return Err(AdjustError::InvalidInertGas);
+        }
+
+        let gases = self
+            .eq
+            .iter_compounds()
+            .filter(|c| is_gas(c))
+            .map(|c| c.concentration)
+            .sum::<f32>();
+        let total = gases + self.inert;
+
+        match gas {
+            InertGas::AtConstantPressure(_) if total > 0.0 => {
+                // volume grows with the total moles of gas
+                let factor = (total + amount) / total;
+                for cmp in self.eq.iter_compounds_mut().filter(|c| is_gas(c)) {
+                    cmp.concentration /= factor;
+                }
+                self.inert = (self.inert + amount) / factor;
+                if let Some(volume) = self.eq.volume() {
+                    self.eq.set_volume(volume * factor);
+                }
             }
+            _ => self.inert += amount,
+        }
+
+        Ok(())
+    }
+
+    /// Continue to react until the Kc matches Qc, recording the approach if there's a recorder
+    fn react_to_match_k(&mut self) -> Result<(), SystemError> {
+        let before = self.eq.get_concentrations();
+        self.react_steps()?;
+        if let Some(recorder) = self.recorder {
+            self.trajectory = Some(Trajectory::new(
+                &self.eq,
+                recorder.samples(),
+                &before,
+                self.rate(),
+            ));
         }
+
+        Ok(())
     }
 
-    /// Continue to react until the Kc matches Qc
-    fn react_to_match_k(&mut self) {
+    /// React until the Kc matches Qc
+    fn react_steps(&mut self) -> Result<(), SystemError> {
+        // solids and liquids can run out before reaching equilibrium
+        if self.exhaust_pure_compounds()? {
+            return Ok(());
+        }
+
         // number to modify concentrations by
         let mut addend = 1.0;
-        let mut prev = self.direction_to_favour();
+        let mut prev = self.direction_to_favour()?;
         let mut res = Ok(());
 
-        loop {
-            let dir = match self.direction_to_favour() {
-                Direction::None => break,
+        for _ in 0..MAX_ITERATIONS {
+            let dir = match self.direction_to_favour()? {
+                Direction::None => return Ok(()),
                 d => d,
             };
 
@@ -77,8 +240,62 @@ impl System {
                 prev = dir;
                 addend /= 2.0;
             }
+            if addend == 0.0 {
+                return Err(SystemError::DidNotConverge);
+            }
 
+            let before = self.eq.reaction_quotient();
             res = self.react_addend(addend, dir);
+
+            // the addend is too small to change anything, this is as close as we can get
+            if res.is_ok() && self.eq.reaction_quotient() == before {
+                return Ok(());
+            }
+        }
+
+        Err(SystemError::DidNotConverge)
+    }
+
+    /// Solids and liquids don't appear in the reaction quotient, so consuming them doesn't
+    /// bring the system closer to equilibrium. If using all of one up still wouldn't reach
+    /// equilibrium, react until it's gone and return `true`.
+    fn exhaust_pure_compounds(&mut self) -> Result<bool, SystemError> {
+        let dir = self.direction_to_favour()?;
+        let consumed = match dir {
+            Direction::Forward => self.eq.left(),
+            Direction::Reverse => self.eq.right(),
+            Direction::None => return Ok(false),
+        };
+        let Some(extent) = consumed
+            .iter()
+            .filter(|c| matches!(c.state, Some(State::Solid | State::Liquid)))
+            .map(|c| c.concentration / c.coefficient as f32)
+            .min_by(f32::total_cmp)
+        else {
+            return Ok(false);
+        };
+
+        let mut exhausted = self.clone();
+        let forward = dir == Direction::Forward;
+        for cmp in exhausted.eq.left_mut() {
+            let change = if forward { -extent } else { extent };
+            cmp.concentration = change
+                .mul_add(cmp.coefficient as f32, cmp.concentration)
+                .max(0.0);
+        }
+        for cmp in exhausted.eq.right_mut() {
+            let change = if forward { extent } else { -extent };
+            cmp.concentration = change
+                .mul_add(cmp.coefficient as f32, cmp.concentration)
+                .max(0.0);
+        }
+
+        // a side could run out entirely, which isn't an error here
+        if exhausted.direction_to_favour() == Ok(dir) {
+            *self = exhausted;
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
@@ -125,44 +342,35 @@ impl System {
     }
 
     /// Which direction the equation should go, based on k_expr and the system's goal k_expr
-    fn direction_to_favour(&self) -> Direction {
-        match self.eq.reaction_quotient() {
-            ReactionQuotient::BothSidesZero => {
-                panic!("both sides of equation have concentration of 0")
-            }
+    fn direction_to_favour(&self) -> Result<Direction, SystemError> {
+        let dir = match self.eq.reaction_quotient() {
+            ReactionQuotient::BothSidesZero => return Err(SystemError::BothSidesZero),
             ReactionQuotient::LeftZero => Direction::Reverse,
             ReactionQuotient::RightZero => Direction::Forward,
-            ReactionQuotient::Val(f) if approx_eq!(f32, self.k_expr, f) => Direction::None,
+            ReactionQuotient::Val(f) if !f.is_finite() => return Err(SystemError::InvalidQ),
+            ReactionQuotient::Val(f) if approx_eq!(f32, self.k_expr, f, ulps = 4) => {
+                Direction::None
+            }
             ReactionQuotient::Val(f) if self.k_expr > f => Direction::Forward,
             ReactionQuotient::Val(_) => Direction::Reverse,
-        }
+        };
+
+        Ok(dir)
     }
 
-    /// Returns which the direction a given adjustment will cause the equilibrium to shift
+    /// Returns which the direction a given adjustment will cause the equilibrium to shift. Use
+    /// [`System::explain`] to also get why.
     pub fn get_shift_direction(&self, adjust: Adjustment) -> Result<Direction, AdjustError> {
-        match adjust {
-            Adjustment::Concentration(cmp, conc) => {
-                if conc == 0.0 {
-                    return Err(AdjustError::ZeroConcentration);
-                }
-
-                // update the one concentration
-                let mut sys = self.clone();
-                sys.eq.set_concentration_by_name(cmp, conc)?;
-
-                // get direction and return
-                let res = sys.direction_to_favour();
-
-                Ok(res)
-            }
-            Adjustment::Temperature(_) => todo!("shift temperature"),
-            Adjustment::Volume(_) => todo!("shift volume"),
-        }
+        self.explain(adjust).map(|e| e.direction)
     }
 
-    /// Move system to match k expression
-    pub fn update(&mut self) {
-        self.react_to_match_k();
+    /// Move system to match k expression. The system is left unchanged on an error.
+    pub fn update(&mut self) -> Result<(), SystemError> {
+        let mut sys = self.clone();
+        sys.react_to_match_k()?;
+        *self = sys;
+
+        Ok(())
     }
 
     /// Set the k expression
@@ -170,14 +378,53 @@ impl System {
         self.k_expr
     }
 
-    /// Multiply the k expression
-    pub fn mul_k_expr(&mut self, v: f32) {
-        self.k_expr *= v;
+    /// Multiply the k expression. The result must be positive and finite.
+    pub fn mul_k_expr(&mut self, v: f32) -> Result<(), SystemError> {
+        self.set_k_expr(self.k_expr * v)
     }
 
-    /// Set the k expression
-    pub fn set_k_expr(&mut self, k: f32) {
+    /// Set the k expression. It must be positive and finite.
+    pub fn set_k_expr(&mut self, k: f32) -> Result<(), SystemError> {
+        if k <= 0.0 || !k.is_finite() {
+            return Err(SystemError::InvalidK);
+        }
         self.k_expr = k;
+
+        Ok(())
+    }
+
+    /// Get the concentration of inert gas in M (mol/L)
+    pub const fn inert_gas(&self) -> f32 {
+        self.inert
+    }
+
+    /// Get the total pressure of every gas, including inert gas, in atm. Uses the temperature
+    /// of the equation or 25°C if it isn't set.
+    pub fn total_pressure(&self) -> f32 {
+        let gases = self
+            .eq
+            .iter_compounds()
+            .filter(|c| is_gas(c))
+            .map(|c| c.concentration)
+            .sum::<f32>();
+        let temperature = self.eq.temperature().unwrap_or(25.0) + 273.15;
+
+        // PV = nRT, P = cRT
+        (gases + self.inert) * GAS_CONSTANT * temperature
+    }
+
+    /// Check whether a catalyst has been added
+    pub const fn is_catalysed(&self) -> bool {
+        self.catalysed
+    }
+
+    /// How fast the system approaches equilibrium compared to without a catalyst
+    pub const fn rate(&self) -> f32 {
+        if self.catalysed {
+            CATALYST_RATE
+        } else {
+            1.0
+        }
     }
 
     /// Get the internal equation
@@ -191,6 +438,11 @@ impl System {
     }
 }
 
+/// Compounds without a state are treated as gases
+fn is_gas(cmp: &Compound) -> bool {
+    matches!(cmp.state, Some(State::Gas) | None)
+}
+
 /// An change to a [`System`]
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -201,6 +453,23 @@ pub enum Adjustment<'a> {
     Volume(f32),
     /// Change in concentration, passing the name and new value
     Concentration(&'a str, f32),
+    /// Add a catalyst, which speeds up reaching equilibrium without shifting it
+    Catalyst,
+    /// Add an inert gas that doesn't take part in the reaction
+    InertGas(InertGas),
+}
+
+/// How an inert gas is added to a [`System`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum InertGas {
+    /// Added to a rigid container, passing the amount in M (mol/L). The total pressure rises,
+    /// but partial pressures don't change, so there's no shift.
+    AtConstantVolume(f32),
+    /// Added while the container expands to keep the pressure the same, passing the amount in
+    /// M (mol/L) of the original volume. The partial pressures fall, shifting towards the side
+    /// with more moles of gas.
+    AtConstantPressure(f32),
 }
 
 /// The direction of an equilibrium to shift
@@ -225,6 +494,21 @@ pub enum SystemError {
     NotReversible,
     #[error("a concentration is zero, the system isn't at equilibrium")]
     ConcentrationIsZero,
+    /// Both sides of the equation have no concentration, so it can't react
+    #[error("both sides of equation have concentration of 0")]
+    BothSidesZero,
+    /// The k expression was zero, negative or not finite
+    #[error("k expression must be a positive number")]
+    InvalidK,
+    /// The reaction quotient overflowed
+    #[error("reaction quotient is too large")]
+    InvalidQ,
+    /// The system couldn't reach equilibrium
+    #[error("system didn't reach equilibrium")]
+    DidNotConverge,
+    /// Reacting was stopped by the observer of [`System::adjust_observed`]
+    #[error("reacting was cancelled")]
+    Cancelled,
 }
 
 /// An error on using [`System`]
@@ -236,6 +520,16 @@ pub enum AdjustError {
     CompoundNotFound(#[from] ConcentrationNameError),
     #[error("tried to set concentration to 0M")]
     ZeroConcentration,
+    #[error("amount of inert gas must be positive")]
+    InvalidInertGas,
+    #[error("volume must be positive")]
+    InvalidVolume,
+    #[error("temperature must be above absolute zero")]
+    InvalidTemperature,
+    #[error("{0}")]
+    System(#[from] SystemError),
+    #[error("a batch of adjustments can't be used here")]
+    Batch,
 }
 
 /// The addend given was too big
@@ -246,6 +540,7 @@ struct AddendTooBig;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::dinitrogen_tetroxide;
     macro_rules! system_eq {
         ($eq:literal) => {
             System::new(Equation::new($eq).unwrap())
@@ -305,4 +600,230 @@ mod tests {
             vec![2.0399098, 0.98004514, 1.5301354]
         );
     }
+
+    #[test]
+    fn catalyst_doesnt_shift() {
+        let mut system = dinitrogen_tetroxide();
+
+        assert_eq!(
+            system.get_shift_direction(Adjustment::Catalyst),
+            Ok(Direction::None)
+        );
+        system.adjust(Adjustment::Catalyst).unwrap();
+        assert_eq!(system.equation().get_concentrations(), vec![1.0, 0.5]);
+        assert!(system.is_catalysed());
+        assert!(system.rate() > 1.0);
+    }
+
+    #[test]
+    fn inert_gas_constant_volume() {
+        let mut system = dinitrogen_tetroxide();
+        let pressure = system.total_pressure();
+
+        let gas = Adjustment::InertGas(InertGas::AtConstantVolume(1.5));
+        assert_eq!(system.get_shift_direction(gas), Ok(Direction::None));
+        system.adjust(gas).unwrap();
+
+        assert_eq!(system.equation().get_concentrations(), vec![1.0, 0.5]);
+        assert_eq!(system.inert_gas(), 1.5);
+        assert!(approx_eq!(
+            f32,
+            system.total_pressure(),
+            pressure * 2.0,
+            ulps = 4
+        ));
+    }
+
+    #[test]
+    fn inert_gas_constant_pressure() {
+        let mut system = dinitrogen_tetroxide();
+        let pressure = system.total_pressure();
+
+        // more moles of gas on the right
+        let gas = Adjustment::InertGas(InertGas::AtConstantPressure(1.5));
+        assert_eq!(system.get_shift_direction(gas), Ok(Direction::Forward));
+        system.adjust(gas).unwrap();
+
+        assert_eq!(system.equation().volume(), Some(2.0));
+        assert!(approx_eq!(
+            f32,
+            system.equation().equilibrium_constant().unwrap(),
+            0.25,
+            ulps = 4
+        ));
+        // pressure only changes as the reaction makes more gas
+        assert!(system.total_pressure() > pressure);
+    }
+
+    #[test]
+    fn inert_gas_negative() {
+        let mut system = dinitrogen_tetroxide();
+
+        assert_eq!(
+            system.adjust(Adjustment::InertGas(InertGas::AtConstantVolume(-1.0))),
+            Err(AdjustError::InvalidInertGas)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut system = dinitrogen_tetroxide();
+        system.adjust(Adjustment::Catalyst).unwrap();
+        system
+            .adjust(Adjustment::InertGas(InertGas::AtConstantVolume(0.3)))
+            .unwrap();
+
+        let json = serde_json::to_string(&system).unwrap();
+        let de = serde_json::from_str::<System>(&json).unwrap();
+        assert_eq!(de, system);
+        assert!(de.is_catalysed());
+        assert_eq!(de.inert_gas(), 0.3);
+    }
+
+    #[test]
+    fn both_sides_zero() {
+        let eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+        assert_eq!(
+            System::with_k_expr(eq, 0.25),
+            Err(SystemError::BothSidesZero)
+        );
+    }
+
+    #[test]
+    fn invalid_k_expr() {
+        let mut system = dinitrogen_tetroxide();
+
+        assert_eq!(system.set_k_expr(0.0), Err(SystemError::InvalidK));
+        assert_eq!(system.set_k_expr(f32::NAN), Err(SystemError::InvalidK));
+        assert_eq!(system.mul_k_expr(f32::INFINITY), Err(SystemError::InvalidK));
+        assert_eq!(system.get_k_expr(), 0.25);
+    }
+
+    #[test]
+    fn adjust_volume() {
+        let mut system = dinitrogen_tetroxide();
+
+        // compressing favours fewer moles of gas
+        system.adjust(Adjustment::Volume(0.5)).unwrap();
+        let cnc = system.equation().get_concentrations();
+        assert!(cnc[0] > 2.0 && cnc[1] < 1.0);
+        assert_eq!(system.equation().volume(), Some(0.5));
+        assert!(approx_eq!(
+            f32,
+            system.equation().equilibrium_constant().unwrap(),
+            0.25,
+            ulps = 4
+        ));
+
+        assert_eq!(
+            system.adjust(Adjustment::Volume(0.0)),
+            Err(AdjustError::InvalidVolume)
+        );
+    }
+
+    #[test]
+    fn adjust_temperature() {
+        let mut system = dinitrogen_tetroxide();
+
+        // heating an endothermic reaction increases k and shifts forward
+        system.adjust(Adjustment::Temperature(100.0)).unwrap();
+        assert!(system.get_k_expr() > 0.25);
+        assert_eq!(system.equation().temperature(), Some(100.0));
+        let cnc = system.equation().get_concentrations();
+        assert!(cnc[0] < 1.0 && cnc[1] > 0.5);
+
+        assert_eq!(
+            system.adjust(Adjustment::Temperature(-300.0)),
+            Err(AdjustError::InvalidTemperature)
+        );
+    }
+
+    mod props {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        const NAMES: [&str; 2] = ["N2O4(g)", "2NO2(g)"];
+
+        #[derive(Debug, Clone, Copy)]
+        enum Op {
+            Adjust(usize, f32),
+            Temperature(f32),
+            Volume(f32),
+            InertGas(bool, f32),
+            Catalyst,
+            SetK(f32),
+            MulK(f32),
+        }
+
+        fn any_f32() -> impl Strategy<Value = f32> {
+            prop_oneof![
+                -10.0_f32..10.0,
+                Just(0.0),
+                Just(f32::NAN),
+                Just(f32::INFINITY),
+                Just(f32::NEG_INFINITY),
+                Just(f32::MAX),
+                Just(f32::MIN_POSITIVE),
+            ]
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            prop_oneof![
+                (0..NAMES.len(), any_f32()).prop_map(|(i, c)| Op::Adjust(i, c)),
+                any_f32().prop_map(Op::Temperature),
+                any_f32().prop_map(Op::Volume),
+                (any::<bool>(), any_f32()).prop_map(|(p, c)| Op::InertGas(p, c)),
+                Just(Op::Catalyst),
+                any_f32().prop_map(Op::SetK),
+                any_f32().prop_map(Op::MulK),
+            ]
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn never_panics(ops in prop::collection::vec(op(), 1..8)) {
+                let mut system = dinitrogen_tetroxide();
+
+                for op in ops {
+                    let before = system.clone();
+                    let res = match op {
+                        Op::Adjust(i, c) => system
+                            .adjust(Adjustment::Concentration(NAMES[i], c))
+                            .map_err(|_| ()),
+                        Op::Temperature(t) => {
+                            system.adjust(Adjustment::Temperature(t)).map_err(|_| ())
+                        }
+                        Op::Volume(v) => system.adjust(Adjustment::Volume(v)).map_err(|_| ()),
+                        Op::InertGas(pressure, c) => {
+                            let gas = if pressure {
+                                InertGas::AtConstantPressure(c)
+                            } else {
+                                InertGas::AtConstantVolume(c)
+                            };
+                            let _ = system.get_shift_direction(Adjustment::InertGas(gas));
+                            system.adjust(Adjustment::InertGas(gas)).map_err(|_| ())
+                        }
+                        Op::Catalyst => system.adjust(Adjustment::Catalyst).map_err(|_| ()),
+                        Op::SetK(k) => system.set_k_expr(k).map_err(|_| ()),
+                        Op::MulK(k) => system.mul_k_expr(k).map_err(|_| ()),
+                    };
+
+                    match res {
+                        // a failed change leaves the system alone
+                        Err(()) => prop_assert_eq!(&system, &before),
+                        Ok(()) => {
+                            prop_assert!(system.get_k_expr() > 0.0);
+                            prop_assert!(system.get_k_expr().is_finite());
+                        }
+                    }
+                    let _ = system.update();
+                    let _ = system.total_pressure();
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,396 @@
+//! Implementation of [`Network`]
+
+use chem_eq::{error::ConcentrationNameError, Equation, ReactionQuotient};
+use thiserror::Error;
+
+use crate::{AdjustError, Adjustment, Direction, System, SystemError};
+
+/// How many times every reaction is brought to equilibrium before giving up
+const MAX_SWEEPS: usize = 10_000;
+
+/// Several reversible reactions sharing species, each with its own k expression.
+///
+/// Species are identified by their name in the equation without the coefficient, so the
+/// `H+(aq)` of `H2CO3(aq) <-> H+(aq) + HCO3-(aq)` and of `HCO3-(aq) <-> H+(aq) + CO3^2-(aq)`
+/// is the same species with one concentration.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::{Adjustment, Direction, Network};
+///
+/// let mut network = Network::new();
+/// let mut first = Equation::new("H2CO3(aq) <-> H+(aq) + HCO3-(aq)").unwrap();
+/// first.set_concentrations(&[0.03, 0.0, 0.0]).unwrap();
+/// network.add_reaction_with_k(first, 4.3e-7).unwrap();
+/// network
+///     .add_reaction_with_k(Equation::new("HCO3-(aq) <-> H+(aq) + CO3^2-(aq)").unwrap(), 4.8e-11)
+///     .unwrap();
+/// network.solve().unwrap();
+///
+/// // adding acid pushes both reactions back
+/// let shifts = network.adjust(Adjustment::Concentration("H+(aq)", 0.01)).unwrap();
+/// assert_eq!(shifts, vec![Direction::Reverse, Direction::Reverse]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Network {
+    species: Vec<(String, f32)>,
+    reactions: Vec<Reaction>,
+}
+
+/// A reaction in a [`Network`]
+#[derive(Debug, Clone, PartialEq)]
+struct Reaction {
+    system: System,
+    /// Index into the species table for each compound of the equation
+    species: Vec<usize>,
+}
+
+impl Network {
+    /// Construct an empty [`Network`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reaction at equilibrium, taking its k expression from its concentrations. Species
+    /// already in the network keep their concentration. Returns the index of the reaction.
+    pub fn add_reaction(&mut self, eq: Equation) -> Result<usize, NetworkError> {
+        let system = System::new(eq)?;
+        Ok(self.push(system))
+    }
+
+    /// Add a reaction with a known k expression, it doesn't have to be at equilibrium. Species
+    /// already in the network keep their concentration. Returns the index of the reaction.
+    pub fn add_reaction_with_k(
+        &mut self,
+        eq: Equation,
+        k_expr: f32,
+    ) -> Result<usize, NetworkError> {
+        if !matches!(eq.direction(), chem_eq::Direction::Reversible) {
+            return Err(SystemError::NotReversible.into());
+        }
+        if k_expr <= 0.0 || !k_expr.is_finite() {
+            return Err(SystemError::InvalidK.into());
+        }
+        Ok(self.push(System {
+            eq,
+            k_expr,
+            ..Default::default()
+        }))
+    }
+
+    fn push(&mut self, mut system: System) -> usize {
+        let mut species = Vec::with_capacity(system.eq.num_compounds());
+        for (name, cnc) in system.eq.name_and_concentration_mut() {
+            let name = species_name(&name);
+            let idx = match self.species.iter().position(|(n, _)| n == name) {
+                Some(idx) => {
+                    *cnc = self.species[idx].1;
+                    idx
+                }
+                None => {
+                    self.species.push((name.to_string(), *cnc));
+                    self.species.len() - 1
+                }
+            };
+            species.push(idx);
+        }
+        self.reactions.push(Reaction { system, species });
+
+        self.reactions.len() - 1
+    }
+
+    /// Bring every reaction to equilibrium at once. Returns the net direction each reaction
+    /// shifted in, in the order they were added.
+    pub fn solve(&mut self) -> Result<Vec<Direction>, NetworkError> {
+        let mut extents = vec![0.0_f32; self.reactions.len()];
+
+        for _ in 0..MAX_SWEEPS {
+            let mut settled = true;
+            for (reaction, extent) in self.reactions.iter_mut().zip(extents.iter_mut()) {
+                // pick up changes other reactions made to shared species
+                for (cnc, &idx) in reaction
+                    .system
+                    .eq
+                    .concentrations_mut()
+                    .zip(reaction.species.iter())
+                {
+                    *cnc = self.species[idx].1;
+                }
+
+                // nothing can react if both sides are empty
+                if matches!(
+                    reaction.system.eq.reaction_quotient(),
+                    ReactionQuotient::BothSidesZero
+                ) || reaction.system.direction_to_favour()? == Direction::None
+                {
+                    continue;
+                }
+
+                let before = reaction.system.eq.get_concentrations();
+                reaction.system.react_to_match_k()?;
+                let after = reaction.system.eq.get_concentrations();
+                if before == after {
+                    continue;
+                }
+                settled = false;
+
+                // the first compound tells how far the reaction went
+                let first = &reaction.system.eq.iter_compounds().next().unwrap();
+                *extent -= (after[0] - before[0]) / first.coefficient as f32;
+
+                for (&cnc, &idx) in after.iter().zip(reaction.species.iter()) {
+                    self.species[idx].1 = cnc;
+                }
+            }
+
+            if settled {
+                return Ok(extents
+                    .into_iter()
+                    .map(|e| {
+                        if e > 0.0 {
+                            Direction::Forward
+                        } else if e < 0.0 {
+                            Direction::Reverse
+                        } else {
+                            Direction::None
+                        }
+                    })
+                    .collect());
+            }
+        }
+
+        Err(NetworkError::DidNotConverge)
+    }
+
+    /// Adjust a species and bring every reaction back to equilibrium. Returns the net direction
+    /// each reaction shifted in. The network isn't changed if there's an error.
+    pub fn adjust(&mut self, adjust: Adjustment) -> Result<Vec<Direction>, NetworkError> {
+        match adjust {
+            Adjustment::Concentration(name, cnc) => {
+                if cnc == 0.0 {
+                    return Err(AdjustError::ZeroConcentration.into());
+                }
+                // a network that fails to solve is left as it was
+                let mut network = self.clone();
+                network.set_concentration(name, cnc)?;
+                let shifts = network.solve()?;
+                *self = network;
+
+                Ok(shifts)
+            }
+            // a catalyst never moves equilibrium
+            Adjustment::Catalyst => Ok(vec![Direction::None; self.reactions.len()]),
+            Adjustment::Temperature(_) | Adjustment::Volume(_) | Adjustment::InertGas(_) => {
+                Err(NetworkError::UnsupportedAdjustment)
+            }
+        }
+    }
+
+    /// Get the concentration of a species by name, without a coefficient
+    pub fn get_concentration(&self, name: &str) -> Result<f32, ConcentrationNameError> {
+        self.species
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, c)| *c)
+            .ok_or(ConcentrationNameError::NotFound)
+    }
+
+    /// Set the concentration of a species by name, without a coefficient. This doesn't
+    /// react the network, use [`Network::solve`] afterwards.
+    pub fn set_concentration(
+        &mut self,
+        name: &str,
+        concentration: f32,
+    ) -> Result<(), ConcentrationNameError> {
+        if concentration.is_nan() {
+            return Err(ConcentrationNameError::NAN);
+        }
+        if concentration.is_infinite() {
+            return Err(ConcentrationNameError::Infinite);
+        }
+        if concentration < 0.0 {
+            return Err(ConcentrationNameError::Negative);
+        }
+        let (_, cnc) = self
+            .species
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .ok_or(ConcentrationNameError::NotFound)?;
+        *cnc = concentration;
+
+        Ok(())
+    }
+
+    /// Get an iterator over the name and concentration of each species
+    pub fn species(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.species.iter().map(|(n, c)| (n.as_str(), *c))
+    }
+
+    /// Get the number of reactions
+    pub fn num_reactions(&self) -> usize {
+        self.reactions.len()
+    }
+
+    /// Get the equation of a reaction, with the concentrations from the last time it reacted
+    pub fn equation(&self, idx: usize) -> Option<&Equation> {
+        self.reactions.get(idx).map(|r| r.system.equation())
+    }
+
+    /// Get the k expression of a reaction
+    pub fn get_k_expr(&self, idx: usize) -> Option<f32> {
+        self.reactions.get(idx).map(|r| r.system.get_k_expr())
+    }
+
+    /// Set the k expression of a reaction. This doesn't react the network, use
+    /// [`Network::solve`] afterwards.
+    pub fn set_k_expr(&mut self, idx: usize, k: f32) -> Result<(), NetworkError> {
+        self.reactions
+            .get_mut(idx)
+            .ok_or(NetworkError::ReactionNotFound)?
+            .system
+            .set_k_expr(k)?;
+        Ok(())
+    }
+}
+
+/// Strip the coefficient from the name of a compound
+fn species_name(name: &str) -> &str {
+    name.trim_start_matches(|c: char| c.is_ascii_digit())
+}
+
+/// An error on using [`Network`]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NetworkError {
+    /// A reaction couldn't be added
+    #[error("{0}")]
+    System(#[from] SystemError),
+    /// A species couldn't be adjusted
+    #[error("{0}")]
+    Adjust(#[from] AdjustError),
+    /// No reaction has the given index
+    #[error("reaction not found")]
+    ReactionNotFound,
+    /// The reactions kept shifting each other without settling
+    #[error("reactions didn't reach equilibrium together")]
+    DidNotConverge,
+    /// Only concentration and catalyst adjustments are supported
+    #[error("networks can only adjust concentration or add a catalyst")]
+    UnsupportedAdjustment,
+}
+
+impl From<ConcentrationNameError> for NetworkError {
+    fn from(e: ConcentrationNameError) -> Self {
+        Self::Adjust(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+
+    const KA1: f32 = 4.3e-7;
+    const KA2: f32 = 4.8e-11;
+
+    fn carbonic_acid() -> Network {
+        let mut network = Network::new();
+        let mut first = Equation::new("H2CO3(aq) <-> H+(aq) + HCO3-(aq)").unwrap();
+        first.set_concentrations(&[0.03, 0.0, 0.0]).unwrap();
+        network.add_reaction_with_k(first, KA1).unwrap();
+        network
+            .add_reaction_with_k(
+                Equation::new("HCO3-(aq) <-> H+(aq) + CO3^2-(aq)").unwrap(),
+                KA2,
+            )
+            .unwrap();
+        network
+    }
+
+    #[test]
+    fn shared_species() {
+        let network = carbonic_acid();
+        assert_eq!(
+            network.species().map(|(n, _)| n).collect::<Vec<_>>(),
+            vec!["H2CO3(aq)", "H+(aq)", "HCO3-(aq)", "CO3^2-(aq)"]
+        );
+    }
+
+    #[test]
+    fn solve_all_reactions() {
+        let mut network = carbonic_acid();
+        assert_eq!(
+            network.solve(),
+            Ok(vec![Direction::Forward, Direction::Forward])
+        );
+
+        for idx in 0..network.num_reactions() {
+            let q = network.equation(idx).unwrap().equilibrium_constant();
+            let k = network.get_k_expr(idx).unwrap();
+            assert!(approx_eq!(f32, q.unwrap(), k, epsilon = k * 1e-4));
+        }
+
+        // both reactions make H+, it's their combined result
+        let h = network.get_concentration("H+(aq)").unwrap();
+        let hco3 = network.get_concentration("HCO3-(aq)").unwrap();
+        let co3 = network.get_concentration("CO3^2-(aq)").unwrap();
+        assert!(approx_eq!(f32, h, hco3 + 2.0 * co3, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn adjustment_propagates() {
+        let mut network = carbonic_acid();
+        network.solve().unwrap();
+        let co3 = network.get_concentration("CO3^2-(aq)").unwrap();
+
+        // removing H+ drives both dissociations forward
+        let shifts = network
+            .adjust(Adjustment::Concentration("H+(aq)", 1e-6))
+            .unwrap();
+        assert_eq!(shifts, vec![Direction::Forward, Direction::Forward]);
+        assert!(network.get_concentration("CO3^2-(aq)").unwrap() > co3);
+    }
+
+    #[test]
+    fn unknown_species() {
+        let mut network = carbonic_acid();
+        assert_eq!(
+            network.adjust(Adjustment::Concentration("OH-(aq)", 1.0)),
+            Err(NetworkError::Adjust(AdjustError::CompoundNotFound(
+                ConcentrationNameError::NotFound
+            )))
+        );
+    }
+
+    #[test]
+    fn invalid_concentration() {
+        let mut network = carbonic_acid();
+        network.solve().unwrap();
+        let before = network.clone();
+
+        for (cnc, err) in [
+            (-1.0, ConcentrationNameError::Negative),
+            (f32::INFINITY, ConcentrationNameError::Infinite),
+            (f32::NAN, ConcentrationNameError::NAN),
+        ] {
+            assert_eq!(network.set_concentration("H+(aq)", cnc), Err(err));
+            assert_eq!(
+                network.adjust(Adjustment::Concentration("H+(aq)", cnc)),
+                Err(NetworkError::Adjust(AdjustError::CompoundNotFound(err)))
+            );
+        }
+        assert_eq!(network, before);
+    }
+
+    #[test]
+    fn not_reversible() {
+        let mut network = Network::new();
+        assert_eq!(
+            network.add_reaction_with_k(Equation::new("H2 + O2 -> H2O").unwrap(), 1.0),
+            Err(NetworkError::System(SystemError::NotReversible))
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Implementation of [`OwnedAdjustment`]
+
+use chem_eq::{error::ConcentrationNameError, Equation};
+
+use crate::{AdjustError, Adjustment, Direction, InertGas, System};
+
+/// An owned version of [`Adjustment`], that can be stored, sent across threads and
+/// deserialized from owned data. Compounds can be targeted by name or by index, and several
+/// changes can be applied at once with [`OwnedAdjustment::Batch`].
+///
+/// With the `serde` feature, a [`Target`] is either a string or a number, so
+/// `{"Concentration":["N2",2.0]}` and `{"Concentration":[0,2.0]}` are both valid.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::{Direction, OwnedAdjustment, System};
+///
+/// let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+/// eq.set_concentrations(&[1.0, 0.5]).unwrap();
+/// eq.set_volume(1.0);
+/// let mut system = System::new(eq).unwrap();
+///
+/// // add reactant and compress the container together
+/// let adjust = OwnedAdjustment::Batch(vec![
+///     OwnedAdjustment::Concentration(0.into(), 3.0),
+///     OwnedAdjustment::Volume(0.5),
+/// ]);
+/// assert_eq!(system.get_owned_shift_direction(&adjust), Ok(Direction::Forward));
+/// system.adjust_owned(&adjust).unwrap();
+/// assert_eq!(system.equation().volume(), Some(0.5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum OwnedAdjustment {
+    /// Change in the temperature, passing the new value
+    Temperature(f32),
+    /// Change in volume, passing the new value
+    Volume(f32),
+    /// Change in concentration, passing the compound and new value
+    Concentration(Target, f32),
+    /// Add a catalyst, which speeds up reaching equilibrium without shifting it
+    Catalyst,
+    /// Add an inert gas that doesn't take part in the reaction
+    InertGas(InertGas),
+    /// Several changes made at the same time, before the system reacts
+    Batch(Vec<OwnedAdjustment>),
+}
+
+/// A compound of an equation, used by [`OwnedAdjustment`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(untagged)
+)]
+pub enum Target {
+    /// The index of the compound, counting from the left
+    Index(usize),
+    /// The name of the compound, as in the equation
+    Name(String),
+}
+
+impl Target {
+    /// Get the name of the compound in an equation
+    pub fn name<'a>(&'a self, eq: &'a Equation) -> Result<&'a str, AdjustError> {
+        match self {
+            Self::Index(idx) => eq
+                .compound_names()
+                .nth(*idx)
+                .ok_or(AdjustError::CompoundNotFound(
+                    ConcentrationNameError::NotFound,
+                )),
+            Self::Name(name) => Ok(name),
+        }
+    }
+}
+
+impl OwnedAdjustment {
+    /// Borrow as an [`Adjustment`], using the equation to find compounds by index. A
+    /// [`OwnedAdjustment::Batch`] can't be represented as an [`Adjustment`].
+    pub fn as_adjustment<'a>(&'a self, eq: &'a Equation) -> Result<Adjustment<'a>, AdjustError> {
+        let adjust = match self {
+            Self::Temperature(t) => Adjustment::Temperature(*t),
+            Self::Volume(v) => Adjustment::Volume(*v),
+            Self::Concentration(target, cnc) => Adjustment::Concentration(target.name(eq)?, *cnc),
+            Self::Catalyst => Adjustment::Catalyst,
+            Self::InertGas(gas) => Adjustment::InertGas(*gas),
+            Self::Batch(_) => return Err(AdjustError::Batch),
+        };
+
+        Ok(adjust)
+    }
+}
+
+impl From<Adjustment<'_>> for OwnedAdjustment {
+    fn from(adjust: Adjustment<'_>) -> Self {
+        match adjust {
+            Adjustment::Temperature(t) => Self::Temperature(t),
+            Adjustment::Volume(v) => Self::Volume(v),
+            Adjustment::Concentration(name, cnc) => Self::Concentration(name.into(), cnc),
+            Adjustment::Catalyst => Self::Catalyst,
+            Adjustment::InertGas(gas) => Self::InertGas(gas),
+        }
+    }
+}
+
+impl From<Vec<OwnedAdjustment>> for OwnedAdjustment {
+    fn from(adjusts: Vec<OwnedAdjustment>) -> Self {
+        Self::Batch(adjusts)
+    }
+}
+
+impl FromIterator<OwnedAdjustment> for OwnedAdjustment {
+    fn from_iter<T: IntoIterator<Item = OwnedAdjustment>>(iter: T) -> Self {
+        Self::Batch(iter.into_iter().collect())
+    }
+}
+
+impl From<usize> for Target {
+    fn from(idx: usize) -> Self {
+        Self::Index(idx)
+    }
+}
+
+impl From<&str> for Target {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_string())
+    }
+}
+
+impl From<String> for Target {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl System {
+    /// Take an owned transformation to the reaction. Every change of a batch is made before
+    /// the system reacts. The system is left unchanged on an error.
+    pub fn adjust_owned(&mut self, adjust: &OwnedAdjustment) -> Result<(), AdjustError> {
+        let mut sys = self.clone();
+        sys.change_owned(adjust)?;
+        sys.react_to_match_k()?;
+        sys.history.push(adjust.clone(), self.snapshot());
+        *self = sys;
+
+        Ok(())
+    }
+
+    /// Get the direction the system would shift after an owned adjustment, without changing
+    /// the system
+    pub fn get_owned_shift_direction(
+        &self,
+        adjust: &OwnedAdjustment,
+    ) -> Result<Direction, AdjustError> {
+        let mut sys = self.clone();
+        sys.change_owned(adjust)?;
+
+        Ok(sys.direction_to_favour()?)
+    }
+
+    /// Make every change of an adjustment without reacting
+    pub(crate) fn change_owned(&mut self, adjust: &OwnedAdjustment) -> Result<(), AdjustError> {
+        match adjust {
+            OwnedAdjustment::Batch(adjusts) => {
+                adjusts.iter().try_for_each(|a| self.change_owned(a))
+            }
+            OwnedAdjustment::Concentration(target, cnc) => {
+                let name = target.name(&self.eq)?.to_string();
+                self.change(Adjustment::Concentration(&name, *cnc))
+            }
+            OwnedAdjustment::Temperature(t) => self.change(Adjustment::Temperature(*t)),
+            OwnedAdjustment::Volume(v) => self.change(Adjustment::Volume(*v)),
+            OwnedAdjustment::Catalyst => self.change(Adjustment::Catalyst),
+            OwnedAdjustment::InertGas(gas) => self.change(Adjustment::InertGas(*gas)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::dinitrogen_tetroxide;
+
+    use super::*;
+
+    #[test]
+    fn matches_borrowed() {
+        let adjust = Adjustment::Concentration("2NO2(g)", 1.0);
+        let mut borrowed = dinitrogen_tetroxide();
+        borrowed.adjust(adjust).unwrap();
+
+        let mut by_name = dinitrogen_tetroxide();
+        by_name.adjust_owned(&adjust.into()).unwrap();
+        let mut by_index = dinitrogen_tetroxide();
+        by_index
+            .adjust_owned(&OwnedAdjustment::Concentration(1.into(), 1.0))
+            .unwrap();
+
+        assert_eq!(by_name, borrowed);
+        // the history remembers the index
+        assert_eq!(by_index.equation(), borrowed.equation());
+    }
+
+    #[test]
+    fn batch_reacts_once() {
+        let mut system = dinitrogen_tetroxide();
+        let adjust = [
+            OwnedAdjustment::Concentration(0.into(), 3.0),
+            OwnedAdjustment::Volume(0.5),
+        ]
+        .into_iter()
+        .collect::<OwnedAdjustment>();
+        system.adjust_owned(&adjust).unwrap();
+
+        // N2O4 was set to 3M then compressed to 6M before reacting
+        let cnc = system.equation().get_concentrations();
+        assert!(cnc[0] > 5.0 && cnc[0] < 6.0);
+        assert_eq!(system.equation().volume(), Some(0.5));
+    }
+
+    #[test]
+    fn batch_error_leaves_system() {
+        let mut system = dinitrogen_tetroxide();
+        let before = system.clone();
+        let adjust = OwnedAdjustment::Batch(vec![
+            OwnedAdjustment::Volume(0.5),
+            OwnedAdjustment::Concentration(5.into(), 1.0),
+        ]);
+
+        assert_eq!(
+            system.adjust_owned(&adjust),
+            Err(AdjustError::CompoundNotFound(
+                ConcentrationNameError::NotFound
+            ))
+        );
+        assert_eq!(system, before);
+        assert_eq!(
+            adjust.as_adjustment(system.equation()).unwrap_err(),
+            AdjustError::Batch
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_targets() {
+        let adjust = serde_json::from_str::<OwnedAdjustment>(
+            r#"{"Batch":[{"Concentration":["N2O4(g)",2.0]},{"Concentration":[1,1.0]},"Catalyst"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            adjust,
+            OwnedAdjustment::Batch(vec![
+                OwnedAdjustment::Concentration("N2O4(g)".into(), 2.0),
+                OwnedAdjustment::Concentration(1.into(), 1.0),
+                OwnedAdjustment::Catalyst,
+            ])
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Solubility equilibria, such as `AgCl(s) <-> Ag+(aq) + Cl-(aq)`
+//!
+//! A dissolution has a single solid on the left and its dissolved ions on the right. Its
+//! equilibrium constant is the solubility product, Ksp. Since the solid doesn't appear in the
+//! reaction quotient, a [`System`] uses its concentration as the amount of undissolved solid
+//! per litre of solution. The solid can then run out, leaving an unsaturated solution.
+
+use chem_eq::{error::ConcentrationNameError, Equation, ReactionQuotient, State};
+use float_cmp::approx_eq;
+use thiserror::Error;
+
+use crate::{System, SystemError};
+
+/// A solution that's mixed with another in [`predict_precipitation`]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Solution {
+    /// The volume of the solution in litres
+    pub volume: f32,
+    /// The name of each ion in the dissolution and its concentration in M (mol/L)
+    pub ions: Vec<(String, f32)>,
+}
+
+impl Solution {
+    /// Construct a [`Solution`] with a volume and no ions
+    pub const fn new(volume: f32) -> Self {
+        Self {
+            volume,
+            ions: Vec::new(),
+        }
+    }
+
+    /// Add an ion to the solution
+    pub fn with_ion(mut self, name: &str, concentration: f32) -> Self {
+        self.ions.push((name.to_string(), concentration));
+        self
+    }
+}
+
+/// What happens when two solutions are mixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Precipitation {
+    /// Q is greater than Ksp, a precipitate forms
+    Forms,
+    /// Q is equal to Ksp, the solution is exactly saturated
+    Saturated,
+    /// Q is less than Ksp, no precipitate forms
+    Unsaturated,
+}
+
+/// Get the molar solubility of the solid in a dissolution, in M (mol/L). Concentrations of ions
+/// already in the equation are treated as common ions, which lower the solubility.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::solubility::molar_solubility;
+///
+/// let mut eq = Equation::new("PbI2(s) <-> Pb^2+(aq) + 2I-(aq)").unwrap();
+/// let s = molar_solubility(&eq, 7.1e-9).unwrap();
+/// assert!((s - 1.21e-3).abs() < 1e-5);
+///
+/// // adding iodide makes lead(II) iodide less soluble
+/// eq.set_concentration_by_name("2I-(aq)", 0.1).unwrap();
+/// assert!(molar_solubility(&eq, 7.1e-9).unwrap() < s);
+/// ```
+pub fn molar_solubility(eq: &Equation, ksp: f32) -> Result<f32, SolubilityError> {
+    check_dissolution(eq)?;
+    if ksp <= 0.0 || !ksp.is_finite() {
+        return Err(SolubilityError::InvalidKsp);
+    }
+    let ksp = ksp as f64;
+
+    // the product of ion concentrations after `s` mol/L has dissolved
+    let ion_product = |s: f64| {
+        eq.right().iter().fold(1.0, |acc, cmp| {
+            let coef = cmp.coefficient as f64;
+            acc * coef.mul_add(s, cmp.concentration as f64).powf(coef)
+        })
+    };
+
+    // without common ions Ksp = Π(νs)^ν can be solved directly
+    let total = eq.right().iter().map(|c| c.coefficient as f64).sum::<f64>();
+    let factor = eq
+        .right()
+        .iter()
+        .map(|c| (c.coefficient as f64).powf(c.coefficient as f64))
+        .product::<f64>();
+    let pure = (ksp / factor).powf(total.recip());
+    if eq.right().iter().all(|c| c.concentration == 0.0) {
+        return Ok(pure as f32);
+    }
+    if ion_product(0.0) >= ksp {
+        return Ok(0.0);
+    }
+
+    // common ions only lower solubility, so it's somewhere between 0 and the pure value
+    let (mut low, mut high) = (0.0, pure);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if ion_product(mid) < ksp {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    Ok(((low + high) / 2.0) as f32)
+}
+
+/// Get the dissolution equation with the ion concentrations after mixing two solutions. The
+/// volume of the returned equation is the combined volume.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::solubility::{mix, Solution};
+///
+/// let eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+/// let silver_nitrate = Solution::new(0.1).with_ion("Ag+(aq)", 0.002);
+/// let sodium_chloride = Solution::new(0.3).with_ion("Cl-(aq)", 0.004);
+///
+/// let mixed = mix(&eq, &silver_nitrate, &sodium_chloride).unwrap();
+/// assert_eq!(mixed.get_concentrations(), vec![0.0, 0.0005, 0.003]);
+/// assert_eq!(mixed.volume(), Some(0.4));
+/// ```
+pub fn mix(
+    eq: &Equation,
+    first: &Solution,
+    second: &Solution,
+) -> Result<Equation, SolubilityError> {
+    check_dissolution(eq)?;
+    let volume = first.volume + second.volume;
+    if first.volume < 0.0 || second.volume < 0.0 || volume <= 0.0 || !volume.is_finite() {
+        return Err(SolubilityError::InvalidVolume);
+    }
+
+    let mut mixed = eq.clone();
+    for cnc in mixed.right_mut().iter_mut().map(|c| &mut c.concentration) {
+        *cnc = 0.0;
+    }
+    for solution in [first, second] {
+        for (name, cnc) in &solution.ions {
+            let moles = cnc * solution.volume;
+            let cmp = mixed
+                .get_compound_by_name_mut(name)
+                .filter(|c| c.state != Some(State::Solid))
+                .ok_or(ConcentrationNameError::NotFound)?;
+            cmp.concentration += moles / volume;
+        }
+    }
+    mixed.set_volume(volume);
+
+    Ok(mixed)
+}
+
+/// Predict whether a precipitate forms when mixing two solutions by comparing Q to Ksp.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::solubility::{predict_precipitation, Precipitation, Solution};
+///
+/// let eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+/// let silver_nitrate = Solution::new(0.1).with_ion("Ag+(aq)", 0.001);
+/// let sodium_chloride = Solution::new(0.1).with_ion("Cl-(aq)", 0.001);
+///
+/// assert_eq!(
+///     predict_precipitation(&eq, 1.8e-10, &silver_nitrate, &sodium_chloride),
+///     Ok(Precipitation::Forms)
+/// );
+/// ```
+pub fn predict_precipitation(
+    eq: &Equation,
+    ksp: f32,
+    first: &Solution,
+    second: &Solution,
+) -> Result<Precipitation, SolubilityError> {
+    if ksp <= 0.0 || !ksp.is_finite() {
+        return Err(SolubilityError::InvalidKsp);
+    }
+    let mixed = mix(eq, first, second)?;
+
+    // the solid isn't counted, so only the ions can be zero
+    let res = match mixed.reaction_quotient() {
+        ReactionQuotient::Val(q) if approx_eq!(f32, q, ksp, ulps = 4) => Precipitation::Saturated,
+        ReactionQuotient::Val(q) if q > ksp => Precipitation::Forms,
+        _ => Precipitation::Unsaturated,
+    };
+
+    Ok(res)
+}
+
+/// Construct a [`System`] for a dissolution, letting the solid dissolve until the ions satisfy
+/// Ksp. The concentration of the solid is how much is available to dissolve per litre, if it
+/// runs out the solution is left unsaturated.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::{solubility, Adjustment};
+///
+/// let mut eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+/// eq.set_concentration_by_name("AgCl(s)", 0.01).unwrap();
+/// let mut system = solubility::dissolve(eq, 1.8e-10).unwrap();
+///
+/// // common ion effect: adding chloride precipitates silver chloride
+/// let silver = system.equation().get_concentration_by_name("Ag+(aq)").unwrap();
+/// system.adjust(Adjustment::Concentration("Cl-(aq)", 0.1)).unwrap();
+/// assert!(system.equation().get_concentration_by_name("Ag+(aq)").unwrap() < silver);
+/// ```
+pub fn dissolve(eq: Equation, ksp: f32) -> Result<System, SolubilityError> {
+    check_dissolution(&eq)?;
+    if ksp <= 0.0 || !ksp.is_finite() {
+        return Err(SolubilityError::InvalidKsp);
+    }
+
+    Ok(System::with_k_expr(eq, ksp)?)
+}
+
+/// Ensure an equation is the dissolution of one solid into aqueous ions
+fn check_dissolution(eq: &Equation) -> Result<(), SolubilityError> {
+    let solid = matches!(eq.left(), [cmp] if cmp.state == Some(State::Solid));
+    let dissolved = !eq.right().is_empty()
+        && eq
+            .right()
+            .iter()
+            .all(|c| matches!(c.state, Some(State::Aqueous) | None));
+
+    if matches!(eq.direction(), chem_eq::Direction::Reversible) && solid && dissolved {
+        Ok(())
+    } else {
+        Err(SolubilityError::NotDissolution)
+    }
+}
+
+/// An error for solubility equilibria
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SolubilityError {
+    /// The equation isn't a reversible dissolution of a single solid
+    #[error("equation isn't the dissolution of a solid into aqueous ions")]
+    NotDissolution,
+    /// Ksp was zero, negative or not finite
+    #[error("Ksp must be a positive number")]
+    InvalidKsp,
+    /// A solution had a negative volume, or they had no volume combined
+    #[error("solutions must have a positive volume")]
+    InvalidVolume,
+    /// An ion wasn't part of the dissolution
+    #[error("ion not found: {0:?}")]
+    CompoundNotFound(#[from] ConcentrationNameError),
+    /// The system couldn't be created
+    #[error("{0}")]
+    System(#[from] SystemError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Adjustment, Direction};
+
+    const AGCL_KSP: f32 = 1.8e-10;
+
+    fn silver_chloride(solid: f32) -> System {
+        let mut eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+        eq.set_concentration_by_name("AgCl(s)", solid).unwrap();
+        dissolve(eq, AGCL_KSP).unwrap()
+    }
+
+    #[test]
+    fn solubility_pure_water() {
+        let eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+        let s = molar_solubility(&eq, AGCL_KSP).unwrap();
+        assert!(approx_eq!(f32, s, AGCL_KSP.sqrt(), ulps = 2));
+    }
+
+    #[test]
+    fn solubility_common_ion() {
+        let mut eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+        eq.set_concentration_by_name("Cl-(aq)", 0.1).unwrap();
+        let s = molar_solubility(&eq, AGCL_KSP).unwrap();
+        assert!(approx_eq!(f32, s, 1.8e-9, epsilon = 1e-12));
+    }
+
+    #[test]
+    fn solubility_not_dissolution() {
+        let eq = Equation::new("2NH3(g) <-> N2(g) + 3H2(g)").unwrap();
+        assert_eq!(
+            molar_solubility(&eq, 1.0),
+            Err(SolubilityError::NotDissolution)
+        );
+        let eq = Equation::new("AgCl(s) -> Ag+(aq) + Cl-(aq)").unwrap();
+        assert_eq!(
+            molar_solubility(&eq, 1.0),
+            Err(SolubilityError::NotDissolution)
+        );
+    }
+
+    #[test]
+    fn precipitation_too_dilute() {
+        let eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+        let first = Solution::new(0.5).with_ion("Ag+(aq)", 1e-5);
+        let second = Solution::new(0.5).with_ion("Cl-(aq)", 1e-5);
+        assert_eq!(
+            predict_precipitation(&eq, AGCL_KSP, &first, &second),
+            Ok(Precipitation::Unsaturated)
+        );
+    }
+
+    #[test]
+    fn precipitation_unknown_ion() {
+        let eq = Equation::new("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap();
+        let first = Solution::new(0.5).with_ion("Na+(aq)", 0.1);
+        assert_eq!(
+            predict_precipitation(&eq, AGCL_KSP, &first, &Solution::new(0.5)),
+            Err(SolubilityError::CompoundNotFound(
+                ConcentrationNameError::NotFound
+            ))
+        );
+    }
+
+    #[test]
+    fn saturated_system() {
+        let system = silver_chloride(1.0);
+        let ions = system.equation().get_concentrations();
+        assert!(approx_eq!(f32, ions[1], AGCL_KSP.sqrt(), epsilon = 1e-9));
+        assert!(approx_eq!(f32, ions[2], AGCL_KSP.sqrt(), epsilon = 1e-9));
+    }
+
+    #[test]
+    fn common_ion_precipitates() {
+        let mut system = silver_chloride(1.0);
+        let solid = system.equation().get_concentrations()[0];
+        assert_eq!(
+            system.get_shift_direction(Adjustment::Concentration("Cl-(aq)", 0.01)),
+            Ok(Direction::Reverse)
+        );
+
+        system
+            .adjust(Adjustment::Concentration("Cl-(aq)", 0.01))
+            .unwrap();
+        let cnc = system.equation().get_concentrations();
+        assert!(cnc[0] > solid);
+        assert!(approx_eq!(f32, cnc[1], 1.8e-8, epsilon = 1e-10));
+    }
+
+    #[test]
+    fn solid_fully_dissolves() {
+        // only a little solid is left at the bottom
+        let mut system = silver_chloride(1.0);
+        system
+            .equation_mut()
+            .set_concentration_by_name("AgCl(s)", 1e-6)
+            .unwrap();
+        system
+            .adjust(Adjustment::Concentration("Ag+(aq)", 1e-7))
+            .unwrap();
+
+        let cnc = system.equation().get_concentrations();
+        assert_eq!(cnc[0], 0.0);
+        assert!(approx_eq!(f32, cnc[1], 1.1e-6, epsilon = 1e-9));
+        let q = system.equation().equilibrium_constant().unwrap();
+        assert!(q < system.get_k_expr());
+
+        // nothing left to dissolve, updating again shouldn't change anything
+        system.update().unwrap();
+        assert_eq!(system.equation().get_concentrations(), cnc);
+    }
+}
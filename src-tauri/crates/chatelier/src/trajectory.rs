@@ -0,0 +1,366 @@
+//! Modelling how a [`System`] approaches equilibrium
+//!
+//! The solver finds equilibrium by searching, so the states it passes through aren't a
+//! physical path. Instead a [`Trajectory`] is modelled from the concentrations right after an
+//! adjustment and those at equilibrium.
+
+use std::ops::ControlFlow;
+
+use chem_eq::Equation;
+
+use crate::{AdjustError, OwnedAdjustment, System, SystemError};
+
+/// The most points a [`Recorder::Samples`] keeps, asking for more keeps this many
+pub const MAX_SAMPLES: usize = 10_000;
+
+/// Points passed to the observer of [`System::adjust_observed`] without a recorder
+pub const OBSERVED_SAMPLES: usize = 61;
+
+/// How a [`System`] records its modelled approach to equilibrium, set with
+/// [`System::set_recorder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Recorder {
+    /// Keep this many evenly spaced points, including the first and last, up to
+    /// [`MAX_SAMPLES`]
+    Samples(usize),
+}
+
+impl Recorder {
+    /// How many points are kept
+    pub fn samples(self) -> usize {
+        match self {
+            Self::Samples(n) => n.min(MAX_SAMPLES),
+        }
+    }
+}
+
+/// How many time constants a reaction takes to reach equilibrium, the larger it is the
+/// sooner concentrations level off
+const TIME_CONSTANTS: f32 = 5.0;
+
+/// The concentrations of a [`System`] as it approached equilibrium, from
+/// [`System::trajectory`].
+///
+/// The path isn't captured from the solver, the reaction is modelled as first order, so each concentration moves from its value right
+/// after the adjustment towards equilibrium quickly at first, then levels off. Concentrations
+/// only ever move one way, they never overshoot.
+///
+/// Time is in arbitrary units, an uncatalysed reaction takes 1 unit to reach equilibrium and a
+/// catalysed one takes `1 / CATALYST_RATE` units. The first point is right after the
+/// adjustment, before any reacting.
+///
+/// ## Examples
+///
+/// ```rust
+/// use chem_eq::Equation;
+/// use chatelier::{Adjustment, Recorder, System};
+///
+/// let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+/// eq.set_concentrations(&[1.0, 0.5]).unwrap();
+/// let mut system = System::new(eq).unwrap();
+/// system.set_recorder(Some(Recorder::Samples(10)));
+///
+/// system.adjust(Adjustment::Concentration("N2O4(g)", 2.0)).unwrap();
+/// let trajectory = system.trajectory().unwrap();
+/// assert_eq!(trajectory.time.len(), 10);
+///
+/// // N2O4 spikes then falls as it reacts, and NO2 rises
+/// let n2o4 = &trajectory.series[0].concentrations;
+/// let no2 = &trajectory.series[1].concentrations;
+/// assert_eq!(n2o4[0], 2.0);
+/// assert!(n2o4.windows(2).all(|w| w[1] <= w[0]));
+/// assert!(no2.windows(2).all(|w| w[1] >= w[0]));
+/// assert!(n2o4[9] < 2.0);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trajectory {
+    /// The time of each point
+    pub time: Vec<f32>,
+    /// The concentrations of each compound, in the order of the equation
+    pub series: Vec<Series>,
+}
+
+/// The concentration of one compound over time, part of a [`Trajectory`]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Series {
+    /// Name of the compound, as in the equation
+    pub compound: String,
+    /// Concentration in M (mol/L) at each time of the [`Trajectory`]
+    pub concentrations: Vec<f32>,
+}
+
+impl Trajectory {
+    /// Model `samples` points, up to [`MAX_SAMPLES`], from the concentrations `before`
+    /// reacting to those of `eq` at equilibrium
+    pub(crate) fn new(eq: &Equation, samples: usize, before: &[f32], rate: f32) -> Self {
+        let len = samples.min(MAX_SAMPLES);
+        let duration = rate.recip();
+        let progress = match len {
+            0 => vec![],
+            1 => vec![0.0],
+            len => (0..len).map(|i| i as f32 / (len - 1) as f32).collect(),
+        };
+        let time = progress.iter().map(|p| p * duration).collect();
+        let series = eq
+            .iter_compounds()
+            .zip(eq.compound_names())
+            .zip(before)
+            .map(|((cmp, name), &start)| Series {
+                compound: name.to_string(),
+                concentrations: progress
+                    .iter()
+                    .map(|&p| relax(start, cmp.concentration, p))
+                    .collect(),
+            })
+            .collect();
+
+        Self { time, series }
+    }
+}
+
+/// The concentration part way, `progress` from 0 to 1, through a first order reaction from
+/// `start` to `end`. Always between the two, and exactly `start` and `end` at either end.
+fn relax(start: f32, end: f32, progress: f32) -> f32 {
+    if progress <= 0.0 {
+        return start;
+    }
+    // how much of the change is left, falling exponentially from 1 to 0
+    let remaining = ((-TIME_CONSTANTS * progress).exp() - (-TIME_CONSTANTS).exp())
+        / (1.0 - (-TIME_CONSTANTS).exp());
+    let conc = end - (end - start) * remaining.max(0.0);
+
+    conc.clamp(start.min(end), start.max(end))
+}
+
+impl System {
+    /// Start or stop recording how the system approaches equilibrium. The latest recording
+    /// is available from [`System::trajectory`].
+    pub fn set_recorder(&mut self, recorder: Option<Recorder>) {
+        self.recorder = recorder;
+        if recorder.is_none() {
+            self.trajectory = None;
+        }
+    }
+
+    /// Get the recorder, if recording
+    pub fn recorder(&self) -> Option<Recorder> {
+        self.recorder
+    }
+
+    /// Get the trajectory of the last time the system reacted, if recording
+    pub fn trajectory(&self) -> Option<&Trajectory> {
+        self.trajectory.as_ref()
+    }
+
+    /// Take the trajectory of the last time the system reacted, if recording
+    pub fn take_trajectory(&mut self) -> Option<Trajectory> {
+        self.trajectory.take()
+    }
+
+    /// Make an adjustment like [`System::adjust_owned`], calling `on_frame` with the
+    /// concentrations at each point of the modelled approach to equilibrium. These are the
+    /// points the recorder keeps, or [`OBSERVED_SAMPLES`] of them without a recorder. If
+    /// `on_frame` returns [`ControlFlow::Break`], it stops with [`SystemError::Cancelled`]
+    /// and the system is left unchanged.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use std::ops::ControlFlow;
+    ///
+    /// use chem_eq::Equation;
+    /// use chatelier::{AdjustError, OwnedAdjustment, System, SystemError};
+    ///
+    /// let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+    /// eq.set_concentrations(&[1.0, 0.5]).unwrap();
+    /// let mut system = System::new(eq).unwrap();
+    /// let adjust = OwnedAdjustment::Concentration("N2O4(g)".into(), 2.0);
+    ///
+    /// // stop at the first point
+    /// let res = system.adjust_observed(&adjust, |_| ControlFlow::Break(()));
+    /// assert_eq!(res, Err(AdjustError::System(SystemError::Cancelled)));
+    /// assert_eq!(system.equation().get_concentrations(), vec![1.0, 0.5]);
+    ///
+    /// let mut points = 0;
+    /// system
+    ///     .adjust_observed(&adjust, |_| {
+    ///         points += 1;
+    ///         ControlFlow::Continue(())
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(points, chatelier::OBSERVED_SAMPLES);
+    /// ```
+    pub fn adjust_observed(
+        &mut self,
+        adjust: &OwnedAdjustment,
+        mut on_frame: impl FnMut(&[f32]) -> ControlFlow<()>,
+    ) -> Result<(), AdjustError> {
+        let mut sys = self.clone();
+        sys.change_owned(adjust)?;
+        let before = sys.eq.get_concentrations();
+        sys.react_to_match_k()?;
+
+        let samples = sys.recorder.map_or(OBSERVED_SAMPLES, Recorder::samples);
+        let trajectory = Trajectory::new(&sys.eq, samples, &before, sys.rate());
+        for i in 0..trajectory.time.len() {
+            let frame = trajectory
+                .series
+                .iter()
+                .map(|s| s.concentrations[i])
+                .collect::<Vec<_>>();
+            if on_frame(&frame).is_break() {
+                return Err(SystemError::Cancelled.into());
+            }
+        }
+        sys.history.push(adjust.clone(), self.snapshot());
+        *self = sys;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixtures::dinitrogen_tetroxide, Adjustment};
+
+    use super::*;
+
+    fn is_monotone(conc: &[f32]) -> bool {
+        conc.windows(2).all(|w| w[1] >= w[0]) || conc.windows(2).all(|w| w[1] <= w[0])
+    }
+
+    #[test]
+    fn spike_then_relax() {
+        let mut system = dinitrogen_tetroxide();
+        system.set_recorder(Some(Recorder::Samples(20)));
+        system
+            .adjust(Adjustment::Concentration("2NO2(g)", 1.0))
+            .unwrap();
+
+        let trajectory = system.trajectory().unwrap();
+        let no2 = &trajectory.series[1].concentrations;
+        assert_eq!(no2.len(), 20);
+        assert_eq!(no2.len(), trajectory.time.len());
+        assert_eq!(trajectory.time[0], 0.0);
+        assert_eq!(*trajectory.time.last().unwrap(), 1.0);
+
+        // spikes then relaxes towards equilibrium
+        assert_eq!(no2[0], 1.0);
+        assert_eq!(
+            *no2.last().unwrap(),
+            system.equation().get_concentrations()[1]
+        );
+        assert!(*no2.last().unwrap() < 1.0);
+        assert!(trajectory
+            .series
+            .iter()
+            .all(|s| is_monotone(&s.concentrations)));
+    }
+
+    #[test]
+    fn monotone() {
+        let mut eq = Equation::new("N2(g) + 3H2(g) <-> 2NH3(g)").unwrap();
+        eq.set_concentrations(&[0.5, 0.8, 0.2]).unwrap();
+        let mut system = System::new(eq).unwrap();
+        for recorder in [Recorder::Samples(2), Recorder::Samples(50)] {
+            system.set_recorder(Some(recorder));
+            for adjust in [
+                Adjustment::Concentration("N2(g)", 2.0),
+                Adjustment::Volume(0.25),
+                Adjustment::Concentration("2NH3(g)", 0.01),
+            ] {
+                system.adjust(adjust).unwrap();
+                let trajectory = system.trajectory().unwrap();
+                assert!(trajectory.time.windows(2).all(|w| w[1] > w[0]));
+                for series in &trajectory.series {
+                    assert!(is_monotone(&series.concentrations), "{:?}", series);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn catalyst_is_faster() {
+        let mut system = dinitrogen_tetroxide();
+        system.set_recorder(Some(Recorder::Samples(5)));
+        system.adjust(Adjustment::Catalyst).unwrap();
+        system.adjust(Adjustment::Volume(0.5)).unwrap();
+
+        let trajectory = system.trajectory().unwrap();
+        assert_eq!(trajectory.time, vec![0.0, 0.025, 0.05, 0.075, 0.1]);
+    }
+
+    #[test]
+    fn not_recording() {
+        let mut system = dinitrogen_tetroxide();
+        system.adjust(Adjustment::Volume(0.5)).unwrap();
+        assert_eq!(system.trajectory(), None);
+
+        system.set_recorder(Some(Recorder::Samples(5)));
+        system.adjust(Adjustment::Volume(1.0)).unwrap();
+        assert!(system.take_trajectory().is_some());
+        assert_eq!(system.trajectory(), None);
+    }
+
+    #[test]
+    fn observed_matches_recorded() {
+        let mut system = dinitrogen_tetroxide();
+        system.set_recorder(Some(Recorder::Samples(15)));
+        let mut frames = vec![];
+        system
+            .adjust_observed(&OwnedAdjustment::Volume(0.5), |frame| {
+                frames.push(frame.to_vec());
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        // the observer sees exactly the recorded points
+        let trajectory = system.trajectory().unwrap();
+        assert_eq!(frames.len(), 15);
+        for (i, frame) in frames.iter().enumerate() {
+            let point = trajectory
+                .series
+                .iter()
+                .map(|s| s.concentrations[i])
+                .collect::<Vec<_>>();
+            assert_eq!(*frame, point);
+        }
+        assert_eq!(
+            *frames.last().unwrap(),
+            system.equation().get_concentrations()
+        );
+        assert!(system.can_undo());
+
+        // without a recorder there are still points to observe, but nothing is kept
+        system.set_recorder(None);
+        let mut observed = 0;
+        system
+            .adjust_observed(&OwnedAdjustment::Volume(1.0), |_| {
+                observed += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(observed, OBSERVED_SAMPLES);
+        assert_eq!(system.trajectory(), None);
+    }
+
+    #[test]
+    fn relax_ends() {
+        assert_eq!(relax(2.0, 1.3, 0.0), 2.0);
+        assert_eq!(relax(2.0, 1.3, 1.0), 1.3);
+        assert_eq!(relax(0.1, 0.7, 1.0), 0.7);
+        // most of the change happens early
+        assert!(relax(0.0, 1.0, 0.5) > 0.9);
+    }
+
+    #[test]
+    fn samples_are_bounded() {
+        let mut system = dinitrogen_tetroxide();
+        system.set_recorder(Some(Recorder::Samples(usize::MAX)));
+        system.adjust(Adjustment::Volume(0.5)).unwrap();
+        assert_eq!(system.trajectory().unwrap().time.len(), MAX_SAMPLES);
+    }
+}
@@ -0,0 +1,555 @@
+//! Classifying reactions, and the oxidation states that tell if one is a redox reaction
+//!
+//! [`Equation::classify`] looks at the structure of the compounds on each side, like whether a
+//! compound is an element or which ions it's made of, to find the kind of reaction a student
+//! would call it. Reactions that don't fit any pattern are checked for a change in oxidation
+//! state with [`Equation::is_redox`].
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    ops::Range,
+};
+
+use crate::{properties::Metallicity, Compound, Direction, Element, Equation, State};
+
+/// The kind of a reaction, returned by [`Equation::classify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReactionType {
+    /// Two or more reactants combine into one product, eg. `2Na + Cl2 -> 2NaCl`
+    Synthesis,
+    /// One reactant breaks down into two or more products, eg. `2H2O -> 2H2 + O2`
+    Decomposition,
+    /// An element takes the place of another in a compound, eg. `Zn + CuSO4 -> ZnSO4 + Cu`
+    SingleReplacement,
+    /// Two ionic compounds swap ions, eg. `KBr + AgF -> KF + AgBr`
+    DoubleReplacement,
+    /// A hydrocarbon burns in oxygen to make carbon dioxide and water, eg.
+    /// `CH4 + 2O2 -> CO2 + 2H2O`
+    Combustion,
+    /// An acid and a base make a salt and water, eg. `HCl + NaOH -> NaCl + H2O`
+    Neutralization,
+    /// Ions in solution form a solid, eg. `AgNO3(aq) + NaCl(aq) -> AgCl(s) + NaNO3(aq)`
+    Precipitation,
+    /// Electrons move between elements, changing their oxidation states, without fitting
+    /// another pattern, eg. `CO + H2O -> CO2 + H2`
+    Redox,
+}
+
+impl ReactionType {
+    /// Describe what happens in this kind of reaction in a sentence
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Synthesis => "Two or more reactants combine to form a single product.",
+            Self::Decomposition => "A single reactant breaks down into two or more products.",
+            Self::SingleReplacement => {
+                "An element replaces another element in a compound, setting it free."
+            }
+            Self::DoubleReplacement => "Two ionic compounds exchange ions.",
+            Self::Combustion => "A hydrocarbon burns in oxygen, making carbon dioxide and water.",
+            Self::Neutralization => "An acid reacts with a base, making a salt and water.",
+            Self::Precipitation => "Ions in solution combine to form an insoluble solid.",
+            Self::Redox => "Electrons are transferred, changing the oxidation states of elements.",
+        }
+    }
+}
+
+impl Display for ReactionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Synthesis => "synthesis",
+            Self::Decomposition => "decomposition",
+            Self::SingleReplacement => "single replacement",
+            Self::DoubleReplacement => "double replacement",
+            Self::Combustion => "combustion",
+            Self::Neutralization => "acid-base neutralization",
+            Self::Precipitation => "precipitation",
+            Self::Redox => "redox",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Equation {
+    /// Classify the reaction. The most specific kind is returned, so a precipitation isn't
+    /// also called a double replacement, and a reaction is only [`ReactionType::Redox`] if it
+    /// isn't anything else. [`None`] is returned if the reaction doesn't fit any kind.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::{classify::ReactionType, Equation};
+    ///
+    /// let classify = |eq: &str| Equation::new(eq).unwrap().classify();
+    /// assert_eq!(classify("2Na + Cl2 -> 2NaCl"), Some(ReactionType::Synthesis));
+    /// assert_eq!(classify("CH4 + 2O2 -> CO2 + 2H2O"), Some(ReactionType::Combustion));
+    /// assert_eq!(
+    ///     classify("AgNO3(aq) + NaCl(aq) -> AgCl(s) + NaNO3(aq)"),
+    ///     Some(ReactionType::Precipitation)
+    /// );
+    /// assert_eq!(classify("CO + H2O -> CO2 + H2"), Some(ReactionType::Redox));
+    /// ```
+    pub fn classify(&self) -> Option<ReactionType> {
+        let (reactants, products) = match self.direction {
+            Direction::Left => (&self.right, &self.left),
+            Direction::Right | Direction::Reversible => (&self.left, &self.right),
+        };
+
+        let kind = if is_combustion(reactants, products) {
+            ReactionType::Combustion
+        } else if is_neutralization(reactants, products) {
+            ReactionType::Neutralization
+        } else if is_precipitation(reactants, products) {
+            ReactionType::Precipitation
+        } else if is_double_replacement(reactants, products) {
+            ReactionType::DoubleReplacement
+        } else if is_single_replacement(reactants, products) {
+            ReactionType::SingleReplacement
+        } else if combines(reactants, products) {
+            ReactionType::Synthesis
+        } else if combines(products, reactants) {
+            ReactionType::Decomposition
+        } else if self.is_redox() {
+            ReactionType::Redox
+        } else {
+            return None;
+        };
+
+        Some(kind)
+    }
+
+    /// Check if any element's oxidation state changes in the reaction. Returns `false` if the
+    /// oxidation states of a compound can't be found.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Equation;
+    ///
+    /// assert!(Equation::new("Zn + CuSO4 -> ZnSO4 + Cu").unwrap().is_redox());
+    /// assert!(!Equation::new("HCl + NaOH -> NaCl + H2O").unwrap().is_redox());
+    /// ```
+    pub fn is_redox(&self) -> bool {
+        match (side_states(&self.left), side_states(&self.right)) {
+            (Some(left), Some(right)) => {
+                left.len() != right.len()
+                    || left
+                        .iter()
+                        .zip(&right)
+                        .any(|((a, a_states), (b, b_states))| {
+                            a != b
+                                || a_states.len() != b_states.len()
+                                || a_states
+                                    .iter()
+                                    .zip(b_states)
+                                    .any(|(x, y)| (x - y).abs() > 1e-3)
+                        })
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Compound {
+    /// Get the oxidation state of each element in [`Compound::elements`]. Polyatomic ions
+    /// found by the parser are worked out on their own, so the nitrogens in `NH4NO3` are -3 and
+    /// +5. Averages are given when atoms of an element could differ, like 8/3 for the iron in
+    /// `Fe3O4`.
+    ///
+    /// Fluorine, group 1 and 2 metals, hydrogen and oxygen get their usual states first, then
+    /// the most electronegative elements get their lowest common state, until one element is
+    /// left to balance the charge. [`None`] is returned if an element has no common states,
+    /// like a noble gas.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Compound;
+    ///
+    /// let states = |cmp: &str| Compound::parse(cmp).unwrap().oxidation_states().unwrap();
+    /// assert_eq!(states("KMnO4"), [1.0, 7.0, -2.0]);
+    /// assert_eq!(states("NH4NO3"), [-3.0, 1.0, 5.0, -2.0]);
+    /// assert_eq!(states("H2O2"), [1.0, -1.0]);
+    /// assert_eq!(states("Cr2O7^2-"), [6.0, -2.0]);
+    /// ```
+    pub fn oxidation_states(&self) -> Option<Vec<f32>> {
+        let mut states = vec![0.0; self.elements.len()];
+        let mut rest = (0..self.elements.len()).collect::<Vec<_>>();
+        let mut rest_charge = self.charge.unwrap_or(0);
+
+        for group in &self.groups {
+            let Some(ion) = group.ion(self) else {
+                continue;
+            };
+            let charge = ion.charge * group.count as i32;
+            let solved = solve(&self.elements[group.start..group.end], charge)?;
+            states[group.start..group.end].copy_from_slice(&solved);
+            rest.retain(|i| !(group.start..group.end).contains(i));
+            rest_charge -= charge;
+        }
+
+        if !rest.is_empty() {
+            let elements = rest
+                .iter()
+                .map(|&i| self.elements[i].clone())
+                .collect::<Vec<_>>();
+            for (i, state) in rest.into_iter().zip(solve(&elements, rest_charge)?) {
+                states[i] = state;
+            }
+        }
+
+        Some(states)
+    }
+}
+
+/// Work out the oxidation state of each element in a neutral compound or simple ion
+fn solve(elements: &[Element], charge: i32) -> Option<Vec<f32>> {
+    // each element and how many atoms of it there are, in order
+    let mut totals: Vec<(&Element, usize)> = vec![];
+    for el in elements {
+        match totals.iter_mut().find(|(e, _)| e.symbol() == el.symbol()) {
+            Some((_, count)) => *count += el.count,
+            None => totals.push((el, el.count)),
+        }
+    }
+    // hydrogen is a hydride ion with metals
+    let hydride = totals
+        .iter()
+        .filter(|(e, _)| e.symbol() != "H")
+        .all(|(e, _)| e.metallicity() == Metallicity::Metal);
+
+    let mut states: Vec<Option<f32>> = vec![None; totals.len()];
+    let unknown = |states: &[Option<f32>]| states.iter().filter(|s| s.is_none()).count();
+    for priority in 0..RULES {
+        for (i, (el, _)) in totals.iter().enumerate() {
+            if unknown(&states) > 1 && states[i].is_none() {
+                states[i] = usual_state(priority, el, hydride).map(|s| s as f32);
+            }
+        }
+    }
+    while unknown(&states) > 1 {
+        let (i, el) = totals
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| states[*i].is_none())
+            .map(|(i, (el, _))| (i, el))
+            .max_by(|(_, a), (_, b)| {
+                let en = |e: &Element| e.electronegativity().unwrap_or(0.0);
+                en(a).total_cmp(&en(b))
+            })?;
+        states[i] = Some(*el.common_oxidation_states().first()? as f32);
+    }
+
+    // the last element balances the charge
+    let known = totals
+        .iter()
+        .zip(&states)
+        .filter_map(|((_, count), state)| state.map(|s| s * *count as f32))
+        .sum::<f32>();
+    if let Some(i) = states.iter().position(Option::is_none) {
+        states[i] = Some((charge as f32 - known) / totals[i].1 as f32);
+    }
+
+    elements
+        .iter()
+        .map(|el| {
+            let i = totals.iter().position(|(e, _)| e.symbol() == el.symbol())?;
+            states[i]
+        })
+        .collect()
+}
+
+/// How many rules [`usual_state`] has
+const RULES: usize = 5;
+
+/// The usual oxidation state of an element by the rule at `priority`, where earlier rules win
+fn usual_state(priority: usize, el: &Element, hydride: bool) -> Option<i32> {
+    let group = el.group().map(|g| g.group_number());
+    match (priority, el.symbol()) {
+        (0, "F") => Some(-1),
+        (1, "H") => None,
+        (1, _) if group == Some(1) => Some(1),
+        (2, _) if group == Some(2) => Some(2),
+        (3, "H") if hydride => Some(-1),
+        (3, "H") => Some(1),
+        (4, "O") => Some(-2),
+        _ => None,
+    }
+}
+
+/// Every oxidation state of each element on one side of an equation, in order
+fn side_states(side: &[Compound]) -> Option<BTreeMap<&str, Vec<f32>>> {
+    let mut all = BTreeMap::<&str, Vec<f32>>::new();
+    for cmp in side {
+        for (el, state) in cmp.elements.iter().zip(cmp.oxidation_states()?) {
+            all.entry(el.symbol()).or_default().push(state);
+        }
+    }
+    for states in all.values_mut() {
+        states.sort_by(f32::total_cmp);
+        states.dedup_by(|a, b| (*a - *b).abs() < 1e-3);
+    }
+
+    Some(all)
+}
+
+/// The cation and anion of a neutral ionic compound, written so they can be compared between
+/// compounds. `CaCl2` is `("Ca", "Cl")` and `(NH4)2SO4` is `("NH4", "SO4")`.
+fn ions(cmp: &Compound) -> Option<(String, String)> {
+    let elements = &cmp.elements;
+    if cmp.charge.unwrap_or(0) != 0 || elements.len() < 2 {
+        return None;
+    }
+    let part = |range: Range<usize>| {
+        let ion = cmp
+            .groups
+            .iter()
+            .find(|g| g.start == range.start && g.end == range.end)
+            .and_then(|g| g.ion(cmp));
+        let els = &elements[range];
+        match ion {
+            Some(ion) => ion.formula.to_string(),
+            None if els.iter().all(|e| e.symbol() == els[0].symbol()) => {
+                els[0].symbol().to_string()
+            }
+            None => els.iter().map(ToString::to_string).collect(),
+        }
+    };
+
+    let split = cation_end(cmp)?;
+
+    Some((part(0..split), part(split..elements.len())))
+}
+
+/// The index in [`Compound::elements`] after the cation of a compound that starts with a metal,
+/// hydrogen or a polyatomic ion, if something comes after it
+pub(crate) fn cation_end(cmp: &Compound) -> Option<usize> {
+    let elements = &cmp.elements;
+    let split = match cmp.groups.first() {
+        Some(group) if group.start == 0 => group.end,
+        _ if elements.first()?.symbol() == "H"
+            || elements[0].metallicity() == Metallicity::Metal =>
+        {
+            1
+        }
+        _ => return None,
+    };
+
+    (split < elements.len()).then_some(split)
+}
+
+/// If `parts` combine into the one compound in `whole`, like `N2 + 3H2 -> 2NH3` or
+/// `2NO2 -> N2O4`
+fn combines(parts: &[Compound], whole: &[Compound]) -> bool {
+    let atoms = |cmp: &Compound| cmp.elements.iter().map(|e| e.count).sum::<usize>();
+    match (parts, whole) {
+        ([_, _, ..], [_]) => true,
+        ([part], [whole]) => atoms(part) < atoms(whole),
+        _ => false,
+    }
+}
+
+/// If a compound is a single element, like `O2` or `Cu`
+pub(crate) fn is_element(cmp: &Compound) -> bool {
+    cmp.charge.unwrap_or(0) == 0
+        && cmp
+            .elements
+            .iter()
+            .all(|e| e.symbol() == cmp.elements[0].symbol())
+}
+
+fn is_acid(cmp: &Compound) -> bool {
+    let symbols = cmp.elements.iter().map(|e| e.symbol()).collect::<Vec<_>>();
+    // carboxylic acids, like CH3COOH
+    if symbols.ends_with(&["C", "O", "O", "H"]) {
+        return true;
+    }
+    matches!(ions(cmp), Some((cation, anion)) if cation == "H" && anion != "O")
+}
+
+fn is_base(cmp: &Compound) -> bool {
+    cmp.formula() == "NH3"
+        || matches!(ions(cmp), Some((cation, anion)) if cation != "H" && anion == "OH")
+}
+
+fn is_combustion(reactants: &[Compound], products: &[Compound]) -> bool {
+    let has = |side: &[Compound], formula: &str| side.iter().any(|c| c.formula() == formula);
+    let fuel = |c: &Compound| {
+        let has_symbol = |s: &str| c.elements.iter().any(|e| e.symbol() == s);
+        has_symbol("C")
+            && has_symbol("H")
+            && c.elements
+                .iter()
+                .all(|e| ["C", "H", "O"].contains(&e.symbol()))
+    };
+
+    reactants.len() == 2
+        && has(reactants, "O2")
+        && reactants.iter().any(fuel)
+        && has(products, "CO2")
+        && has(products, "H2O")
+}
+
+fn is_neutralization(reactants: &[Compound], products: &[Compound]) -> bool {
+    let [a, b] = reactants else {
+        return false;
+    };
+    let makes_water = products.iter().any(|c| c.formula() == "H2O");
+    // ammonia is a base without hydroxide, so it doesn't make water
+    let ammonia = a.formula() == "NH3" || b.formula() == "NH3";
+
+    ((is_acid(a) && is_base(b)) || (is_base(a) && is_acid(b))) && (makes_water || ammonia)
+}
+
+fn is_precipitation(reactants: &[Compound], products: &[Compound]) -> bool {
+    let solid = products
+        .iter()
+        .any(|c| c.state == Some(State::Solid) && c.charge.unwrap_or(0) == 0);
+    let dissolved = reactants.iter().all(|c| c.state == Some(State::Aqueous));
+    // a net ionic equation, like Ag+(aq) + Cl-(aq) -> AgCl(s)
+    let net_ionic = reactants.iter().all(|c| c.charge.unwrap_or(0) != 0);
+
+    solid && dissolved && (net_ionic || is_double_replacement(reactants, products))
+}
+
+fn is_double_replacement(reactants: &[Compound], products: &[Compound]) -> bool {
+    let (Some((a, b)), Some((c, d))) = (
+        reactants.first().and_then(ions),
+        reactants.get(1).and_then(ions),
+    ) else {
+        return false;
+    };
+    if reactants.len() != 2 || products.len() != 2 || a == c {
+        return false;
+    }
+
+    let mut expected = vec![(a, d), (c, b)];
+    let mut found = products.iter().filter_map(ions).collect::<Vec<_>>();
+    expected.sort();
+    found.sort();
+    expected == found
+}
+
+fn is_single_replacement(reactants: &[Compound], products: &[Compound]) -> bool {
+    let split = |side: &[Compound]| match side {
+        [a, b] if is_element(a) && !is_element(b) => Some((a.elements[0].symbol(), b.clone())),
+        [a, b] if is_element(b) && !is_element(a) => Some((b.elements[0].symbol(), a.clone())),
+        _ => None,
+    };
+    let (Some((element, _)), Some((freed, compound))) = (split(reactants), split(products)) else {
+        return false;
+    };
+
+    element != freed && compound.elements.iter().any(|e| e.symbol() == element)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(eq: &str) -> Option<ReactionType> {
+        Equation::new(eq).unwrap().classify()
+    }
+
+    #[test]
+    fn structural() {
+        assert_eq!(classify("N2 + 3H2 -> 2NH3"), Some(ReactionType::Synthesis));
+        assert_eq!(
+            classify("CaO + CO2 -> CaCO3"),
+            Some(ReactionType::Synthesis)
+        );
+        assert_eq!(
+            classify("2KClO3 -> 2KCl + 3O2"),
+            Some(ReactionType::Decomposition)
+        );
+        assert_eq!(
+            classify("N2O4(g) <-> 2NO2(g)"),
+            Some(ReactionType::Decomposition)
+        );
+        assert_eq!(
+            classify("Cl2 + 2NaBr -> 2NaCl + Br2"),
+            Some(ReactionType::SingleReplacement)
+        );
+        assert_eq!(
+            classify("Fe + CuSO4 -> FeSO4 + Cu"),
+            Some(ReactionType::SingleReplacement)
+        );
+        assert_eq!(
+            classify("BaCl2 + Na2SO4 -> BaSO4 + 2NaCl"),
+            Some(ReactionType::DoubleReplacement)
+        );
+        assert_eq!(classify("2NO2 -> N2O4"), Some(ReactionType::Synthesis));
+        // products are on the left
+        assert_eq!(classify("2NH3 <- N2 + 3H2"), Some(ReactionType::Synthesis));
+    }
+
+    #[test]
+    fn combustion_and_neutralization() {
+        assert_eq!(
+            classify("2C8H18 + 25O2 -> 16CO2 + 18H2O"),
+            Some(ReactionType::Combustion)
+        );
+        assert_eq!(
+            classify("C2H5OH + 3O2 -> 2CO2 + 3H2O"),
+            Some(ReactionType::Combustion)
+        );
+        assert_eq!(
+            classify("H2SO4 + 2NaOH -> Na2SO4 + 2H2O"),
+            Some(ReactionType::Neutralization)
+        );
+        assert_eq!(
+            classify("CH3COOH + KOH -> CH3COOK + H2O"),
+            Some(ReactionType::Neutralization)
+        );
+        assert_eq!(
+            classify("HCl + NH3 -> NH4Cl"),
+            Some(ReactionType::Neutralization)
+        );
+        // burning hydrogen isn't combustion of a hydrocarbon
+        assert_eq!(classify("2H2 + O2 -> 2H2O"), Some(ReactionType::Synthesis));
+    }
+
+    #[test]
+    fn precipitation() {
+        assert_eq!(
+            classify("Pb(NO3)2(aq) + 2KI(aq) -> PbI2(s) + 2KNO3(aq)"),
+            Some(ReactionType::Precipitation)
+        );
+        assert_eq!(
+            classify("Ag+(aq) + Cl-(aq) -> AgCl(s)"),
+            Some(ReactionType::Precipitation)
+        );
+        // without states it can't be known
+        assert_eq!(
+            classify("Pb(NO3)2 + 2KI -> PbI2 + 2KNO3"),
+            Some(ReactionType::DoubleReplacement)
+        );
+    }
+
+    #[test]
+    fn redox() {
+        assert_eq!(classify("CO + H2O -> CO2 + H2"), Some(ReactionType::Redox));
+        assert_eq!(
+            classify("MnO4-(aq) + 5Fe^2+(aq) + 8H+(aq) -> Mn^2+(aq) + 5Fe^3+(aq) + 4H2O(l)"),
+            Some(ReactionType::Redox)
+        );
+        assert!(Equation::new("2Na + Cl2 -> 2NaCl").unwrap().is_redox());
+        assert!(!Equation::new("CaO + CO2 -> CaCO3").unwrap().is_redox());
+        assert_eq!(classify("NaCl + KBr -> NaBr + KCl + NaCl"), None);
+    }
+
+    #[test]
+    fn oxidation_states() {
+        let states = |cmp: &str| Compound::parse(cmp).unwrap().oxidation_states();
+        assert_eq!(states("O2").unwrap(), [0.0]);
+        assert_eq!(states("Fe^3+").unwrap(), [3.0]);
+        assert_eq!(states("NaH").unwrap(), [1.0, -1.0]);
+        assert_eq!(states("OF2").unwrap(), [2.0, -1.0]);
+        assert_eq!(states("CH3COOH").unwrap(), [0.0, 1.0, 0.0, -2.0, -2.0, 1.0]);
+        assert_eq!(states("Fe2(SO4)3").unwrap(), [3.0, 6.0, -2.0]);
+        assert_eq!(states("PCl5").unwrap(), [5.0, -1.0]);
+        assert!((states("Fe3O4").unwrap()[0] - 8.0 / 3.0).abs() < 1e-6);
+        assert_eq!(states("XeF4").unwrap(), [4.0, -1.0]);
+    }
+}
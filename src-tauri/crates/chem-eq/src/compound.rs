@@ -1,6 +1,6 @@
 //! Implementation of [`Compound`]
 
-use crate::{error::CompoundError, parse, Element, State, AVAGADRO_CONSTANT};
+use crate::{error::CompoundError, ion::PolyatomicIon, parse, Element, State, AVAGADRO_CONSTANT};
 
 /// An inidiviual compound. Containing some elements and a coefficient.
 ///
@@ -9,21 +9,58 @@ use crate::{error::CompoundError, parse, Element, State, AVAGADRO_CONSTANT};
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Compound {
     /// The elements of a compound
-    #[cfg_attr(feature = "serde", serde(skip))]
     pub elements: Vec<Element>,
+    /// Elements written as a unit, like brackets or a polyatomic ion, in order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub groups: Vec<Group>,
     /// The coefficient of the whole compound
     pub coefficient: usize,
     /// The state of the compound
     pub state: Option<State>,
+    /// The charge of the compound if it's an ion
+    ///
+    /// Eg. SO4^2- will have a charge of -2
+    pub charge: Option<i32>,
     /// The concentration in M (mol/L) of the compound
     pub concentration: f32,
 }
 
+/// Elements of a [`Compound`] written as a unit, like the `(SO4)3` in `Al2(SO4)3`
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Group {
+    /// The index in [`Compound::elements`] of the first element of the group
+    pub start: usize,
+    /// The index after the last element of the group
+    pub end: usize,
+    /// How many of the group there are. The count of each element is already multiplied by
+    /// this.
+    pub count: usize,
+}
+
+impl Group {
+    /// Get the polyatomic ion the group is, if it's one
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Compound;
+    ///
+    /// let cmp = Compound::parse("Al2(SO4)3").unwrap();
+    /// let ion = cmp.groups[0].ion(&cmp).unwrap();
+    /// assert_eq!(ion.name, "sulfate");
+    /// ```
+    pub fn ion(&self, compound: &Compound) -> Option<&'static PolyatomicIon> {
+        PolyatomicIon::find(compound.elements.get(self.start..self.end)?, self.count)
+    }
+}
+
 impl PartialEq for Compound {
     fn eq(&self, other: &Self) -> bool {
         self.elements == other.elements
             && self.coefficient == other.coefficient
             && self.state == other.state
+            && self.charge == other.charge
             && self.concentration == other.concentration
     }
 }
@@ -124,4 +161,22 @@ mod tests {
         cmp.add_unit(volume, -1);
         assert_eq!(cmp.get_units(volume), AVAGADRO_CONSTANT - 1.0);
     }
+
+    #[test]
+    fn formula_with_bad_groups() {
+        let mut cmp = Compound::parse("Ca(OH)2").unwrap();
+        cmp.groups = vec![Group {
+            start: 1,
+            end: 9,
+            count: 2,
+        }];
+        assert_eq!(cmp.formula(), "CaO2H2");
+
+        cmp.groups = vec![Group {
+            start: 1,
+            end: 1,
+            count: 2,
+        }];
+        assert_eq!(cmp.formula(), "CaO2H2");
+    }
 }
@@ -14,11 +14,77 @@ impl Display for Compound {
             .state
             .as_ref()
             .map_or_else(Default::default, |s| format!("{}", s));
+        let charge = charge(self.charge);
         let mut elms = String::default();
         for el in &self.elements {
             elms.push_str(el.to_string().as_str());
         }
-        write!(f, "{}{}{}", self.coefficient, elms, state)
+        write!(f, "{}{}{}{}", self.coefficient, elms, charge, state)
+    }
+}
+
+impl Compound {
+    /// Get the formula of the compound as it's usually written, with brackets around groups
+    /// there's more than one of and counts of one left out. The coefficient and state aren't
+    /// included.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Compound;
+    ///
+    /// let cmp = Compound::parse("3Ca(OH)2(aq)").unwrap();
+    /// assert_eq!(cmp.to_string(), "3Ca1O2H2(aq)");
+    /// assert_eq!(cmp.formula(), "Ca(OH)2");
+    ///
+    /// assert_eq!(Compound::parse("SO4^2-").unwrap().formula(), "SO4^2-");
+    /// ```
+    pub fn formula(&self) -> String {
+        let push = |formula: &mut String, elements: &[Element], divisor: usize| {
+            for el in elements {
+                formula.push_str(el.symbol());
+                if el.count / divisor != 1 {
+                    formula.push_str(&(el.count / divisor).to_string());
+                }
+            }
+        };
+
+        let mut formula = String::new();
+        let mut i = 0;
+        while i < self.elements.len() {
+            // groups are public, so one that doesn't fit the elements is written ungrouped
+            let grouped = self.groups.iter().find_map(|g| {
+                let elements = self.elements.get(g.start..g.end)?;
+                (g.start == i && g.count > 1 && !elements.is_empty()).then_some((g, elements))
+            });
+            match grouped {
+                Some((group, elements)) => {
+                    formula.push('(');
+                    push(&mut formula, elements, group.count);
+                    formula.push(')');
+                    formula.push_str(&group.count.to_string());
+                    i = group.end;
+                }
+                None => {
+                    push(&mut formula, &self.elements[i..=i], 1);
+                    i += 1;
+                }
+            }
+        }
+        formula.push_str(&charge(self.charge));
+
+        formula
+    }
+}
+
+/// Write the charge of an ion, or nothing if it isn't one
+fn charge(charge: Option<i32>) -> String {
+    match charge {
+        None | Some(0) => String::default(),
+        Some(1) => "+".to_string(),
+        Some(-1) => "-".to_string(),
+        Some(c) if c > 0 => format!("^{}+", c),
+        Some(c) => format!("^{}-", -c),
     }
 }
 
@@ -26,11 +26,17 @@ impl SimpleElement {
 /// and the count of how many there are.
 ///
 /// Eg: O2
+///
+/// With the `serde` feature, it's (de)serialized as a [`SimpleElement`], using the symbol of
+/// the element as its name.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SimpleElement", try_from = "SimpleElement")
+)]
 pub struct Element {
     /// Chemical properties and information about this element
-    #[cfg_attr(feature = "serde", serde(skip))]
     el: &'static MendeleevElement,
     /// How many of this element there are.
     /// In O2 the count will be 2 and in 2NO3 it will be 3
@@ -93,6 +99,15 @@ impl TryFrom<SimpleElement> for Element {
     }
 }
 
+impl From<Element> for SimpleElement {
+    fn from(el: Element) -> Self {
+        Self {
+            name: el.symbol().to_string(),
+            count: el.count,
+        }
+    }
+}
+
 impl Default for Element {
     fn default() -> Self {
         Self {
@@ -126,4 +141,15 @@ mod tests {
             Err(ElementError::NotInPeriodicTable("Bill".to_string()))
         )
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let el = Element::parse("Fe3").unwrap();
+        let json = serde_json::to_string(&el).unwrap();
+        assert_eq!(json, r#"{"name":"Fe","count":3}"#);
+        assert_eq!(serde_json::from_str::<Element>(&json).unwrap(), el);
+
+        assert!(serde_json::from_str::<Element>(r#"{"name":"Bill","count":1}"#).is_err());
+    }
 }
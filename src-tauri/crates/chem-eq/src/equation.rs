@@ -107,21 +107,13 @@ impl Equation {
         let left = self
             .left
             .iter()
-            .filter(|c| {
-                c.state
-                    .as_ref()
-                    .map_or(true, |s| matches!(s, State::Aqueous | State::Gas))
-            })
+            .filter(|c| matches!(c.state, Some(State::Aqueous | State::Gas) | None))
             .map(|c| c.coefficient)
             .sum::<usize>();
         let right = self
             .right
             .iter()
-            .filter(|c| {
-                c.state
-                    .as_ref()
-                    .map_or(true, |s| matches!(s, State::Aqueous | State::Gas))
-            })
+            .filter(|c| matches!(c.state, Some(State::Aqueous | State::Gas) | None))
             .map(|c| c.coefficient)
             .sum::<usize>();
         if left == 0 && right == 0 {
@@ -327,7 +319,7 @@ impl Equation {
     /// ```
     #[cfg(feature = "balance")]
     #[cfg_attr(docsrs, doc(cfg(feature = "balance")))]
-    pub fn to_balancer(&self) -> crate::balance::EquationBalancer {
+    pub fn to_balancer(&self) -> crate::balance::EquationBalancer<'_> {
         use crate::balance::EquationBalancer;
 
         EquationBalancer::new(self)
@@ -438,6 +430,11 @@ impl Equation {
     /// assert_eq!(eq.get_concentrations(), vec![1.0, 2.0, 3.0]);
     ///
     /// assert_eq!(eq.set_concentrations(&[1.0, 34.0]), Err(ConcentrationError::WrongSliceSize));
+    /// assert_eq!(eq.set_concentrations(&[1.0, -2.0, 3.0]), Err(ConcentrationError::Negative));
+    /// assert_eq!(
+    ///     eq.set_concentrations(&[f32::INFINITY, 2.0, 3.0]),
+    ///     Err(ConcentrationError::Infinite)
+    /// );
     /// ```
     pub fn set_concentrations(&mut self, concentrations: &[f32]) -> Result<(), ConcentrationError> {
         // check assumptions
@@ -447,6 +444,12 @@ impl Equation {
         if concentrations.iter().any(|&c| c.is_nan()) {
             return Err(ConcentrationError::NAN);
         }
+        if concentrations.iter().any(|&c| c.is_infinite()) {
+            return Err(ConcentrationError::Infinite);
+        }
+        if concentrations.iter().any(|&c| c < 0.0) {
+            return Err(ConcentrationError::Negative);
+        }
 
         for (orig, new) in self.concentrations_mut().zip(concentrations.iter()) {
             *orig = *new;
@@ -537,6 +540,7 @@ impl Equation {
     ///
     /// assert_eq!(eq.set_concentration_by_name("joe", 24.0), Err(ConcentrationNameError::NotFound));
     /// assert_eq!(eq.set_concentration_by_name("H2O", f32::NAN), Err(ConcentrationNameError::NAN));
+    /// assert_eq!(eq.set_concentration_by_name("H2O", -1.0), Err(ConcentrationNameError::Negative));
     /// ```
     pub fn set_concentration_by_name(
         &mut self,
@@ -546,6 +550,12 @@ impl Equation {
         if concentration.is_nan() {
             return Err(ConcentrationNameError::NAN);
         }
+        if concentration.is_infinite() {
+            return Err(ConcentrationNameError::Infinite);
+        }
+        if concentration < 0.0 {
+            return Err(ConcentrationNameError::Negative);
+        }
         // I don't like the collecting here...
         // but I can't avoid double borrowing self as mutable and immutable
         let (_name, cmp) = self
@@ -765,6 +775,36 @@ mod tests {
         )
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut eq = Equation::new("2SO2(g) + O2(g) <-> 2SO3(g)").unwrap();
+        eq.set_concentrations(&[0.5, 0.25, 1.0 / 3.0]).unwrap();
+        eq.set_delta_h(-198.0);
+        eq.set_temperature(400.0);
+        eq.set_volume(2.0);
+
+        let json = serde_json::to_string(&eq).unwrap();
+        assert_eq!(serde_json::from_str::<Equation>(&json).unwrap(), eq);
+
+        let ion = Compound::parse("SO4^2-(aq)").unwrap();
+        let json = serde_json::to_string(&ion).unwrap();
+        assert_eq!(serde_json::from_str::<Compound>(&json).unwrap(), ion);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn parse_error_serializes() {
+        let input = "N2O4(g) <-> 2Xx(g)";
+        let err = Equation::new(input).unwrap_err();
+        assert_eq!(err.span(input), Some(13..18));
+
+        let json = serde_json::to_value(&err).unwrap();
+        let errors = json["ParsingError"].as_array().unwrap();
+        assert_eq!(errors[0]["kind"], "... while getting right side");
+        assert_eq!(errors.last().unwrap()["input"], "(g)");
+    }
+
     #[test]
     fn mol_ratio_basic() {
         let eq = Equation::new("2O2 + H2 -> H2O").unwrap();
@@ -1,6 +1,8 @@
 //! Error types for `chem-eq`
 
-use crate::parse::util::Error;
+use std::ops::Range;
+
+use crate::parse::util::{remaining_span, Error};
 
 /// Errors type for issues with chemical equations
 #[derive(thiserror::Error, Clone, PartialEq, Eq)]
@@ -8,7 +10,7 @@ use crate::parse::util::Error;
 pub enum EquationError {
     /// The string couldn't be parsed into a chemical equation
     #[error("couldn't parse the equation:\n{0}")]
-    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
     ParsingError(Error<String>),
     /// The equation is not valid. Eg: There are different elements on each side of the equation
     #[error("this equation is not valid")]
@@ -18,6 +20,27 @@ pub enum EquationError {
     TooMuchInput(String),
 }
 
+impl EquationError {
+    /// Where in `input`, the equation given to [`Equation::new`], this error happened. [`None`]
+    /// if the problem isn't in one place.
+    ///
+    /// ```rust
+    /// use chem_eq::Equation;
+    ///
+    /// let input = "2H2 + O2 -> 2H2O + &";
+    /// let err = Equation::new(input).unwrap_err();
+    /// assert_eq!(err.span(input), Some(19..20));
+    /// assert_eq!(&input[19..20], "&");
+    /// ```
+    pub fn span(&self, input: &str) -> Option<Range<usize>> {
+        match self {
+            Self::ParsingError(e) => Some(e.span(input)),
+            Self::TooMuchInput(rest) => Some(remaining_span(input, rest)),
+            Self::IncorrectEquation => None,
+        }
+    }
+}
+
 impl std::fmt::Debug for EquationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self, f)
@@ -35,7 +58,7 @@ use crate::Compound;
 pub enum CompoundError {
     /// The input couldn't be parsed into a compound
     #[error("couldn't parse the compound:\n{0}")]
-    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
     ParsingError(Error<String>),
     /// The compound was parsed, but there was remaining input
     #[error("too much input, remaining: {0:?}")]
@@ -48,6 +71,18 @@ impl std::fmt::Debug for CompoundError {
     }
 }
 
+/// Error for [`Compound::from_ions`]
+#[derive(Debug, thiserror::Error, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IonError {
+    /// The cation didn't have a positive charge
+    #[error("the cation must have a positive charge, eg. Al^3+")]
+    NotCation,
+    /// The anion didn't have a negative charge
+    #[error("the anion must have a negative charge, eg. SO4^2-")]
+    NotAnion,
+}
+
 // done for rustdoc
 #[cfg(doc)]
 #[allow(unused)]
@@ -63,6 +98,12 @@ pub enum ConcentrationError {
     /// A concentration value was NAN which is invalid
     #[error("concentration value was NAN")]
     NAN,
+    /// A concentration value was negative which is invalid
+    #[error("concentration value was negative")]
+    Negative,
+    /// A concentration value was infinite which is invalid
+    #[error("concentration value was infinite")]
+    Infinite,
 }
 
 /// Error for [`Equation::set_concentration_by_name`] and [`Equation::get_concentration_by_name`]
@@ -75,6 +116,12 @@ pub enum ConcentrationNameError {
     /// Concentration value was NAN, which is invalid
     #[error("concentration value was NAN")]
     NAN,
+    /// Concentration value was negative, which is invalid
+    #[error("concentration value was negative")]
+    Negative,
+    /// Concentration value was infinite, which is invalid
+    #[error("concentration value was infinite")]
+    Infinite,
 }
 
 // done for rustdoc
@@ -96,6 +143,33 @@ pub enum BalanceError {
     Infeasable,
 }
 
+/// Error for [`Equation::predict`]
+#[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PredictError {
+    /// The reactants couldn't be parsed
+    #[error(transparent)]
+    Equation(#[from] EquationError),
+    /// Products were given as well as reactants
+    #[error("only give the reactants, eg. `Na + Cl2 ->`")]
+    HasProducts,
+    /// The reactants aren't a kind of reaction products can be predicted for
+    #[error("the products of these reactants can't be predicted")]
+    Unknown,
+    /// An element is less reactive than the one it would replace, so there's no reaction
+    #[error("{element} is less reactive than {replaced}, so there's no reaction")]
+    LessReactive {
+        /// The free element
+        element: String,
+        /// The element in the compound
+        replaced: String,
+    },
+    /// Neither product of a double replacement is a precipitate or water, so there's no
+    /// reaction
+    #[error("all of the products are soluble, so there's no reaction")]
+    AllSoluble,
+}
+
 #[cfg(doc)]
 #[allow(unused)]
 use crate::Element;
@@ -109,7 +183,7 @@ pub enum ElementError {
     NotInPeriodicTable(String),
     /// The input could no be parsed into an element
     #[error("The element could not be parsed")]
-    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
     ParseError(Error<String>),
     /// The element was parsed, but there was remaining input
     #[error("too much input, remaining: {0:?}")]
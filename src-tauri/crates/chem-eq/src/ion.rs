@@ -0,0 +1,307 @@
+//! Polyatomic ions, and ionic compounds made from them
+//!
+//! [`POLYATOMIC_IONS`] lists the ions a chemistry student is expected to know. When a compound
+//! is parsed, ions from the table are recorded as [`Group`]s, so `NH4NO3` is known to be made of
+//! ammonium and nitrate. [`Compound::from_ions`] uses the same table to write the formula of an
+//! ionic compound.
+
+use crate::{compound::Group, error::IonError, properties::Metallicity, Compound, Element};
+
+/// An ion made of more than one atom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolyatomicIon {
+    /// The ion's name, eg. `sulfate`
+    pub name: &'static str,
+    /// How the ion is written without its charge, eg. `SO4`
+    pub formula: &'static str,
+    /// The charge of the ion, eg. -2 for sulfate
+    pub charge: i32,
+    /// Each element of the formula and its count, in order
+    elements: &'static [(&'static str, usize)],
+}
+
+macro_rules! ion {
+    ($name:literal, $formula:literal, $charge:literal, [$(($symbol:literal, $count:literal)),+]) => {
+        PolyatomicIon {
+            name: $name,
+            formula: $formula,
+            charge: $charge,
+            elements: &[$(($symbol, $count)),+],
+        }
+    };
+}
+
+/// Common polyatomic ions
+pub const POLYATOMIC_IONS: &[PolyatomicIon] = &[
+    ion!("ammonium", "NH4", 1, [("N", 1), ("H", 4)]),
+    ion!("hydronium", "H3O", 1, [("H", 3), ("O", 1)]),
+    ion!("hydroxide", "OH", -1, [("O", 1), ("H", 1)]),
+    ion!("nitrate", "NO3", -1, [("N", 1), ("O", 3)]),
+    ion!("nitrite", "NO2", -1, [("N", 1), ("O", 2)]),
+    ion!("sulfate", "SO4", -2, [("S", 1), ("O", 4)]),
+    ion!("sulfite", "SO3", -2, [("S", 1), ("O", 3)]),
+    ion!(
+        "hydrogen sulfate",
+        "HSO4",
+        -1,
+        [("H", 1), ("S", 1), ("O", 4)]
+    ),
+    ion!("phosphate", "PO4", -3, [("P", 1), ("O", 4)]),
+    ion!(
+        "hydrogen phosphate",
+        "HPO4",
+        -2,
+        [("H", 1), ("P", 1), ("O", 4)]
+    ),
+    ion!(
+        "dihydrogen phosphate",
+        "H2PO4",
+        -1,
+        [("H", 2), ("P", 1), ("O", 4)]
+    ),
+    ion!("carbonate", "CO3", -2, [("C", 1), ("O", 3)]),
+    ion!(
+        "hydrogen carbonate",
+        "HCO3",
+        -1,
+        [("H", 1), ("C", 1), ("O", 3)]
+    ),
+    ion!(
+        "acetate",
+        "CH3COO",
+        -1,
+        [("C", 1), ("H", 3), ("C", 1), ("O", 1), ("O", 1)]
+    ),
+    ion!("oxalate", "C2O4", -2, [("C", 2), ("O", 4)]),
+    ion!("cyanide", "CN", -1, [("C", 1), ("N", 1)]),
+    ion!("thiocyanate", "SCN", -1, [("S", 1), ("C", 1), ("N", 1)]),
+    ion!("hypochlorite", "ClO", -1, [("Cl", 1), ("O", 1)]),
+    ion!("chlorite", "ClO2", -1, [("Cl", 1), ("O", 2)]),
+    ion!("chlorate", "ClO3", -1, [("Cl", 1), ("O", 3)]),
+    ion!("perchlorate", "ClO4", -1, [("Cl", 1), ("O", 4)]),
+    ion!("permanganate", "MnO4", -1, [("Mn", 1), ("O", 4)]),
+    ion!("chromate", "CrO4", -2, [("Cr", 1), ("O", 4)]),
+    ion!("dichromate", "Cr2O7", -2, [("Cr", 2), ("O", 7)]),
+];
+
+impl PolyatomicIon {
+    /// Get an ion from [`POLYATOMIC_IONS`] by its name, eg. `"nitrate"`
+    pub fn by_name(name: &str) -> Option<&'static Self> {
+        POLYATOMIC_IONS
+            .iter()
+            .find(|ion| ion.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Get an ion from [`POLYATOMIC_IONS`] by its formula without a charge, eg. `"NO3"`
+    pub fn by_formula(formula: &str) -> Option<&'static Self> {
+        POLYATOMIC_IONS.iter().find(|ion| ion.formula == formula)
+    }
+
+    /// Find the ion that `count` of make up exactly `elements`
+    pub(crate) fn find(elements: &[Element], count: usize) -> Option<&'static Self> {
+        POLYATOMIC_IONS.iter().find(|ion| {
+            ion.elements.len() == elements.len()
+                && ion
+                    .elements
+                    .iter()
+                    .zip(elements)
+                    .all(|(&(symbol, n), el)| el.symbol() == symbol && el.count == n * count)
+        })
+    }
+}
+
+/// Record the polyatomic ions of a parsed compound that weren't bracketed. A compound that's
+/// an ion itself, like `NH4+`, is one group. Otherwise the compound must start with a cation,
+/// either a metal, hydrogen or ammonium, and the rest of it is the anion.
+pub(crate) fn recognise(cmp: &mut Compound) {
+    let elements = &cmp.elements;
+    let whole = Group {
+        start: 0,
+        end: elements.len(),
+        count: 1,
+    };
+    if cmp.groups.is_empty() && elements.len() > 1 {
+        if let (Some(charge), Some(ion)) = (cmp.charge, PolyatomicIon::find(elements, 1)) {
+            if ion.charge == charge {
+                cmp.groups.push(whole);
+                return;
+            }
+        }
+    }
+
+    let ammonium = PolyatomicIon::by_formula("NH4").unwrap();
+    let mut groups = vec![];
+    let start = match cmp.groups.first() {
+        Some(group) if group.start == 0 => group.end,
+        _ if elements.len() > 2 && PolyatomicIon::find(&elements[..2], 1) == Some(ammonium) => {
+            groups.push(Group {
+                start: 0,
+                end: 2,
+                count: 1,
+            });
+            2
+        }
+        _ if matches!(elements.first(), Some(e) if e.symbol() == "H"
+            || e.metallicity() == Metallicity::Metal) =>
+        {
+            1
+        }
+        _ => return,
+    };
+
+    let bracketed = cmp.groups.iter().any(|g| g.start == start);
+    if !bracketed && start < elements.len() && PolyatomicIon::find(&elements[start..], 1).is_some()
+    {
+        groups.push(Group {
+            start,
+            end: elements.len(),
+            count: 1,
+        });
+    }
+    cmp.groups.extend(groups);
+    cmp.groups.sort();
+}
+
+impl Compound {
+    /// Make the neutral compound of a cation and an anion, using as few of each as balances
+    /// their charges. A polyatomic ion is bracketed if there's more than one of it. An ion
+    /// without a charge is looked up in [`POLYATOMIC_IONS`].
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Compound;
+    ///
+    /// let al = Compound::parse("Al^3+").unwrap();
+    /// let sulfate = Compound::parse("SO4^2-").unwrap();
+    /// let cmp = Compound::from_ions(&al, &sulfate).unwrap();
+    /// assert_eq!(cmp.formula(), "Al2(SO4)3");
+    ///
+    /// let ammonium = Compound::parse("NH4").unwrap();
+    /// let cl = Compound::parse("Cl-").unwrap();
+    /// assert_eq!(Compound::from_ions(&ammonium, &cl).unwrap().formula(), "NH4Cl");
+    /// ```
+    pub fn from_ions(cation: &Compound, anion: &Compound) -> Result<Self, IonError> {
+        let cation_charge = ion_charge(cation)
+            .filter(|&c| c > 0)
+            .ok_or(IonError::NotCation)?;
+        let anion_charge = ion_charge(anion)
+            .filter(|&c| c < 0)
+            .ok_or(IonError::NotAnion)?
+            .abs();
+
+        let lcm = lcm(cation_charge, anion_charge);
+        let mut cmp = Self {
+            coefficient: 1,
+            ..Default::default()
+        };
+        for (ion, count) in [(cation, lcm / cation_charge), (anion, lcm / anion_charge)] {
+            let count = count as usize;
+            let start = cmp.elements.len();
+            cmp.elements.extend(ion.elements.iter().map(|el| {
+                let mut el = el.clone();
+                el.count *= count;
+                el
+            }));
+            let polyatomic = ion.elements.len() > 1 || ion.elements.iter().any(|e| e.count > 1);
+            if polyatomic {
+                cmp.groups.push(Group {
+                    start,
+                    end: cmp.elements.len(),
+                    count,
+                });
+            }
+        }
+
+        Ok(cmp)
+    }
+}
+
+/// The charge of an ion, from its formula or [`POLYATOMIC_IONS`]
+fn ion_charge(ion: &Compound) -> Option<i32> {
+    ion.charge
+        .or_else(|| PolyatomicIon::find(&ion.elements, 1).map(|ion| ion.charge))
+}
+
+fn lcm(a: i32, b: i32) -> i32 {
+    let gcd = |mut a: i32, mut b: i32| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ionic(cation: &str, anion: &str) -> Result<String, IonError> {
+        let cation = Compound::parse(cation).unwrap();
+        let anion = Compound::parse(anion).unwrap();
+        Compound::from_ions(&cation, &anion).map(|cmp| cmp.formula())
+    }
+
+    fn ions(cmp: &str) -> Vec<&'static str> {
+        let cmp = Compound::parse(cmp).unwrap();
+        cmp.groups
+            .iter()
+            .filter_map(|g| g.ion(&cmp))
+            .map(|ion| ion.name)
+            .collect()
+    }
+
+    #[test]
+    fn balance_charges() {
+        assert_eq!(ionic("Na+", "Cl-").unwrap(), "NaCl");
+        assert_eq!(ionic("Ca^2+", "Cl-").unwrap(), "CaCl2");
+        assert_eq!(ionic("Al^3+", "O^2-").unwrap(), "Al2O3");
+        assert_eq!(ionic("Ca^2+", "PO4^3-").unwrap(), "Ca3(PO4)2");
+        assert_eq!(ionic("NH4+", "SO4^2-").unwrap(), "(NH4)2SO4");
+        assert_eq!(ionic("Mg^2+", "OH").unwrap(), "Mg(OH)2");
+        assert_eq!(ionic("Pb^4+", "O^2-").unwrap(), "PbO2");
+    }
+
+    #[test]
+    fn wrong_charges() {
+        assert_eq!(ionic("Cl-", "Na+"), Err(IonError::NotCation));
+        assert_eq!(ionic("Na", "Cl-"), Err(IonError::NotCation));
+        assert_eq!(ionic("Na+", "Cl"), Err(IonError::NotAnion));
+    }
+
+    #[test]
+    fn formula_parses_back() {
+        let built = Compound::from_ions(
+            &Compound::parse("Fe^3+").unwrap(),
+            &Compound::parse("SO4^2-").unwrap(),
+        )
+        .unwrap();
+        let parsed = Compound::parse(&built.formula()).unwrap();
+        assert_eq!(parsed, built);
+        assert_eq!(parsed.groups, built.groups);
+    }
+
+    #[test]
+    fn recognise_ions() {
+        assert_eq!(ions("NH4NO3"), vec!["ammonium", "nitrate"]);
+        assert_eq!(ions("NaHCO3"), vec!["hydrogen carbonate"]);
+        assert_eq!(ions("H2SO4"), vec!["sulfate"]);
+        assert_eq!(ions("KMnO4"), vec!["permanganate"]);
+        assert_eq!(ions("Ca(OH)2"), vec!["hydroxide"]);
+        assert_eq!(ions("SO4^2-"), vec!["sulfate"]);
+        assert_eq!(ions("Pb(CH3COO)2"), vec!["acetate"]);
+
+        // not ions, even if they look like one
+        assert!(ions("NO2").is_empty());
+        assert!(ions("CH3OH").is_empty());
+        assert!(ions("Fe2O3").is_empty());
+    }
+
+    #[test]
+    fn lookup() {
+        assert_eq!(PolyatomicIon::by_name("Nitrate").unwrap().formula, "NO3");
+        assert_eq!(PolyatomicIon::by_formula("PO4").unwrap().charge, -3);
+        assert_eq!(PolyatomicIon::by_name("unobtainium"), None);
+    }
+}
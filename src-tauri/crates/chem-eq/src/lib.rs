@@ -12,7 +12,7 @@
 use std::str::FromStr;
 
 pub use crate::{
-    compound::Compound,
+    compound::{Compound, Group},
     element::Element,
     equation::{Equation, ReactionQuotient},
 };
@@ -20,12 +20,16 @@ pub use crate::{
 #[cfg(feature = "balance")]
 #[cfg_attr(docsrs, doc(cfg(feature = "balance")))]
 pub mod balance;
+pub mod classify;
 mod compound;
 mod display;
 mod element;
 mod equation;
 pub mod error;
+pub mod ion;
 mod parse;
+pub mod predict;
+pub mod properties;
 
 /// Avagadro's number, approximately equal to 6.02 * 10^23
 pub const AVAGADRO_CONSTANT: f64 = 6.02214e23;
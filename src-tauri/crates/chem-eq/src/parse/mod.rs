@@ -5,7 +5,7 @@ pub mod util;
 use std::str::FromStr;
 
 use nom::{
-    bytes::complete::{tag, take_till1, take_while, take_while1},
+    bytes::complete::{tag, take_while, take_while1},
     character::complete::{anychar, digit0, multispace0},
     combinator::{map, map_opt, map_res, opt, peek, verify},
     error::{context, Error as NomError, ErrorKind as NomErrorKind},
@@ -14,7 +14,9 @@ use nom::{
 };
 
 use crate::{
+    compound::Group,
     element::SimpleElement,
+    ion,
     parse::util::{Error, ErrorKind, Input, Result},
     Compound, Direction, Element, Equation, State,
 };
@@ -22,10 +24,7 @@ use crate::{
 /// Parse an [`Equation`] from a str
 pub fn parse_equation(orig_i: Input) -> Result<Equation> {
     // get the left side of the equals
-    let (i, lhs) = context(
-        "splitting equation",
-        take_till1(|c: char| c == '<' || c == '-'),
-    )(orig_i)?;
+    let (i, lhs) = context("splitting equation", take_till_arrow)(orig_i)?;
 
     // get the direction of reaction
     let (rhs, tag) = context(
@@ -37,7 +36,12 @@ pub fn parse_equation(orig_i: Input) -> Result<Equation> {
 
     // parse either side
     let (_, left_cmp) = context("left side", parse_side)(lhs)?;
-    let (i, right_cmp) = context("right side", parse_side)(rhs)?;
+    // the right side can be left empty to predict the products
+    let (i, right_cmp) = if rhs.trim().is_empty() {
+        (rhs, vec![])
+    } else {
+        context("right side", parse_side)(rhs)?
+    };
 
     // clear trailing whitespace
     let mut orig_i = orig_i.to_string();
@@ -111,7 +115,7 @@ pub fn parse_element(orig_i: Input) -> Result<Element> {
 /// Parse a [`Compound`] from an input
 pub fn parse_compound(i: Input) -> Result<Compound> {
     // get prefix of compound and extra elements
-    let (i, (num, elements)) = tuple((
+    let (i, (num, (elements, groups))) = tuple((
         // optional coefficient
         context(
             "compound coefficient",
@@ -120,12 +124,27 @@ pub fn parse_compound(i: Input) -> Result<Compound> {
         // get all the elements
         context(
             "optionally bracketed elements",
-            map(many1(bracketed_elements), |v| {
-                v.into_iter().flatten().collect::<Vec<_>>()
+            map(many1(bracketed_elements), |parts| {
+                let mut elements = vec![];
+                let mut groups = vec![];
+                for (els, count) in parts {
+                    if let Some(count) = count {
+                        groups.push(Group {
+                            start: elements.len(),
+                            end: elements.len() + els.len(),
+                            count,
+                        });
+                    }
+                    elements.extend(els);
+                }
+                (elements, groups)
             }),
         ),
     ))(i)?;
 
+    // get charge of compound if it's an ion
+    let (i, charge) = context("compound charge", opt(parse_charge))(i)?;
+
     // get state of compound
     let (i, state) = match delimited(
         context(
@@ -152,19 +171,22 @@ pub fn parse_compound(i: Input) -> Result<Compound> {
         }
     };
 
-    Ok((
-        i,
-        Compound {
-            elements,
-            coefficient: num.unwrap_or(1),
-            state,
-            concentration: 0.0,
-        },
-    ))
+    let mut cmp = Compound {
+        elements,
+        groups,
+        coefficient: num.unwrap_or(1),
+        state,
+        charge,
+        concentration: 0.0,
+    };
+    ion::recognise(&mut cmp);
+
+    Ok((i, cmp))
 }
 
-/// Parse elements that are bracketed with a coefficient on the end
-fn bracketed_elements(orig_i: Input) -> Result<Vec<Element>> {
+/// Parse elements that are bracketed with a coefficient on the end, returning the coefficient
+/// if they were bracketed
+fn bracketed_elements(orig_i: Input) -> Result<(Vec<Element>, Option<usize>)> {
     // get bracket
     let (i, b) = peek(anychar)(orig_i)?;
 
@@ -193,7 +215,7 @@ fn bracketed_elements(orig_i: Input) -> Result<Vec<Element>> {
     let (i, b) = match peek(anychar::<_, NomError<&str>>)(i) {
         Ok((i, b)) => (i, b),
         Err(e) => match e {
-            nom::Err::Error(e) if e.code == NomErrorKind::Eof => return Ok((i, elements)),
+            nom::Err::Error(e) if e.code == NomErrorKind::Eof => return Ok((i, (elements, None))),
             nom::Err::Error(e) => return Err(nom::Err::Error(e.into())),
             nom::Err::Failure(e) => return Err(nom::Err::Failure(e.into())),
             // not using streaming parsers
@@ -214,11 +236,65 @@ fn bracketed_elements(orig_i: Input) -> Result<Vec<Element>> {
     };
 
     // multiply each element's count by the coefficient
+    let count = coef.unwrap_or(1);
     for el in &mut elements {
-        el.count *= coef.as_ref().unwrap_or(&1);
+        el.count *= count;
+    }
+
+    Ok((i, (elements, deep.then_some(count))))
+}
+
+/// Take input until the arrow of the equation. Unlike the signs of a charge, `<` always begins
+/// an arrow and `-` only does if it is followed by `>`.
+fn take_till_arrow(i: Input) -> Result<Input> {
+    let idx = i
+        .char_indices()
+        .find(|&(idx, c)| c == '<' || (c == '-' && i[idx + 1..].starts_with('>')))
+        .map_or(i.len(), |(idx, _)| idx);
+
+    if idx == 0 {
+        return Err(nom::Err::Error(
+            NomError::new(i, NomErrorKind::TakeTill1).into(),
+        ));
     }
 
-    Ok((i, elements))
+    Ok((&i[idx..], &i[..idx]))
+}
+
+/// Parse the charge of an ion. Either a lone sign for a charge of one (`Ag+`, `Cl-`) or a caret
+/// followed by the magnitude and sign (`SO4^2-`, `Al^3+`).
+///
+/// A lone sign is only a charge if it ends the compound, so that `H2+O2` is still two compounds.
+fn parse_charge(i: Input) -> Result<i32> {
+    let (i, magnitude) = match tag::<_, _, Error<&str>>("^")(i) {
+        Ok((i, _)) => context(
+            "charge magnitude",
+            map_opt(digit0, |s: &str| {
+                if s.is_empty() {
+                    Some(1)
+                } else {
+                    s.parse::<i32>().ok()
+                }
+            }),
+        )(i)?,
+        Err(_) => {
+            // lone sign must be followed by a state, whitespace or the end of input
+            let next = i.chars().nth(1);
+            if matches!(next, Some(c) if c != '(' && !c.is_whitespace()) {
+                return Err(nom::Err::Error(
+                    NomError::new(i, NomErrorKind::Verify).into(),
+                ));
+            }
+            (i, 1)
+        }
+    };
+
+    let (i, sign) = context(
+        "charge sign",
+        verify(anychar, |c: &char| *c == '+' || *c == '-'),
+    )(i)?;
+
+    Ok((i, if sign == '-' { -magnitude } else { magnitude }))
 }
 
 /// Parse a compound and an optional "+"
@@ -974,3 +974,88 @@ fn kitchen_sink() {
         Ok(("", eq))
     );
 }
+
+#[test]
+fn compound_lone_charge() {
+    let cmp = Compound {
+        elements: vec![SimpleElement {
+            name: "Ag".to_owned(),
+            count: 1,
+        }
+        .into_element()
+        .unwrap()],
+        coefficient: 1,
+        state: Some(State::Aqueous),
+        charge: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(parse_compound("Ag+(aq)"), Ok(("", cmp)));
+}
+
+#[test]
+fn compound_caret_charge() {
+    let cmp = Compound {
+        elements: vec![
+            SimpleElement {
+                name: "S".to_owned(),
+                count: 1,
+            }
+            .into_element()
+            .unwrap(),
+            SimpleElement {
+                name: "O".to_owned(),
+                count: 4,
+            }
+            .into_element()
+            .unwrap(),
+        ],
+        coefficient: 3,
+        charge: Some(-2),
+        ..Default::default()
+    };
+    assert_eq!(parse_compound("3SO4^2-"), Ok(("", cmp)));
+}
+
+#[test]
+fn compound_plus_is_not_charge() {
+    let (i, cmp) = parse_compound("H2+O2").unwrap();
+    assert_eq!(i, "+O2");
+    assert_eq!(cmp.charge, None);
+}
+
+#[test]
+fn equation_with_ions() {
+    let eq = parse_equation("AgCl(s) <-> Ag+(aq) + Cl-(aq)").unwrap().1;
+    assert_eq!(eq.direction, Direction::Reversible);
+    assert_eq!(eq.left.len(), 1);
+    assert_eq!(
+        eq.right.iter().map(|c| c.charge).collect::<Vec<_>>(),
+        vec![Some(1), Some(-1)]
+    );
+    assert_eq!(
+        eq.compound_names().collect::<Vec<_>>(),
+        vec!["AgCl(s)", "Ag+(aq)", "Cl-(aq)"]
+    );
+}
+
+#[test]
+fn equation_empty_right_side() {
+    let eq = parse_equation("Na + Cl2 -> ").unwrap().1;
+    assert_eq!(eq.left.len(), 2);
+    assert!(eq.right.is_empty());
+    assert_eq!(eq.equation, "Na + Cl2 ->");
+
+    // still not a valid equation on its own
+    assert_eq!(
+        Equation::new("Na + Cl2 ->"),
+        Err(crate::error::EquationError::IncorrectEquation)
+    );
+}
+
+#[test]
+fn equation_ion_before_arrow() {
+    let eq = parse_equation("Ag+ + Cl- -> AgCl").unwrap().1;
+    assert_eq!(eq.direction, Direction::Right);
+    assert_eq!(eq.left.len(), 2);
+    assert_eq!(eq.right.len(), 1);
+}
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, ops::Range};
 
 use itertools::Itertools;
 use nom::{
@@ -33,6 +33,54 @@ where
     }
 }
 
+impl Error<String> {
+    /// Where in `input` parsing stopped, as the range of the word it couldn't parse
+    pub fn span(&self, input: &str) -> Range<usize> {
+        // errors go from innermost out, and the innermost context is the start of what
+        // couldn't be parsed
+        let rest = self
+            .errors
+            .iter()
+            .find(|(_, kind)| matches!(kind, ErrorKind::Context(_)))
+            .or_else(|| self.errors.first())
+            .map_or("", |(i, _)| i.as_str());
+        remaining_span(input, rest)
+    }
+}
+
+/// The range of the first word of `rest`, which is the end of `input`
+pub(crate) fn remaining_span(input: &str, rest: &str) -> Range<usize> {
+    let start = input.len().saturating_sub(rest.len());
+    let rest = input.get(start..).unwrap_or_default();
+    let len = rest
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(rest.len(), |(i, _)| i);
+
+    start..start + len
+}
+
+#[cfg(feature = "serde")]
+impl<I: serde::Serialize> serde::Serialize for Error<I> {
+    /// Serialized as a list of where each error happened and what it was, outermost first
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct Entry<'a, I> {
+            input: &'a I,
+            kind: String,
+        }
+
+        serializer.collect_seq(self.errors.iter().rev().map(|(input, kind)| Entry {
+            input,
+            kind: kind.to_string(),
+        }))
+    }
+}
+
 impl<I> fmt::Debug for Error<I>
 where
     I: fmt::Display + fmt::Debug,
@@ -0,0 +1,587 @@
+//! Predicting the products of simple reactions from their reactants
+//!
+//! [`Equation::predict`] covers synthesis from two elements, combustion of hydrocarbons, single
+//! replacement using [`ACTIVITY_SERIES`] and [`HALOGEN_SERIES`], and double replacement using
+//! solubility rules. The products it predicts aren't balanced, so the equation is usually
+//! passed to the balancer next.
+
+use std::ops::Range;
+
+use crate::{
+    classify::{cation_end, is_element},
+    error::{EquationError, PredictError},
+    parse,
+    properties::{BondType, Metallicity},
+    Compound, Direction, Element, Equation, State,
+};
+
+// Used for rustdoc
+#[cfg(all(doc, feature = "balance"))]
+#[allow(unused)]
+use crate::balance::EquationBalancer;
+
+/// Metals and hydrogen from most to least reactive. A metal replaces any after it from a
+/// compound.
+pub const ACTIVITY_SERIES: &[&str] = &[
+    "Li", "K", "Ba", "Sr", "Ca", "Na", "Mg", "Al", "Mn", "Zn", "Cr", "Fe", "Cd", "Co", "Ni", "Sn",
+    "Pb", "H", "Cu", "Ag", "Hg", "Pt", "Au",
+];
+
+/// Halogens from most to least reactive. A halogen replaces any after it from a compound.
+pub const HALOGEN_SERIES: &[&str] = &["F", "Cl", "Br", "I"];
+
+/// The last metal in [`ACTIVITY_SERIES`] that replaces hydrogen from cold water
+const WATER_REACTIVE: &str = "Na";
+
+/// The usual charge of metal ions that can have more than one
+const CATION_CHARGES: &[(&str, i32)] = &[
+    ("Ag", 1),
+    ("Zn", 2),
+    ("Cd", 2),
+    ("Cu", 2),
+    ("Ni", 2),
+    ("Co", 2),
+    ("Mn", 2),
+    ("Sn", 2),
+    ("Pb", 2),
+    ("Hg", 2),
+    ("Fe", 3),
+    ("Cr", 3),
+    ("Au", 3),
+];
+
+/// Elements made of two atoms when they're on their own
+const DIATOMIC: &[&str] = &["H", "N", "O", "F", "Cl", "Br", "I"];
+
+impl Equation {
+    /// Predict the products of two reactants, like `Na + Cl2 ->`. The products aren't
+    /// balanced, and get states if the reactants have them. Products of a double replacement
+    /// always get states, since the precipitate is found with solubility rules.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Equation;
+    ///
+    /// let eq = Equation::predict("Na + Cl2 ->").unwrap();
+    /// assert_eq!(eq.equation(), "Na + Cl2 -> NaCl");
+    ///
+    /// let eq = Equation::predict("AgNO3(aq) + NaCl(aq) ->").unwrap();
+    /// assert_eq!(eq.equation(), "AgNO3(aq) + NaCl(aq) -> AgCl(s) + NaNO3(aq)");
+    /// ```
+    ///
+    /// Balance the prediction with [`EquationBalancer`]:
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "balance")]
+    /// # {
+    /// use chem_eq::Equation;
+    ///
+    /// let eq = Equation::predict("C3H8 + O2 ->").unwrap();
+    /// assert_eq!(eq.equation(), "C3H8 + O2 -> CO2 + H2O");
+    /// let balanced = eq.to_balancer().balance().unwrap();
+    /// assert_eq!(balanced.equation(), "C3H8 + 5O2 -> 3CO2 + 4H2O");
+    /// # }
+    /// ```
+    pub fn predict(reactants: &str) -> Result<Self, PredictError> {
+        let eq = match parse::parse_equation(reactants) {
+            Ok((i, _)) if !i.trim().is_empty() => {
+                return Err(EquationError::TooMuchInput(i.to_string()).into())
+            }
+            Ok((_, eq)) => eq,
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                return Err(EquationError::ParsingError(e.into()).into())
+            }
+            // no streaming parsers were used
+            Err(nom::Err::Incomplete(_)) => unreachable!(),
+        };
+        if !eq.right.is_empty() || eq.direction == Direction::Left {
+            return Err(PredictError::HasProducts);
+        }
+        let [a, b] = eq.left.as_slice() else {
+            return Err(PredictError::Unknown);
+        };
+
+        let right = products(a, b)?;
+        let side = |side: &[Compound]| side.iter().map(name).collect::<Vec<_>>().join(" + ");
+        let equation = format!("{} {} {}", side(&eq.left), eq.direction, side(&right));
+
+        Ok(Self {
+            right,
+            equation,
+            ..eq
+        })
+    }
+}
+
+impl Compound {
+    /// Check if an ionic compound dissolves in water using the solubility rules, or [`None`]
+    /// if it isn't made of a cation and an anion
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Compound;
+    ///
+    /// let soluble = |cmp: &str| Compound::parse(cmp).unwrap().is_soluble();
+    /// assert_eq!(soluble("NaCl"), Some(true));
+    /// assert_eq!(soluble("AgCl"), Some(false));
+    /// assert_eq!(soluble("CaCO3"), Some(false));
+    /// assert_eq!(soluble("CH4"), None);
+    /// ```
+    pub fn is_soluble(&self) -> Option<bool> {
+        let (cation, anion) = ions(self)?;
+        Some(soluble(&cation, &anion))
+    }
+}
+
+/// The products of two reactants
+fn products(a: &Compound, b: &Compound) -> Result<Vec<Compound>, PredictError> {
+    let states = a.state.is_some() || b.state.is_some();
+    let oxygen = |c: &Compound| c.formula() == "O2";
+
+    let products = if (is_fuel(a) && oxygen(b)) || (oxygen(a) && is_fuel(b)) {
+        vec![
+            with_state(known("CO2"), states, State::Gas),
+            with_state(known("H2O"), states, State::Gas),
+        ]
+    } else {
+        match (is_element(a), is_element(b)) {
+            (true, true) => vec![synthesis(&a.elements[0], &b.elements[0], states)?],
+            (true, false) => single_replacement(a, b, states)?,
+            (false, true) => single_replacement(b, a, states)?,
+            (false, false) => double_replacement(a, b)?,
+        }
+    };
+    Ok(products)
+}
+
+/// A compound of two elements, like `Na + Cl2 -> NaCl`
+fn synthesis(a: &Element, b: &Element, states: bool) -> Result<Compound, PredictError> {
+    let metal = |e: &Element| e.metallicity() == Metallicity::Metal;
+    let (cation, anion) = match (a, b) {
+        (a, b) if metal(a) && !metal(b) => (a, b),
+        (a, b) if metal(b) && !metal(a) => (b, a),
+        (a, b) if a.symbol() == "H" && a.symbol() != b.symbol() => (a, b),
+        (a, b) if b.symbol() == "H" && a.symbol() != b.symbol() => (b, a),
+        _ => return Err(PredictError::Unknown),
+    };
+    let charge = cation_charge(cation).ok_or(PredictError::Unknown)?;
+    let anion_charge = anion_charge(anion).ok_or(PredictError::Unknown)?;
+
+    let mut cmp = Compound::from_ions(&ion(cation, charge), &ion(anion, anion_charge))
+        .map_err(|_| PredictError::Unknown)?;
+    // hydrides of carbon and nitrogen are written backwards, like CH4 and NH3
+    if cation.symbol() == "H" && matches!(group(anion), Some(14 | 15)) {
+        cmp.elements.reverse();
+    }
+    let state = match cmp.bond_type() {
+        BondType::Ionic => State::Solid,
+        _ if cmp.formula() == "H2O" => State::Liquid,
+        _ => State::Gas,
+    };
+
+    Ok(with_state(cmp, states, state))
+}
+
+/// An element replacing another in a compound, like `Zn + CuSO4 -> ZnSO4 + Cu`
+fn single_replacement(
+    element: &Compound,
+    compound: &Compound,
+    states: bool,
+) -> Result<Vec<Compound>, PredictError> {
+    let el = &element.elements[0];
+    let (cation, anion) = ions(compound).ok_or(PredictError::Unknown)?;
+    let less_reactive = |replaced: &str| PredictError::LessReactive {
+        element: el.symbol().to_string(),
+        replaced: replaced.to_string(),
+    };
+    let rank = |series: &[&str], symbol: &str| series.iter().position(|&s| s == symbol);
+
+    let (new, freed) = if HALOGEN_SERIES.contains(&el.symbol()) {
+        let [replaced] = anion.elements.as_slice() else {
+            return Err(PredictError::Unknown);
+        };
+        match (
+            rank(HALOGEN_SERIES, el.symbol()),
+            rank(HALOGEN_SERIES, replaced.symbol()),
+        ) {
+            (Some(a), Some(b)) if a < b => {}
+            (Some(_), Some(_)) => return Err(less_reactive(replaced.symbol())),
+            _ => return Err(PredictError::Unknown),
+        }
+
+        (
+            Compound::from_ions(&cation, &ion(el, -1)),
+            replaced.symbol(),
+        )
+    } else {
+        let [replaced] = cation.elements.as_slice() else {
+            return Err(PredictError::Unknown);
+        };
+        let limit = match compound.formula().as_str() {
+            "H2O" => rank(ACTIVITY_SERIES, WATER_REACTIVE).map(|n| n + 1),
+            _ => rank(ACTIVITY_SERIES, replaced.symbol()),
+        };
+        match (rank(ACTIVITY_SERIES, el.symbol()), limit) {
+            (Some(a), Some(b)) if a < b => {}
+            (Some(_), Some(_)) => return Err(less_reactive(replaced.symbol())),
+            _ => return Err(PredictError::Unknown),
+        }
+        let charge = cation_charge(el).ok_or(PredictError::Unknown)?;
+
+        (
+            Compound::from_ions(&ion(el, charge), &anion),
+            replaced.symbol(),
+        )
+    };
+    let new = new.map_err(|_| PredictError::Unknown)?;
+    let freed = Compound::parse(freed).map(|cmp| free_element(cmp, states));
+
+    Ok(vec![
+        with_state(new, states, State::Aqueous),
+        freed.map_err(|_| PredictError::Unknown)?,
+    ])
+}
+
+/// Two ionic compounds swapping ions, like `AgNO3 + NaCl -> AgCl + NaNO3`. Products get their
+/// state from the solubility rules.
+fn double_replacement(a: &Compound, b: &Compound) -> Result<Vec<Compound>, PredictError> {
+    let ((a_cation, a_anion), (b_cation, b_anion)) = match (ions(a), ions(b)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Err(PredictError::Unknown),
+    };
+    if a_cation.elements == b_cation.elements {
+        return Err(PredictError::Unknown);
+    }
+
+    let combine = |cation: &Compound, anion: &Compound| {
+        if cation.formula() == "H+" && anion.formula() == "OH-" {
+            return Ok(with_state(known("H2O"), true, State::Liquid));
+        }
+        let state = match soluble(cation, anion) {
+            true => State::Aqueous,
+            false => State::Solid,
+        };
+        Compound::from_ions(cation, anion)
+            .map(|cmp| with_state(cmp, true, state))
+            .map_err(|_| PredictError::Unknown)
+    };
+    let mut products = vec![combine(&a_cation, &b_anion)?, combine(&b_cation, &a_anion)?];
+    // water is written after the salt
+    products.sort_by_key(|c| c.formula() == "H2O");
+    if products.iter().all(|c| c.state == Some(State::Aqueous)) {
+        return Err(PredictError::AllSoluble);
+    }
+
+    Ok(products)
+}
+
+/// The cation and anion of an ionic compound, with their charges. Water is H+ and OH-.
+fn ions(cmp: &Compound) -> Option<(Compound, Compound)> {
+    if cmp.charge.unwrap_or(0) != 0 {
+        return None;
+    }
+    if cmp.formula() == "H2O" {
+        let hydroxide = Compound {
+            charge: Some(-1),
+            ..known("OH")
+        };
+        return Some((ion(&cmp.elements[0], 1), hydroxide));
+    }
+
+    let split = cation_end(cmp)?;
+    let states = cmp.oxidation_states()?;
+    let part = |range: Range<usize>| {
+        let units = match cmp
+            .groups
+            .iter()
+            .find(|g| g.start == range.start && g.end == range.end)
+        {
+            Some(group) => group.count,
+            None if range.len() == 1 => cmp.elements[range.start].count,
+            None => 1,
+        };
+        let elements = &cmp.elements[range.clone()];
+        let charge = elements
+            .iter()
+            .zip(&states[range])
+            .map(|(el, state)| state * el.count as f32)
+            .sum::<f32>()
+            / units as f32;
+        if (charge - charge.round()).abs() > 1e-3 {
+            return None;
+        }
+
+        Some(Compound {
+            elements: elements
+                .iter()
+                .map(|el| {
+                    let mut el = el.clone();
+                    el.count /= units;
+                    el
+                })
+                .collect(),
+            charge: Some(charge.round() as i32),
+            coefficient: 1,
+            ..Default::default()
+        })
+    };
+
+    let cation = part(0..split)?;
+    let anion = part(split..cmp.elements.len())?;
+    (cation.charge > Some(0) && anion.charge < Some(0)).then_some((cation, anion))
+}
+
+/// Check if a cation and anion dissolve in water together
+fn soluble(cation: &Compound, anion: &Compound) -> bool {
+    let key = |cmp: &Compound| {
+        cmp.elements
+            .iter()
+            .map(|e| match e.count {
+                1 => e.symbol().to_string(),
+                n => format!("{}{}", e.symbol(), n),
+            })
+            .collect::<String>()
+    };
+    let (cation, anion) = (key(cation), key(anion));
+    let cation = cation.as_str();
+
+    // group 1, ammonium and hydrogen compounds always dissolve
+    let alkali = matches!(Element::parse(cation), Ok(e) if group(&e) == Some(1));
+    if alkali || cation == "NH4" {
+        return true;
+    }
+    match anion.as_str() {
+        "NO3" | "CH3COO" | "ClO3" | "ClO4" | "HCO3" | "MnO4" => true,
+        "Cl" | "Br" | "I" => !["Ag", "Pb", "Hg"].contains(&cation),
+        "F" => !["Mg", "Ca", "Sr", "Ba", "Pb"].contains(&cation),
+        "SO4" => !["Ca", "Sr", "Ba", "Pb", "Ag", "Hg"].contains(&cation),
+        "OH" => ["Ca", "Sr", "Ba"].contains(&cation),
+        // carbonates, phosphates, sulfides, oxides and the rest
+        _ => false,
+    }
+}
+
+/// A single atom ion of an element
+fn ion(el: &Element, charge: i32) -> Compound {
+    let mut el = el.clone();
+    el.count = 1;
+    Compound {
+        elements: vec![el],
+        charge: Some(charge),
+        coefficient: 1,
+        ..Default::default()
+    }
+}
+
+/// The usual charge of an element's cation
+fn cation_charge(el: &Element) -> Option<i32> {
+    if let Some(&(_, charge)) = CATION_CHARGES.iter().find(|(s, _)| *s == el.symbol()) {
+        return Some(charge);
+    }
+    match group(el) {
+        Some(n @ (1 | 2)) => Some(n as i32),
+        Some(13) => Some(3),
+        _ => el
+            .common_oxidation_states()
+            .iter()
+            .find(|&&s| s > 0)
+            .map(|&s| s as i32),
+    }
+}
+
+/// The usual charge of an element's anion
+fn anion_charge(el: &Element) -> Option<i32> {
+    match group(el) {
+        Some(1) if el.symbol() == "H" => Some(-1),
+        Some(n @ 14..=17) => Some(n as i32 - 18),
+        _ => None,
+    }
+}
+
+fn group(el: &Element) -> Option<u32> {
+    el.group().map(|g| g.group_number())
+}
+
+/// A compound with only carbon, hydrogen and maybe oxygen that can burn
+fn is_fuel(cmp: &Compound) -> bool {
+    let has = |s: &str| cmp.elements.iter().any(|e| e.symbol() == s);
+    has("C")
+        && has("H")
+        && cmp
+            .elements
+            .iter()
+            .all(|e| ["C", "H", "O"].contains(&e.symbol()))
+}
+
+/// An element on its own, in the form and state it's found in at room temperature
+fn free_element(mut cmp: Compound, states: bool) -> Compound {
+    let el = &mut cmp.elements[0];
+    if DIATOMIC.contains(&el.symbol()) {
+        el.count = 2;
+    }
+    let state = match el.symbol() {
+        "Br" | "Hg" => State::Liquid,
+        "H" | "N" | "O" | "F" | "Cl" => State::Gas,
+        _ => State::Solid,
+    };
+
+    with_state(cmp, states, state)
+}
+
+/// Parse a formula that's known to be valid
+fn known(formula: &str) -> Compound {
+    Compound::parse(formula).expect("formula is valid")
+}
+
+fn with_state(mut cmp: Compound, states: bool, state: State) -> Compound {
+    if states {
+        cmp.state = Some(state);
+    }
+    cmp
+}
+
+/// How a compound is written in an equation
+fn name(cmp: &Compound) -> String {
+    let coefficient = match cmp.coefficient {
+        1 => String::new(),
+        n => n.to_string(),
+    };
+    let state = cmp
+        .state
+        .as_ref()
+        .map_or_else(String::new, ToString::to_string);
+    format!("{}{}{}", coefficient, cmp.formula(), state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classify::ReactionType;
+
+    use super::*;
+
+    fn predict(reactants: &str) -> Result<String, PredictError> {
+        Equation::predict(reactants).map(|eq| eq.equation().to_string())
+    }
+
+    #[test]
+    fn synthesis() {
+        assert_eq!(predict("Na + Cl2 ->").unwrap(), "Na + Cl2 -> NaCl");
+        assert_eq!(predict("Al + O2 ->").unwrap(), "Al + O2 -> Al2O3");
+        assert_eq!(
+            predict("Mg(s) + N2(g) ->").unwrap(),
+            "Mg(s) + N2(g) -> Mg3N2(s)"
+        );
+        assert_eq!(predict("H2 + O2 ->").unwrap(), "H2 + O2 -> H2O");
+        assert_eq!(predict("N2 + H2 ->").unwrap(), "N2 + H2 -> NH3");
+        assert_eq!(predict("Li + H2 ->").unwrap(), "Li + H2 -> LiH");
+        assert_eq!(predict("Fe + Cu ->"), Err(PredictError::Unknown));
+    }
+
+    #[test]
+    fn combustion() {
+        assert_eq!(predict("CH4 + O2 ->").unwrap(), "CH4 + O2 -> CO2 + H2O");
+        assert_eq!(
+            predict("O2(g) + C2H5OH(l) ->").unwrap(),
+            "O2(g) + C2H5OH(l) -> CO2(g) + H2O(g)"
+        );
+    }
+
+    #[test]
+    fn single_replacement() {
+        assert_eq!(
+            predict("Zn(s) + CuSO4(aq) ->").unwrap(),
+            "Zn(s) + CuSO4(aq) -> ZnSO4(aq) + Cu(s)"
+        );
+        assert_eq!(
+            predict("Cu + AgNO3 ->").unwrap(),
+            "Cu + AgNO3 -> Cu(NO3)2 + Ag"
+        );
+        assert_eq!(predict("Mg + HCl ->").unwrap(), "Mg + HCl -> MgCl2 + H2");
+        assert_eq!(predict("Na + H2O ->").unwrap(), "Na + H2O -> NaOH + H2");
+        assert_eq!(
+            predict("Cl2 + NaBr ->").unwrap(),
+            "Cl2 + NaBr -> NaCl + Br2"
+        );
+        assert_eq!(
+            predict("Cu + ZnSO4 ->"),
+            Err(PredictError::LessReactive {
+                element: "Cu".to_string(),
+                replaced: "Zn".to_string()
+            })
+        );
+        assert!(matches!(
+            predict("Mg + H2O ->"),
+            Err(PredictError::LessReactive { .. })
+        ));
+        assert!(matches!(
+            predict("I2 + KCl ->"),
+            Err(PredictError::LessReactive { .. })
+        ));
+    }
+
+    #[test]
+    fn double_replacement() {
+        assert_eq!(
+            predict("Pb(NO3)2(aq) + KI(aq) ->").unwrap(),
+            "Pb(NO3)2(aq) + KI(aq) -> PbI2(s) + KNO3(aq)"
+        );
+        assert_eq!(
+            predict("BaCl2 + Na2SO4 ->").unwrap(),
+            "BaCl2 + Na2SO4 -> BaSO4(s) + NaCl(aq)"
+        );
+        assert_eq!(
+            predict("HCl(aq) + NaOH(aq) ->").unwrap(),
+            "HCl(aq) + NaOH(aq) -> NaCl(aq) + H2O(l)"
+        );
+        assert_eq!(
+            predict("Fe2(SO4)3 + NaOH ->").unwrap(),
+            "Fe2(SO4)3 + NaOH -> Fe(OH)3(s) + Na2SO4(aq)"
+        );
+        assert_eq!(
+            predict("NaCl(aq) + KNO3(aq) ->"),
+            Err(PredictError::AllSoluble)
+        );
+    }
+
+    #[test]
+    fn bad_input() {
+        assert_eq!(predict("Na + Cl2 -> NaCl"), Err(PredictError::HasProducts));
+        assert_eq!(predict("Na + Cl2 + Br2 ->"), Err(PredictError::Unknown));
+        assert!(matches!(
+            predict("Na + Cl2"),
+            Err(PredictError::Equation(EquationError::ParsingError(_)))
+        ));
+    }
+
+    #[test]
+    fn predicted_kind() {
+        for (reactants, kind) in [
+            ("Na + Cl2 ->", ReactionType::Synthesis),
+            ("C3H8 + O2 ->", ReactionType::Combustion),
+            ("Zn + CuSO4 ->", ReactionType::SingleReplacement),
+            ("AgNO3(aq) + NaCl(aq) ->", ReactionType::Precipitation),
+            ("H2SO4 + KOH ->", ReactionType::Neutralization),
+        ] {
+            let eq = Equation::predict(reactants).unwrap();
+            assert_eq!(eq.classify(), Some(kind), "{}", reactants);
+        }
+    }
+
+    #[cfg(feature = "balance")]
+    #[test]
+    fn balance_prediction() {
+        for (reactants, balanced) in [
+            ("Al + O2 ->", "4Al + 3O2 -> 2Al2O3"),
+            (
+                "Fe2(SO4)3 + NaOH ->",
+                "Fe2(SO4)3 + 6NaOH -> 2Fe(OH)3(s) + 3Na2SO4(aq)",
+            ),
+            ("Na + H2O ->", "2Na + 2H2O -> 2NaOH + H2"),
+        ] {
+            let eq = Equation::predict(reactants).unwrap();
+            assert_eq!(eq.to_balancer().balance().unwrap().equation(), balanced);
+        }
+    }
+}
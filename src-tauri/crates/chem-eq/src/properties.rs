@@ -0,0 +1,354 @@
+//! Chemistry-level properties of [`Element`]s and [`Compound`]s
+//!
+//! [`Element`] derefs to [`mendeleev::Element`] for raw data like the atomic weight. This module
+//! adds what's needed to reason about bonding: electronegativity, whether an element is a metal,
+//! the block of the periodic table it's in and its common oxidation states.
+
+use mendeleev::OxidationStateCategory;
+
+use crate::{Compound, Element};
+
+/// Below this difference in electronegativity a bond is nonpolar
+pub const NONPOLAR_LIMIT: f32 = 0.4;
+
+/// At or above this difference in electronegativity a bond is ionic
+pub const IONIC_LIMIT: f32 = 1.8;
+
+/// Pauling electronegativity by atomic number, starting at hydrogen. Noble gases without
+/// known compounds and superheavy elements are [`None`].
+#[rustfmt::skip]
+const ELECTRONEGATIVITY: [Option<f32>; 103] = [
+    // H - Ne
+    Some(2.20), None, Some(0.98), Some(1.57), Some(2.04), Some(2.55), Some(3.04), Some(3.44),
+    Some(3.98), None,
+    // Na - Ar
+    Some(0.93), Some(1.31), Some(1.61), Some(1.90), Some(2.19), Some(2.58), Some(3.16), None,
+    // K - Kr
+    Some(0.82), Some(1.00), Some(1.36), Some(1.54), Some(1.63), Some(1.66), Some(1.55),
+    Some(1.83), Some(1.88), Some(1.91), Some(1.90), Some(1.65), Some(1.81), Some(2.01),
+    Some(2.18), Some(2.55), Some(2.96), Some(3.00),
+    // Rb - Xe
+    Some(0.82), Some(0.95), Some(1.22), Some(1.33), Some(1.60), Some(2.16), Some(1.90),
+    Some(2.20), Some(2.28), Some(2.20), Some(1.93), Some(1.69), Some(1.78), Some(1.96),
+    Some(2.05), Some(2.10), Some(2.66), Some(2.60),
+    // Cs - Rn
+    Some(0.79), Some(0.89), Some(1.10), Some(1.12), Some(1.13), Some(1.14), Some(1.13),
+    Some(1.17), Some(1.20), Some(1.20), Some(1.20), Some(1.22), Some(1.23), Some(1.24),
+    Some(1.25), Some(1.10), Some(1.27), Some(1.30), Some(1.50), Some(2.36), Some(1.90),
+    Some(2.20), Some(2.20), Some(2.28), Some(2.54), Some(2.00), Some(1.62), Some(1.87),
+    Some(2.02), Some(2.00), Some(2.20), Some(2.20),
+    // Fr - Lr
+    Some(0.79), Some(0.90), Some(1.10), Some(1.30), Some(1.50), Some(1.38), Some(1.36),
+    Some(1.28), Some(1.13), Some(1.28), Some(1.30), Some(1.30), Some(1.30), Some(1.30),
+    Some(1.30), Some(1.30), Some(1.30),
+];
+
+/// Elements between metals and nonmetals, every element that isn't one of these or a
+/// nonmetal is a metal. Tennessine is counted with astatine above it.
+const METALLOIDS: [&str; 8] = ["B", "Si", "Ge", "As", "Sb", "Te", "At", "Ts"];
+
+/// Every nonmetal, including the noble gases down to oganesson
+const NONMETALS: [&str; 18] = [
+    "H", "He", "C", "N", "O", "F", "Ne", "P", "S", "Cl", "Ar", "Se", "Br", "Kr", "I", "Xe", "Rn",
+    "Og",
+];
+
+/// The block of the periodic table an element is in, named for the subshell its valence
+/// electrons fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Block {
+    /// Groups 1 and 2, and helium
+    S,
+    /// Groups 13 to 18
+    P,
+    /// The transition metals, groups 3 to 12
+    D,
+    /// The lanthanides and actinides
+    F,
+}
+
+/// Whether an element is a metal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Metallicity {
+    /// Shiny and conductive, tends to lose electrons
+    Metal,
+    /// Has properties of both metals and nonmetals, eg. silicon
+    Metalloid,
+    /// Tends to gain or share electrons
+    Nonmetal,
+}
+
+/// How two elements, or the elements of a compound, are bonded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BondType {
+    /// Electrons are shared evenly
+    NonpolarCovalent,
+    /// Electrons are shared, but held closer to one element
+    PolarCovalent,
+    /// Electrons are given from one element to another
+    Ionic,
+    /// Electrons are shared between every atom of a metal
+    Metallic,
+}
+
+impl BondType {
+    /// Whether electrons are shared, polar or not
+    pub const fn is_covalent(self) -> bool {
+        matches!(self, Self::NonpolarCovalent | Self::PolarCovalent)
+    }
+}
+
+/// Where an element is on the periodic table and how it bonds, for showing to a student
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ElementSummary {
+    /// The element's symbol, eg. `Na`
+    pub symbol: &'static str,
+    /// The element's English name, eg. `Sodium`
+    pub name: &'static str,
+    /// The number of protons
+    pub atomic_number: u32,
+    /// The group from 1 to 18, [`None`] for the lanthanides and actinides
+    pub group: Option<u32>,
+    /// The period from 1 to 7
+    pub period: u32,
+    /// The block of the periodic table
+    pub block: Block,
+    /// Whether the element is a metal
+    pub metallicity: Metallicity,
+    /// Pauling electronegativity, [`None`] if it isn't known
+    pub electronegativity: Option<f32>,
+    /// The most common oxidation states, lowest first
+    pub oxidation_states: &'static [i8],
+}
+
+impl Element {
+    /// Get the Pauling electronegativity, or [`None`] if it isn't known, like for helium
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Element;
+    ///
+    /// assert_eq!(Element::parse("F").unwrap().electronegativity(), Some(3.98));
+    /// assert_eq!(Element::parse("He").unwrap().electronegativity(), None);
+    /// ```
+    pub fn electronegativity(&self) -> Option<f32> {
+        let idx = self.atomic_number() as usize - 1;
+        ELECTRONEGATIVITY.get(idx).copied().flatten()
+    }
+
+    /// Get whether the element is a metal, metalloid or nonmetal. Everything that isn't a
+    /// known nonmetal or metalloid is a metal. Tennessine and oganesson have never been made
+    /// in bulk, so they're classed like the elements above them in their groups.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::{properties::Metallicity, Element};
+    ///
+    /// assert_eq!(Element::parse("Na").unwrap().metallicity(), Metallicity::Metal);
+    /// assert_eq!(Element::parse("B").unwrap().metallicity(), Metallicity::Metalloid);
+    /// assert_eq!(Element::parse("Og").unwrap().metallicity(), Metallicity::Nonmetal);
+    /// ```
+    pub fn metallicity(&self) -> Metallicity {
+        let symbol = self.symbol();
+        if NONMETALS.contains(&symbol) {
+            Metallicity::Nonmetal
+        } else if METALLOIDS.contains(&symbol) {
+            Metallicity::Metalloid
+        } else {
+            Metallicity::Metal
+        }
+    }
+
+    /// Get the block of the periodic table the element is in
+    pub fn block(&self) -> Block {
+        if self.symbol() == "He" {
+            return Block::S;
+        }
+        match self.group().map(|g| g.group_number()) {
+            Some(1 | 2) => Block::S,
+            Some(3..=12) => Block::D,
+            Some(_) => Block::P,
+            None => Block::F,
+        }
+    }
+
+    /// Get the most common oxidation states of the element, lowest first. Elements in
+    /// compounds usually have one of these.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::Element;
+    ///
+    /// assert_eq!(Element::parse("Fe").unwrap().common_oxidation_states(), [2, 3]);
+    /// assert_eq!(Element::parse("Cl").unwrap().common_oxidation_states(), [-1, 1, 3, 5, 7]);
+    /// ```
+    pub fn common_oxidation_states(&self) -> &'static [i8] {
+        self.oxidation_states(OxidationStateCategory::Main)
+    }
+
+    /// Get how the element would bond with another, from the difference in their
+    /// electronegativity. Two metals bond metallically, and an element without a known
+    /// electronegativity is treated as bonding evenly.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::{properties::BondType, Element};
+    ///
+    /// let na = Element::parse("Na").unwrap();
+    /// let cl = Element::parse("Cl").unwrap();
+    /// let h = Element::parse("H").unwrap();
+    /// assert_eq!(na.bond_with(&cl), BondType::Ionic);
+    /// assert_eq!(h.bond_with(&cl), BondType::PolarCovalent);
+    /// assert_eq!(cl.bond_with(&cl), BondType::NonpolarCovalent);
+    /// ```
+    pub fn bond_with(&self, other: &Element) -> BondType {
+        if self.metallicity() == Metallicity::Metal && other.metallicity() == Metallicity::Metal {
+            return BondType::Metallic;
+        }
+        let difference = match (self.electronegativity(), other.electronegativity()) {
+            (Some(a), Some(b)) => (a - b).abs(),
+            _ => 0.0,
+        };
+
+        if difference >= IONIC_LIMIT {
+            BondType::Ionic
+        } else if difference >= NONPOLAR_LIMIT {
+            BondType::PolarCovalent
+        } else {
+            BondType::NonpolarCovalent
+        }
+    }
+
+    /// Get where the element is on the periodic table and how it bonds
+    pub fn summary(&self) -> ElementSummary {
+        ElementSummary {
+            symbol: self.symbol(),
+            name: self.name(),
+            atomic_number: self.atomic_number(),
+            group: self.group().map(|g| g.group_number()),
+            period: self.period(),
+            block: self.block(),
+            metallicity: self.metallicity(),
+            electronegativity: self.electronegativity(),
+            oxidation_states: self.common_oxidation_states(),
+        }
+    }
+}
+
+impl Compound {
+    /// Guess how a compound is bonded. A compound of only metals is metallic, a metal with a
+    /// nonmetal is ionic, otherwise it's the most polar bond between two of its elements.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chem_eq::{properties::BondType, Compound};
+    ///
+    /// assert_eq!(Compound::parse("NaCl").unwrap().bond_type(), BondType::Ionic);
+    /// assert_eq!(Compound::parse("H2O").unwrap().bond_type(), BondType::PolarCovalent);
+    /// assert_eq!(Compound::parse("CH4").unwrap().bond_type(), BondType::NonpolarCovalent);
+    /// assert_eq!(Compound::parse("Fe").unwrap().bond_type(), BondType::Metallic);
+    /// ```
+    pub fn bond_type(&self) -> BondType {
+        let has = |m: Metallicity| self.elements.iter().any(|e| e.metallicity() == m);
+        if !has(Metallicity::Nonmetal) && !has(Metallicity::Metalloid) {
+            return BondType::Metallic;
+        }
+        if has(Metallicity::Metal) && has(Metallicity::Nonmetal) {
+            return BondType::Ionic;
+        }
+
+        let mut bond = BondType::NonpolarCovalent;
+        for (i, a) in self.elements.iter().enumerate() {
+            for b in &self.elements[i + 1..] {
+                match a.bond_with(b) {
+                    BondType::Ionic => return BondType::Ionic,
+                    BondType::PolarCovalent => bond = BondType::PolarCovalent,
+                    _ => {}
+                }
+            }
+        }
+
+        bond
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn el(symbol: &str) -> Element {
+        Element::parse(symbol).unwrap()
+    }
+
+    #[test]
+    fn electronegativity_table() {
+        assert_eq!(el("H").electronegativity(), Some(2.20));
+        assert_eq!(el("O").electronegativity(), Some(3.44));
+        assert_eq!(el("Au").electronegativity(), Some(2.54));
+        assert_eq!(el("Lr").electronegativity(), Some(1.30));
+        assert_eq!(el("Og").electronegativity(), None);
+    }
+
+    #[test]
+    fn blocks_and_metals() {
+        assert_eq!(el("He").block(), Block::S);
+        assert_eq!(el("Ca").block(), Block::S);
+        assert_eq!(el("Fe").block(), Block::D);
+        assert_eq!(el("Lu").block(), Block::D);
+        assert_eq!(el("U").block(), Block::F);
+        assert_eq!(el("Br").block(), Block::P);
+
+        assert_eq!(el("Al").metallicity(), Metallicity::Metal);
+        assert_eq!(el("Si").metallicity(), Metallicity::Metalloid);
+        assert_eq!(el("Xe").metallicity(), Metallicity::Nonmetal);
+
+        // the edges of the lists, and elements past them
+        assert_eq!(el("H").metallicity(), Metallicity::Nonmetal);
+        assert_eq!(el("Rn").metallicity(), Metallicity::Nonmetal);
+        assert_eq!(el("At").metallicity(), Metallicity::Metalloid);
+        assert_eq!(el("Fr").metallicity(), Metallicity::Metal);
+        assert_eq!(el("Ts").metallicity(), Metallicity::Metalloid);
+        assert_eq!(el("Og").metallicity(), Metallicity::Nonmetal);
+        assert_eq!(el("Lv").metallicity(), Metallicity::Metal);
+    }
+
+    #[test]
+    fn metallicity_lists_are_elements() {
+        // a typo would quietly make an element a metal
+        for symbol in NONMETALS {
+            assert_eq!(el(symbol).metallicity(), Metallicity::Nonmetal);
+        }
+        for symbol in METALLOIDS {
+            assert_eq!(el(symbol).metallicity(), Metallicity::Metalloid);
+        }
+    }
+
+    #[test]
+    fn summary() {
+        let summary = el("N").summary();
+        assert_eq!(summary.name, "Nitrogen");
+        assert_eq!(summary.atomic_number, 7);
+        assert_eq!(summary.group, Some(15));
+        assert_eq!(summary.period, 2);
+        assert_eq!(summary.oxidation_states, [-3, 3, 5]);
+    }
+
+    #[test]
+    fn compound_bonds() {
+        let bond = |s: &str| Compound::parse(s).unwrap().bond_type();
+        assert_eq!(bond("CuZn"), BondType::Metallic);
+        assert_eq!(bond("Fe2O3"), BondType::Ionic);
+        assert_eq!(bond("O2"), BondType::NonpolarCovalent);
+        assert_eq!(bond("SiO2"), BondType::PolarCovalent);
+        assert_eq!(bond("NH3"), BondType::PolarCovalent);
+    }
+}
@@ -0,0 +1,358 @@
+//! # `lcp-sim`
+//!
+//! Run Le Chatelier's Principle simulations without the app
+
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, Read, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use chatelier::{AdjustError, System, SystemError};
+use chem_eq::{
+    error::{BalanceError, ConcentrationError, EquationError},
+    Equation,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use lcp_service::export::{Export, ExportFormat};
+use serde::Serialize;
+use thiserror::Error;
+
+use report::{BalanceReport, EquilibriumReport, ParseReport, SimulateReport, Step};
+
+mod repl;
+mod report;
+mod script;
+mod serve;
+
+/// Simulate Le Chatelier's Principle from the command line
+#[derive(Debug, Parser)]
+#[command(name = "lcp-sim", version)]
+struct Cli {
+    /// How to print results
+    #[arg(long, short, value_enum, default_value_t = Format::Table, global = true)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Parse an equation and list its compounds
+    Parse {
+        /// The equation, eg. "2H2 + O2 -> 2H2O"
+        equation: String,
+    },
+    /// Balance an equation
+    Balance {
+        /// The equation, eg. "H2 + O2 -> H2O"
+        equation: String,
+    },
+    /// Find the k expression of an equation at equilibrium
+    Equilibrium(Setup),
+    /// Run a script of adjustments on a system at equilibrium
+    Simulate {
+        #[command(flatten)]
+        setup: Setup,
+        /// The k expression, if the concentrations aren't at equilibrium
+        #[arg(long, short)]
+        k: Option<f32>,
+        /// File with one adjustment per line, reads stdin if missing or "-"
+        script: Option<PathBuf>,
+        /// Also write each step to a .csv or .json file
+        #[arg(long)]
+        export: Option<PathBuf>,
+    },
+    /// Start an interactive shell, type `help` in it for commands
+    Repl {
+        /// A session saved with `save` to open
+        session: Option<PathBuf>,
+    },
+    /// Serve the app's commands as JSON-RPC over HTTP
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        addr: SocketAddr,
+        /// Session file to start from, and to write on `save_session`
+        session: Option<PathBuf>,
+    },
+}
+
+/// An equation and its conditions
+#[derive(Debug, Args)]
+struct Setup {
+    /// The equation, eg. "N2O4(g) <-> 2NO2(g)"
+    equation: String,
+    /// Concentration of each compound in M (mol/L), in order
+    #[arg(long, short, value_delimiter = ',', allow_negative_numbers = true)]
+    concentrations: Vec<f32>,
+    /// Volume in L
+    #[arg(long)]
+    volume: Option<f32>,
+    /// Temperature in °C
+    #[arg(long, allow_negative_numbers = true)]
+    temperature: Option<f32>,
+    /// ΔH of the forward reaction in kJ
+    #[arg(long, allow_negative_numbers = true)]
+    delta_h: Option<f32>,
+}
+
+impl Setup {
+    fn equation(&self) -> Result<Equation, CliError> {
+        let mut eq = Equation::new(&self.equation)?;
+        if !self.concentrations.is_empty() {
+            eq.set_concentrations(&self.concentrations)?;
+        }
+        if let Some(volume) = self.volume {
+            eq.set_volume(volume);
+        }
+        if let Some(temperature) = self.temperature {
+            eq.set_temperature(temperature);
+        }
+        if let Some(delta_h) = self.delta_h {
+            eq.set_delta_h(delta_h);
+        }
+
+        Ok(eq)
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let res = match cli.command {
+        Command::Parse { equation } => parse(&equation).and_then(|r| print(cli.format, r)),
+        Command::Balance { equation } => balance(&equation).and_then(|r| print(cli.format, r)),
+        Command::Equilibrium(setup) => equilibrium(&setup).and_then(|r| print(cli.format, r)),
+        Command::Simulate {
+            setup,
+            k,
+            script,
+            export,
+        } => read_script(script)
+            .and_then(|s| simulate(&setup, k, &s, export.as_deref()))
+            .and_then(|r| print(cli.format, r)),
+        Command::Repl { session } => repl::run(session.as_deref()).map_err(CliError::from),
+        Command::Serve { addr, session } => {
+            serve::run(addr, session.as_deref()).map_err(CliError::from)
+        }
+    };
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        // the output was piped to something that stopped reading, like `head`
+        Err(CliError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print(format: Format, report: impl Serialize + Display) -> Result<(), CliError> {
+    let mut stdout = io::stdout().lock();
+    match format {
+        Format::Table => write!(stdout, "{}", report)?,
+        Format::Json => writeln!(stdout, "{}", serde_json::to_string_pretty(&report)?)?,
+    }
+
+    Ok(())
+}
+
+fn parse(equation: &str) -> Result<ParseReport, CliError> {
+    Ok(ParseReport::from(&Equation::new(equation)?))
+}
+
+fn balance(equation: &str) -> Result<BalanceReport, CliError> {
+    let balanced = Equation::new(equation)?.to_balancer().balance()?;
+
+    Ok(BalanceReport {
+        input: equation.to_string(),
+        balanced: balanced.to_string(),
+    })
+}
+
+fn equilibrium(setup: &Setup) -> Result<EquilibriumReport, CliError> {
+    let eq = setup.equation()?;
+
+    Ok(EquilibriumReport {
+        equation: eq.equation().to_string(),
+        concentrations: eq
+            .name_and_concentration()
+            .map(|(n, c)| (n.to_string(), *c))
+            .collect(),
+        k: eq.equilibrium_constant(),
+    })
+}
+
+fn read_script(path: Option<PathBuf>) -> Result<String, CliError> {
+    match path {
+        Some(path) if path.as_os_str() != "-" => Ok(fs::read_to_string(path)?),
+        _ => {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+fn simulate(
+    setup: &Setup,
+    k: Option<f32>,
+    script: &str,
+    export: Option<&Path>,
+) -> Result<SimulateReport, CliError> {
+    let adjusts = script::parse(script)?;
+    let format = export
+        .map(|path| {
+            ExportFormat::from_path(path).ok_or_else(|| CliError::ExportFormat(path.into()))
+        })
+        .transpose()?;
+    let eq = setup.equation()?;
+    let mut system = match k {
+        Some(k) => System::with_k_expr(eq, k)?,
+        None => System::new(eq)?,
+    };
+
+    let mut steps = vec![Step {
+        adjustment: None,
+        shift: chatelier::Direction::None,
+        k: system.get_k_expr(),
+        concentrations: system.equation().get_concentrations(),
+    }];
+    for (i, adjust) in adjusts.into_iter().enumerate() {
+        let step_err = |source| CliError::Step {
+            step: i + 1,
+            source,
+        };
+        let shift = system
+            .get_owned_shift_direction(&adjust)
+            .map_err(step_err)?;
+        system.adjust_owned(&adjust).map_err(step_err)?;
+        steps.push(Step {
+            adjustment: Some(adjust),
+            shift,
+            k: system.get_k_expr(),
+            concentrations: system.equation().get_concentrations(),
+        });
+    }
+    if let (Some(path), Some(format)) = (export, format) {
+        fs::write(path, Export::of(&system).write(format))?;
+    }
+
+    Ok(SimulateReport {
+        equation: system.equation().equation().to_string(),
+        compounds: system
+            .equation()
+            .compound_names()
+            .map(ToString::to_string)
+            .collect(),
+        steps,
+    })
+}
+
+/// An error running a command
+#[derive(Error, Debug)]
+enum CliError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid equation: {0}")]
+    Equation(#[from] EquationError),
+    #[error("invalid concentrations: {0}")]
+    Concentration(#[from] ConcentrationError),
+    #[error("couldn't balance: {0}")]
+    Balance(#[from] BalanceError),
+    #[error("{0}")]
+    System(#[from] SystemError),
+    #[error("invalid script, {0}")]
+    Script(#[from] script::ScriptError),
+    #[error("{0}")]
+    Repl(#[from] repl::ReplError),
+    #[error("adjustment {step}: {source}")]
+    Step { step: usize, source: AdjustError },
+    #[error("can't export to {}, use a .csv or .json file", .0.display())]
+    ExportFormat(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use chatelier::Direction;
+
+    use super::*;
+
+    fn setup(equation: &str, concentrations: &[f32]) -> Setup {
+        Setup {
+            equation: equation.to_string(),
+            concentrations: concentrations.to_vec(),
+            volume: Some(1.0),
+            temperature: None,
+            delta_h: None,
+        }
+    }
+
+    #[test]
+    fn simulate_script() {
+        let setup = setup("N2O4(g) <-> 2NO2(g)", &[1.0, 0.5]);
+        let report = simulate(&setup, None, "c N2O4(g) 2\nvolume 2", None).unwrap();
+
+        assert_eq!(report.compounds, vec!["N2O4(g)", "2NO2(g)"]);
+        assert_eq!(
+            report.steps.iter().map(|s| s.shift).collect::<Vec<_>>(),
+            vec![Direction::None, Direction::Forward, Direction::Forward]
+        );
+        assert_eq!(report.steps[0].concentrations, vec![1.0, 0.5]);
+    }
+
+    #[test]
+    fn simulate_bad_step() {
+        let setup = setup("N2O4(g) <-> 2NO2(g)", &[1.0, 0.5]);
+        let err = simulate(&setup, None, "volume 2\nvolume -1", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "adjustment 2: volume must be positive".to_string()
+        );
+    }
+
+    #[test]
+    fn simulate_export() {
+        let setup = setup("N2O4(g) <-> 2NO2(g)", &[1.0, 0.5]);
+        let path = std::env::temp_dir().join("lcp-sim-simulate-test.json");
+        let report = simulate(&setup, None, "volume 2", Some(&path)).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(json["steps"].as_array().unwrap().len(), report.steps.len());
+
+        let err = simulate(&setup, None, "volume 2", Some(Path::new("out.txt"))).unwrap_err();
+        assert!(matches!(err, CliError::ExportFormat(_)));
+    }
+
+    #[test]
+    fn equilibrium_constant() {
+        let report = equilibrium(&setup("N2O4(g) <-> 2NO2(g)", &[1.0, 0.5])).unwrap();
+        assert_eq!(report.k, Some(0.25));
+    }
+
+    #[test]
+    fn balance_equation() {
+        let report = balance("H2 + O2 -> H2O").unwrap();
+        assert_eq!(report.balanced, "2H2 + O2 -> 2H2O");
+    }
+
+    #[test]
+    fn cli_definition() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,579 @@
+//! An interactive shell for `lcp-sim repl`
+//!
+//! Each line is a command, run by [`Shell::run_line`]. Type `help` for a list of commands.
+
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chatelier::{AdjustError, OwnedAdjustment, System, SystemError};
+use chem_eq::{
+    error::{BalanceError, ConcentrationError, EquationError},
+    Equation, ReactionQuotient,
+};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use is_terminal::IsTerminal;
+use lcp_service::export::{Export, ExportFormat};
+use reedline::{
+    default_emacs_keybindings, ColumnarMenu, Completer, DefaultPrompt, DefaultPromptSegment, Emacs,
+    KeyCode, KeyModifiers, Reedline, ReedlineEvent, ReedlineMenu, Signal, Span, Suggestion,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    report::{number, Table},
+    script::{self, ScriptErrorKind},
+};
+
+/// A command typed into the shell
+#[derive(Debug, Parser)]
+#[command(multicall = true)]
+enum Command {
+    /// Load an equation, eg. `load N2O4(g) <-> 2NO2(g)`
+    Load {
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        equation: Vec<String>,
+    },
+    /// Set every concentration in M (mol/L) and start at equilibrium, or print them
+    Concentrations {
+        #[arg(allow_negative_numbers = true)]
+        values: Vec<f32>,
+    },
+    /// Adjust the system, eg. `adjust concentration N2O4(g) 2` or `adjust volume 0.5`
+    Adjust {
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        adjustment: Vec<String>,
+    },
+    /// Change the volume in L, or print it
+    Volume { value: Option<f32> },
+    /// Change the temperature in °C, or print it
+    Temperature {
+        #[arg(allow_negative_numbers = true)]
+        value: Option<f32>,
+    },
+    /// Set ΔH of the forward reaction in kJ, or print it
+    DeltaH {
+        #[arg(allow_negative_numbers = true)]
+        value: Option<f32>,
+    },
+    /// Balance the equation, clearing concentrations
+    Balance,
+    /// Print the k expression, which the system reacts to match
+    K,
+    /// Print the reaction quotient of the current concentrations
+    Q,
+    /// Print everything about the system
+    Show,
+    /// Undo the last adjustment
+    Undo,
+    /// Redo the last undone adjustment
+    Redo,
+    /// Save the session to a file
+    Save { path: PathBuf },
+    /// Open a session from a file
+    Open { path: PathBuf },
+    /// Write every adjustment and the concentrations after it to a .csv or .json file
+    Export { path: PathBuf },
+    /// Leave the shell
+    #[command(alias = "quit")]
+    Exit,
+}
+
+/// What to do after running a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Print this and keep going
+    Print(String),
+    /// Leave the shell
+    Exit,
+}
+
+/// The state of the shell, saved with `save` and restored with `open`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shell {
+    /// The loaded equation, before the system starts
+    equation: Option<Equation>,
+    /// The system, once concentrations are set
+    system: Option<System>,
+}
+
+impl Shell {
+    /// Run one line, returning what to print
+    pub fn run_line(&mut self, line: &str) -> Result<Outcome, ReplError> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            return Ok(Outcome::Print(String::new()));
+        }
+        let command = match Command::try_parse_from(words) {
+            Ok(command) => command,
+            Err(e) if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) => {
+                return Ok(Outcome::Print(
+                    e.render().to_string().trim_end().to_string(),
+                ));
+            }
+            Err(e) => return Err(ReplError::Command(e.render().to_string())),
+        };
+
+        let output = match command {
+            Command::Load { equation } => {
+                let eq = Equation::new(&equation.join(" "))?;
+                let output = format!("loaded {}", eq);
+                *self = Self {
+                    equation: Some(eq),
+                    system: None,
+                };
+                output
+            }
+            Command::Concentrations { values } if values.is_empty() => {
+                let eq = self.current()?;
+                let rows = eq
+                    .name_and_concentration()
+                    .map(|(n, c)| vec![n.to_string(), number(*c)])
+                    .collect();
+                Table::new(["Compound", "Concentration (M)"], rows).to_string()
+            }
+            Command::Concentrations { values } => {
+                let mut eq = self.start()?.clone();
+                eq.set_concentrations(&values)?;
+                let system = System::new(eq.clone())?;
+                let output = format!("at equilibrium, K = {}", number(system.get_k_expr()));
+                self.equation = Some(eq);
+                self.system = Some(system);
+                output
+            }
+            Command::Adjust { adjustment } => {
+                let adjust = script::parse_line(&adjustment.join(" "))?;
+                self.adjust(&adjust)?
+            }
+            Command::Volume { value: Some(v) } => match self.system {
+                Some(_) => self.adjust(&OwnedAdjustment::Volume(v))?,
+                None => {
+                    self.start_mut()?.set_volume(v);
+                    format!("volume = {} L", number(v))
+                }
+            },
+            Command::Volume { value: None } => match self.current()?.volume() {
+                Some(v) => format!("volume = {} L", number(v)),
+                None => "volume isn't set, 1 L is assumed".to_string(),
+            },
+            Command::Temperature { value: Some(t) } => match self.system {
+                Some(_) => self.adjust(&OwnedAdjustment::Temperature(t))?,
+                None => {
+                    self.start_mut()?.set_temperature(t);
+                    format!("temperature = {} °C", number(t))
+                }
+            },
+            Command::Temperature { value: None } => match self.current()?.temperature() {
+                Some(t) => format!("temperature = {} °C", number(t)),
+                None => "temperature isn't set, 25 °C is assumed".to_string(),
+            },
+            Command::DeltaH { value: Some(h) } => {
+                self.start_mut()?.set_delta_h(h);
+                if let Some(system) = &mut self.system {
+                    system.equation_mut().set_delta_h(h);
+                }
+                format!("ΔH = {} kJ", number(h))
+            }
+            Command::DeltaH { value: None } => {
+                format!("ΔH = {} kJ", number(self.current()?.delta_h()))
+            }
+            Command::Balance => {
+                let balanced = self.start()?.to_balancer().balance()?;
+                let output = format!("balanced {}", balanced);
+                *self = Self {
+                    equation: Some(balanced),
+                    system: None,
+                };
+                output
+            }
+            Command::K => format!("K = {}", number(self.system()?.get_k_expr())),
+            Command::Q => quotient(self.current()?),
+            Command::Show => self.show()?,
+            Command::Undo => match self.system_mut()?.undo() {
+                Some(adjust) => format!("undid {}", script::to_line(&adjust)),
+                None => return Err(ReplError::NothingToUndo),
+            },
+            Command::Redo => match self.system_mut()?.redo() {
+                Some(adjust) => format!("redid {}", script::to_line(&adjust)),
+                None => return Err(ReplError::NothingToRedo),
+            },
+            Command::Save { path } => {
+                fs::write(&path, serde_json::to_string_pretty(self)?)?;
+                format!("saved to {}", path.display())
+            }
+            Command::Open { path } => {
+                *self = serde_json::from_str(&fs::read_to_string(&path)?)?;
+                format!("opened {}", path.display())
+            }
+            Command::Export { path } => {
+                let format = ExportFormat::from_path(&path)
+                    .ok_or_else(|| ReplError::ExportFormat(path.clone()))?;
+                fs::write(&path, Export::of(self.system()?).write(format))?;
+                format!("exported to {}", path.display())
+            }
+            Command::Exit => return Ok(Outcome::Exit),
+        };
+
+        Ok(Outcome::Print(output))
+    }
+
+    /// Names of the compounds in the current equation
+    pub fn compound_names(&self) -> Vec<String> {
+        self.current()
+            .map(|eq| eq.compound_names().map(ToString::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    fn adjust(&mut self, adjust: &OwnedAdjustment) -> Result<String, ReplError> {
+        let system = self.system_mut()?;
+        let shift = system.get_owned_shift_direction(adjust)?;
+        system.adjust_owned(adjust)?;
+        let shift = match shift {
+            chatelier::Direction::Forward => "shifted right",
+            chatelier::Direction::Reverse => "shifted left",
+            chatelier::Direction::None => "didn't shift",
+        };
+
+        Ok(format!("{}, {}", shift, quotient(system.equation())))
+    }
+
+    fn show(&self) -> Result<String, ReplError> {
+        let eq = self.current()?;
+        let mut output = format!("{}\n", eq);
+        let rows = eq
+            .name_and_concentration()
+            .map(|(n, c)| vec![n.to_string(), number(*c)])
+            .collect();
+        output.push_str(&Table::new(["Compound", "Concentration (M)"], rows).to_string());
+
+        match &self.system {
+            Some(system) => {
+                output.push_str(&format!("K = {}\n", number(system.get_k_expr())));
+                if system.is_catalysed() {
+                    output.push_str("catalysed\n");
+                }
+                if system.inert_gas() > 0.0 {
+                    output.push_str(&format!("inert gas = {} M\n", number(system.inert_gas())));
+                }
+            }
+            None => output.push_str("K isn't known until concentrations are set\n"),
+        }
+        output.push_str(&format!("{}\n", quotient(eq)));
+        if let Some(v) = eq.volume() {
+            output.push_str(&format!("volume = {} L\n", number(v)));
+        }
+        if let Some(t) = eq.temperature() {
+            output.push_str(&format!("temperature = {} °C\n", number(t)));
+        }
+        output.push_str(&format!("ΔH = {} kJ", number(eq.delta_h())));
+
+        Ok(output)
+    }
+
+    /// The equation as it is now
+    fn current(&self) -> Result<&Equation, ReplError> {
+        match &self.system {
+            Some(system) => Ok(system.equation()),
+            None => self.start(),
+        }
+    }
+
+    /// The equation as it was loaded
+    fn start(&self) -> Result<&Equation, ReplError> {
+        self.equation.as_ref().ok_or(ReplError::NoEquation)
+    }
+
+    fn start_mut(&mut self) -> Result<&mut Equation, ReplError> {
+        self.equation.as_mut().ok_or(ReplError::NoEquation)
+    }
+
+    fn system(&self) -> Result<&System, ReplError> {
+        self.system.as_ref().ok_or(ReplError::NoSystem)
+    }
+
+    fn system_mut(&mut self) -> Result<&mut System, ReplError> {
+        self.system.as_mut().ok_or(ReplError::NoSystem)
+    }
+}
+
+fn quotient(eq: &Equation) -> String {
+    match eq.reaction_quotient() {
+        ReactionQuotient::Val(q) => format!("Q = {}", number(q)),
+        ReactionQuotient::LeftZero => "Q is infinite, the reactants have no concentration".into(),
+        ReactionQuotient::RightZero => "Q = 0, the products have no concentration".into(),
+        ReactionQuotient::BothSidesZero => "Q is undefined, nothing has concentration".into(),
+    }
+}
+
+/// Completes command names, then compound names
+struct ShellCompleter {
+    names: Arc<Mutex<Vec<String>>>,
+}
+
+impl Completer for ShellCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let line = &line[..pos];
+        let start = line.rfind(' ').map_or(0, |i| i + 1);
+        let word = &line[start..];
+
+        let candidates = if start == 0 {
+            Command::command()
+                .get_subcommands()
+                .map(|c| c.get_name().to_string())
+                .collect()
+        } else {
+            self.names.lock().unwrap().clone()
+        };
+
+        candidates
+            .into_iter()
+            .filter(|c| c.starts_with(word))
+            .map(|value| Suggestion {
+                value,
+                description: None,
+                extra: None,
+                span: Span::new(start, pos),
+                append_whitespace: true,
+            })
+            .collect()
+    }
+}
+
+/// Run the shell, opening a session first if given. When stdin isn't a terminal, each line of
+/// it is run instead.
+pub fn run(session: Option<&Path>) -> Result<(), ReplError> {
+    let mut shell = Shell::default();
+    if let Some(path) = session {
+        shell = serde_json::from_str(&fs::read_to_string(path)?)?;
+    }
+
+    if !io::stdin().is_terminal() {
+        for line in io::stdin().lock().lines() {
+            if !respond(&mut shell, &line?) {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
+    let names = Arc::new(Mutex::new(shell.compound_names()));
+    let mut keybindings = default_emacs_keybindings();
+    keybindings.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Tab,
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::Menu("completion_menu".to_string()),
+            ReedlineEvent::MenuNext,
+        ]),
+    );
+    let mut editor = Reedline::create()
+        .with_edit_mode(Box::new(Emacs::new(keybindings)))
+        .with_completer(Box::new(ShellCompleter {
+            names: Arc::clone(&names),
+        }))
+        .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+            ColumnarMenu::default().with_name("completion_menu"),
+        )));
+    let prompt = DefaultPrompt::new(
+        DefaultPromptSegment::Basic("chatelier".to_string()),
+        DefaultPromptSegment::Empty,
+    );
+
+    println!("chatelier shell, type `help` for commands");
+    while let Signal::Success(line) = editor.read_line(&prompt)? {
+        if !respond(&mut shell, &line) {
+            break;
+        }
+        *names.lock().unwrap() = shell.compound_names();
+    }
+
+    Ok(())
+}
+
+/// Run a line and print the result, returning whether to keep going
+fn respond(shell: &mut Shell, line: &str) -> bool {
+    match shell.run_line(line) {
+        Ok(Outcome::Print(s)) if s.is_empty() => {}
+        Ok(Outcome::Print(s)) => println!("{}", s),
+        Ok(Outcome::Exit) => return false,
+        Err(e) => eprintln!("error: {}", e),
+    }
+
+    true
+}
+
+/// An error running a line of the shell
+#[derive(Error, Debug)]
+pub enum ReplError {
+    #[error("{0}")]
+    Command(String),
+    #[error("no equation loaded, use `load <equation>`")]
+    NoEquation,
+    #[error("the system hasn't started, use `concentrations <values>`")]
+    NoSystem,
+    #[error("nothing to undo")]
+    NothingToUndo,
+    #[error("nothing to redo")]
+    NothingToRedo,
+    #[error("invalid equation: {0}")]
+    Equation(#[from] EquationError),
+    #[error("invalid concentrations: {0}")]
+    Concentration(#[from] ConcentrationError),
+    #[error("couldn't balance: {0}")]
+    Balance(#[from] BalanceError),
+    #[error("{0}")]
+    System(#[from] SystemError),
+    #[error("{0}")]
+    Adjust(#[from] AdjustError),
+    #[error("invalid adjustment: {0}")]
+    Script(#[from] ScriptErrorKind),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("invalid session: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("can't export to {}, use a .csv or .json file", .0.display())]
+    ExportFormat(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(shell: &mut Shell, line: &str) -> String {
+        match shell.run_line(line).unwrap() {
+            Outcome::Print(s) => s,
+            Outcome::Exit => panic!("exited"),
+        }
+    }
+
+    fn dinitrogen_tetroxide() -> Shell {
+        let mut shell = Shell::default();
+        run(&mut shell, "load N2O4(g) <-> 2NO2(g)");
+        run(&mut shell, "concentrations 1 0.5");
+        shell
+    }
+
+    #[test]
+    fn load_and_adjust() {
+        let mut shell = Shell::default();
+        assert_eq!(
+            run(&mut shell, "load N2O4(g) <-> 2NO2(g)"),
+            "loaded N2O4(g) <-> 2NO2(g)"
+        );
+        assert_eq!(
+            run(&mut shell, "concentrations 1 0.5"),
+            "at equilibrium, K = 0.25"
+        );
+        assert!(run(&mut shell, "adjust c N2O4(g) 2").starts_with("shifted right, Q = 0.25"));
+        assert_eq!(run(&mut shell, "k"), "K = 0.25");
+    }
+
+    #[test]
+    fn k_and_q_differ() {
+        let mut shell = Shell::default();
+        run(&mut shell, "load N2O4(g) <-> 2NO2(g)");
+        assert!(matches!(shell.run_line("k"), Err(ReplError::NoSystem)));
+        assert_eq!(
+            run(&mut shell, "q"),
+            "Q is undefined, nothing has concentration"
+        );
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut shell = dinitrogen_tetroxide();
+        run(&mut shell, "volume 0.5");
+        assert_eq!(run(&mut shell, "undo"), "undid volume 0.5");
+        assert_eq!(run(&mut shell, "concentrations"), {
+            "Compound  Concentration (M)\n\
+             --------  -----------------\n\
+             N2O4(g)   1\n\
+             2NO2(g)   0.5\n"
+        });
+        assert_eq!(run(&mut shell, "redo"), "redid volume 0.5");
+        assert!(matches!(
+            shell.run_line("redo"),
+            Err(ReplError::NothingToRedo)
+        ));
+    }
+
+    #[test]
+    fn settings_before_start() {
+        let mut shell = Shell::default();
+        run(&mut shell, "load N2O4(g) <-> 2NO2(g)");
+        run(&mut shell, "volume 2");
+        run(&mut shell, "temperature -10");
+        run(&mut shell, "delta-h 57.2");
+        run(&mut shell, "concentrations 1 0.5");
+
+        let eq = shell.current().unwrap();
+        assert_eq!(eq.volume(), Some(2.0));
+        assert_eq!(eq.temperature(), Some(-10.0));
+        assert_eq!(eq.delta_h(), 57.2);
+    }
+
+    #[test]
+    fn balance_equation() {
+        let mut shell = Shell::default();
+        run(&mut shell, "load H2 + O2 <-> H2O");
+        assert_eq!(run(&mut shell, "balance"), "balanced 2H2 + O2 <-> 2H2O");
+        assert_eq!(shell.compound_names(), vec!["2H2", "O2", "2H2O"]);
+    }
+
+    #[test]
+    fn save_and_open() {
+        let mut shell = dinitrogen_tetroxide();
+        run(&mut shell, "adjust catalyst");
+        let path = std::env::temp_dir().join("lcp-sim-repl-test.json");
+        run(&mut shell, &format!("save {}", path.display()));
+
+        let mut opened = Shell::default();
+        run(&mut opened, &format!("open {}", path.display()));
+        fs::remove_file(path).unwrap();
+        assert_eq!(opened, shell);
+        assert_eq!(run(&mut opened, "undo"), "undid catalyst");
+    }
+
+    #[test]
+    fn export() {
+        let mut shell = dinitrogen_tetroxide();
+        run(&mut shell, "volume 2");
+        let path = std::env::temp_dir().join("lcp-sim-repl-test.csv");
+        run(&mut shell, &format!("export {}", path.display()));
+
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(matches!(
+            shell.run_line("export history.txt"),
+            Err(ReplError::ExportFormat(_))
+        ));
+    }
+
+    #[test]
+    fn bad_commands() {
+        let mut shell = Shell::default();
+        assert!(matches!(shell.run_line("stir"), Err(ReplError::Command(_))));
+        assert!(matches!(shell.run_line("show"), Err(ReplError::NoEquation)));
+        assert_eq!(shell.run_line("quit").unwrap(), Outcome::Exit);
+        assert!(matches!(shell.run_line("help"), Ok(Outcome::Print(_))));
+    }
+
+    #[test]
+    fn complete_names() {
+        let mut completer = ShellCompleter {
+            names: Arc::new(Mutex::new(vec!["N2O4(g)".into(), "2NO2(g)".into()])),
+        };
+        let values = |s: Vec<Suggestion>| s.into_iter().map(|s| s.value).collect::<Vec<_>>();
+
+        assert_eq!(values(completer.complete("adj", 3)), vec!["adjust"]);
+        assert_eq!(
+            values(completer.complete("adjust c N", 10)),
+            vec!["N2O4(g)"]
+        );
+    }
+}
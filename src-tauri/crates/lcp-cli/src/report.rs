@@ -0,0 +1,287 @@
+//! Results of each command, printed as a table or as JSON
+
+use std::fmt::{self, Display};
+
+use chatelier::{Direction, OwnedAdjustment};
+use chem_eq::{Equation, State};
+use serde::Serialize;
+
+use crate::script;
+
+/// The compounds of a parsed equation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseReport {
+    pub equation: String,
+    pub direction: chem_eq::Direction,
+    pub delta_h: f32,
+    pub compounds: Vec<CompoundReport>,
+}
+
+/// One compound of a [`ParseReport`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompoundReport {
+    pub name: String,
+    pub coefficient: usize,
+    pub state: Option<State>,
+    pub charge: Option<i32>,
+    pub elements: Vec<(String, usize)>,
+}
+
+impl From<&Equation> for ParseReport {
+    fn from(eq: &Equation) -> Self {
+        let compounds = eq
+            .compound_names()
+            .zip(eq.iter_compounds())
+            .map(|(name, cmp)| CompoundReport {
+                name: name.to_string(),
+                coefficient: cmp.coefficient,
+                state: cmp.state.clone(),
+                charge: cmp.charge,
+                elements: cmp
+                    .elements
+                    .iter()
+                    .map(|e| (e.symbol().to_string(), e.count))
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            equation: eq.equation().to_string(),
+            direction: eq.direction().clone(),
+            delta_h: eq.delta_h(),
+            compounds,
+        }
+    }
+}
+
+impl Display for ParseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.equation)?;
+        let rows = self
+            .compounds
+            .iter()
+            .map(|c| {
+                vec![
+                    c.name.clone(),
+                    c.coefficient.to_string(),
+                    c.state
+                        .as_ref()
+                        .map_or_else(String::new, ToString::to_string),
+                    c.charge.map_or_else(String::new, |c| format!("{:+}", c)),
+                    c.elements
+                        .iter()
+                        .map(|(s, n)| format!("{}{}", s, n))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ]
+            })
+            .collect();
+
+        Table::new(
+            ["Compound", "Coefficient", "State", "Charge", "Elements"],
+            rows,
+        )
+        .fmt(f)
+    }
+}
+
+/// An equation before and after balancing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceReport {
+    pub input: String,
+    pub balanced: String,
+}
+
+impl Display for BalanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.balanced)
+    }
+}
+
+/// The k expression of an equation at equilibrium
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EquilibriumReport {
+    pub equation: String,
+    pub concentrations: Vec<(String, f32)>,
+    /// [`None`] if a side has no concentration
+    pub k: Option<f32>,
+}
+
+impl Display for EquilibriumReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.equation)?;
+        let rows = self
+            .concentrations
+            .iter()
+            .map(|(n, c)| vec![n.clone(), number(*c)])
+            .collect();
+        Table::new(["Compound", "Concentration (M)"], rows).fmt(f)?;
+
+        match self.k {
+            Some(k) => writeln!(f, "K = {}", number(k)),
+            None => writeln!(f, "K is undefined, a side has no concentration"),
+        }
+    }
+}
+
+/// The state of a system after each adjustment of a script
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulateReport {
+    pub equation: String,
+    pub compounds: Vec<String>,
+    /// The first step is the system before any adjustment
+    pub steps: Vec<Step>,
+}
+
+/// One adjustment of a [`SimulateReport`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Step {
+    /// [`None`] for the starting state
+    pub adjustment: Option<OwnedAdjustment>,
+    pub shift: Direction,
+    pub k: f32,
+    pub concentrations: Vec<f32>,
+}
+
+impl Display for SimulateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.equation)?;
+        let headers = ["Step", "Adjustment", "Shift", "K"]
+            .into_iter()
+            .map(ToString::to_string)
+            .chain(self.compounds.iter().cloned())
+            .collect::<Vec<_>>();
+        let rows = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let shift = match step.shift {
+                    Direction::Forward => "right",
+                    Direction::Reverse => "left",
+                    Direction::None => "none",
+                };
+                [
+                    i.to_string(),
+                    step.adjustment
+                        .as_ref()
+                        .map_or_else(|| "start".to_string(), script::to_line),
+                    shift.to_string(),
+                    number(step.k),
+                ]
+                .into_iter()
+                .chain(step.concentrations.iter().map(|c| number(*c)))
+                .collect()
+            })
+            .collect();
+
+        Table::new(headers, rows).fmt(f)
+    }
+}
+
+/// Format a number to be readable in a table
+pub(crate) fn number(n: f32) -> String {
+    if n != 0.0 && (n.abs() < 1e-3 || n.abs() >= 1e5) {
+        format!("{:.3e}", n)
+    } else {
+        let s = format!("{:.4}", n);
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// A plain text table with left-aligned columns
+pub(crate) struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub(crate) fn new<H: IntoIterator<Item = impl ToString>>(
+        headers: H,
+        rows: Vec<Vec<String>>,
+    ) -> Self {
+        Self {
+            headers: headers.into_iter().map(|h| h.to_string()).collect(),
+            rows,
+        }
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths = (0..self.headers.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .filter_map(|r| r.get(i))
+                    .chain(Some(&self.headers[i]))
+                    .map(|s| s.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let write_row = |f: &mut fmt::Formatter<'_>, row: &[String]| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(s, &w)| format!("{:<w$}", s, w = w))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())
+        };
+
+        write_row(f, &self.headers)?;
+        let rule = widths.iter().map(|&w| "-".repeat(w)).collect::<Vec<_>>();
+        write_row(f, &rule)?;
+        for row in &self.rows {
+            write_row(f, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_alignment() {
+        let table = Table::new(
+            ["Compound", "M"],
+            vec![
+                vec!["H2".to_string(), "1".to_string()],
+                vec!["H2O(l)".to_string(), "0.25".to_string()],
+            ],
+        );
+        assert_eq!(
+            table.to_string(),
+            "Compound  M\n--------  ----\nH2        1\nH2O(l)    0.25\n"
+        );
+    }
+
+    #[test]
+    fn number_format() {
+        assert_eq!(number(0.0), "0");
+        assert_eq!(number(0.25), "0.25");
+        assert_eq!(number(2.0), "2");
+        assert_eq!(number(4.3e-7), "4.300e-7");
+    }
+
+    #[test]
+    fn parse_report() {
+        let eq = Equation::new("Ag+(aq) + Cl-(aq) <-> AgCl(s)").unwrap();
+        let report = ParseReport::from(&eq);
+        assert_eq!(report.compounds[0].charge, Some(1));
+        assert_eq!(
+            report.to_string(),
+            "Ag+(aq) + Cl-(aq) <-> AgCl(s)\n\
+             Compound  Coefficient  State  Charge  Elements\n\
+             --------  -----------  -----  ------  --------\n\
+             Ag+(aq)   1            (aq)   +1      Ag1\n\
+             Cl-(aq)   1            (aq)   -1      Cl1\n\
+             AgCl(s)   1            (s)            Ag1 Cl1\n"
+        );
+    }
+}
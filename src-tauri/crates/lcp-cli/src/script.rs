@@ -0,0 +1,210 @@
+//! Parsing scripts of adjustments for `lcp-sim simulate`
+//!
+//! A script has one adjustment per line. Anything after a `#` is a comment, and blank lines are
+//! ignored.
+//!
+//! ```text
+//! # add reactant, then compress the container
+//! concentration N2O4(g) 2.0
+//! volume 0.5
+//! temperature 100
+//! catalyst
+//! inert-gas pressure 1.0
+//! ```
+//!
+//! Compounds can be given by name or by index. Adjustments on one line separated by `;` are
+//! made together before the system reacts. A line can also be an adjustment in JSON, like
+//! `{"Concentration":[0,2.0]}`.
+
+use chatelier::{InertGas, OwnedAdjustment, Target};
+use thiserror::Error;
+
+/// Parse every adjustment in a script
+pub fn parse(script: &str) -> Result<Vec<OwnedAdjustment>, ScriptError> {
+    script
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line, text)| parse_line(text).map_err(|kind| ScriptError { line, kind }))
+        .collect()
+}
+
+/// Parse one adjustment
+pub(crate) fn parse_line(line: &str) -> Result<OwnedAdjustment, ScriptErrorKind> {
+    if line.starts_with('{') || line.starts_with('"') {
+        return serde_json::from_str(line).map_err(|e| ScriptErrorKind::Json(e.to_string()));
+    }
+    if line.contains(';') {
+        return line
+            .split(';')
+            .map(|a| parse_line(a.trim()))
+            .collect::<Result<_, _>>()
+            .map(OwnedAdjustment::Batch);
+    }
+
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let adjust = match command {
+        "concentration" | "c" => {
+            let target = words.next().ok_or(ScriptErrorKind::MissingArgument)?;
+            let target = target
+                .parse::<usize>()
+                .map_or_else(|_| Target::from(target), Target::from);
+            OwnedAdjustment::Concentration(target, number(words.next())?)
+        }
+        "volume" | "v" => OwnedAdjustment::Volume(number(words.next())?),
+        "temperature" | "t" => OwnedAdjustment::Temperature(number(words.next())?),
+        "catalyst" => OwnedAdjustment::Catalyst,
+        "inert-gas" => {
+            let gas = match words.next() {
+                Some("volume") => InertGas::AtConstantVolume(number(words.next())?),
+                Some("pressure") => InertGas::AtConstantPressure(number(words.next())?),
+                Some(s) => return Err(ScriptErrorKind::UnknownInertGas(s.to_string())),
+                None => return Err(ScriptErrorKind::MissingArgument),
+            };
+            OwnedAdjustment::InertGas(gas)
+        }
+        _ => return Err(ScriptErrorKind::UnknownCommand(command.to_string())),
+    };
+
+    match words.next() {
+        Some(extra) => Err(ScriptErrorKind::TooManyArguments(extra.to_string())),
+        None => Ok(adjust),
+    }
+}
+
+/// Write an adjustment as a line of a script
+pub fn to_line(adjust: &OwnedAdjustment) -> String {
+    match adjust {
+        OwnedAdjustment::Concentration(Target::Index(idx), cnc) => {
+            format!("concentration {} {}", idx, cnc)
+        }
+        OwnedAdjustment::Concentration(Target::Name(name), cnc) => {
+            format!("concentration {} {}", name, cnc)
+        }
+        OwnedAdjustment::Volume(v) => format!("volume {}", v),
+        OwnedAdjustment::Temperature(t) => format!("temperature {}", t),
+        OwnedAdjustment::Catalyst => "catalyst".to_string(),
+        OwnedAdjustment::InertGas(InertGas::AtConstantVolume(amount)) => {
+            format!("inert-gas volume {}", amount)
+        }
+        OwnedAdjustment::InertGas(InertGas::AtConstantPressure(amount)) => {
+            format!("inert-gas pressure {}", amount)
+        }
+        OwnedAdjustment::Batch(adjusts) => {
+            adjusts.iter().map(to_line).collect::<Vec<_>>().join("; ")
+        }
+    }
+}
+
+fn number(word: Option<&str>) -> Result<f32, ScriptErrorKind> {
+    let word = word.ok_or(ScriptErrorKind::MissingArgument)?;
+    word.parse()
+        .map_err(|_| ScriptErrorKind::InvalidNumber(word.to_string()))
+}
+
+/// An error in a script, with the line it's on
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {kind}")]
+pub struct ScriptError {
+    /// The line number, starting from 1
+    pub line: usize,
+    /// What went wrong
+    pub kind: ScriptErrorKind,
+}
+
+/// What went wrong in a [`ScriptError`]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScriptErrorKind {
+    #[error("unknown command {0:?}")]
+    UnknownCommand(String),
+    #[error("inert gas must be added at constant \"volume\" or \"pressure\", not {0:?}")]
+    UnknownInertGas(String),
+    #[error("missing argument")]
+    MissingArgument,
+    #[error("unexpected argument {0:?}")]
+    TooManyArguments(String),
+    #[error("invalid number {0:?}")]
+    InvalidNumber(String),
+    #[error("invalid json: {0}")]
+    Json(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_script() {
+        let script = "
+            # comment
+            concentration N2O4(g) 2.0
+            c 1 0.5
+            volume 0.5 # compress
+
+            t 100
+            catalyst
+            inert-gas pressure 1
+            {\"Volume\":2.0}
+            c 0 3; volume 0.25
+        ";
+        assert_eq!(
+            parse(script),
+            Ok(vec![
+                OwnedAdjustment::Concentration("N2O4(g)".into(), 2.0),
+                OwnedAdjustment::Concentration(1.into(), 0.5),
+                OwnedAdjustment::Volume(0.5),
+                OwnedAdjustment::Temperature(100.0),
+                OwnedAdjustment::Catalyst,
+                OwnedAdjustment::InertGas(InertGas::AtConstantPressure(1.0)),
+                OwnedAdjustment::Volume(2.0),
+                OwnedAdjustment::Batch(vec![
+                    OwnedAdjustment::Concentration(0.into(), 3.0),
+                    OwnedAdjustment::Volume(0.25),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn round_trip_lines() {
+        for line in [
+            "concentration N2O4(g) 2",
+            "concentration 1 0.5",
+            "temperature -10",
+            "catalyst",
+            "inert-gas volume 1.5",
+            "volume 2; concentration 0 1",
+        ] {
+            assert_eq!(to_line(&parse_line(line).unwrap()), line);
+        }
+    }
+
+    #[test]
+    fn script_errors() {
+        assert_eq!(
+            parse("volume 1\nvolume lots"),
+            Err(ScriptError {
+                line: 2,
+                kind: ScriptErrorKind::InvalidNumber("lots".to_string())
+            })
+        );
+        assert_eq!(
+            parse("stir").unwrap_err().kind,
+            ScriptErrorKind::UnknownCommand("stir".to_string())
+        );
+        assert_eq!(
+            parse("concentration H2").unwrap_err().kind,
+            ScriptErrorKind::MissingArgument
+        );
+        assert_eq!(
+            parse("catalyst please").unwrap_err().kind,
+            ScriptErrorKind::TooManyArguments("please".to_string())
+        );
+        assert_eq!(
+            parse("inert-gas both 1").unwrap_err().kind,
+            ScriptErrorKind::UnknownInertGas("both".to_string())
+        );
+    }
+}
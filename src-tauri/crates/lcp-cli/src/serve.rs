@@ -0,0 +1,387 @@
+//! A local HTTP server for the simulator
+//!
+//! JSON-RPC requests are `POST`ed to `/`, and answered by an [`Rpc`]. Each connection gets
+//! its own thread and is closed after one response.
+//!
+//! Any web page open in a browser can send requests to a local port, so requests must have a
+//! `Content-Type` of `application/json`, which a page can't send without the server agreeing
+//! to it, and an `Origin`, if there is one, and `Host` on this machine. Browsers always send
+//! an `Origin` with a `POST` from a page, other clients usually don't send one.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use lcp_service::{rpc::Rpc, session::Session};
+
+/// Largest request head read
+const MAX_HEAD: usize = 16 * 1024;
+
+/// Largest request body read
+const MAX_BODY: usize = 1024 * 1024;
+
+/// Most connections answered at once, more are turned away
+const MAX_CONNECTIONS: usize = 64;
+
+/// How long a client can take to send each part of a request, or read the response
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serve the simulator on `addr` until the process is stopped
+pub fn run(addr: SocketAddr, session: Option<&Path>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let rpc = match session {
+        Some(path) => Rpc::new(Session::load(path), Some(path.to_path_buf())),
+        None => Rpc::new(Session::new(), None),
+    };
+    eprintln!("serving JSON-RPC on http://{}", listener.local_addr()?);
+
+    serve(listener, Arc::new(rpc))
+}
+
+/// Answer every connection to `listener`
+pub fn serve(listener: TcpListener, rpc: Arc<Rpc>) -> io::Result<()> {
+    let local = listener.local_addr()?.ip();
+    let open = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let connection = Connection::open(&open);
+        if connection.is_none() {
+            if let Err(e) = write_response(&mut stream, Response::empty("503 Service Unavailable"))
+            {
+                eprintln!("error: {}", e);
+            }
+            continue;
+        }
+        let rpc = Arc::clone(&rpc);
+        thread::spawn(move || {
+            let _connection = connection;
+            if let Err(e) = handle(stream, &rpc, local) {
+                eprintln!("error: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// One of the connections being answered, counted until it's dropped
+struct Connection(Arc<AtomicUsize>);
+
+impl Connection {
+    /// Count a new connection, [`None`] if there are already [`MAX_CONNECTIONS`]
+    fn open(open: &Arc<AtomicUsize>) -> Option<Self> {
+        open.fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+            (n < MAX_CONNECTIONS).then_some(n + 1)
+        })
+        .ok()?;
+
+        Some(Self(Arc::clone(open)))
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A response to send back
+struct Response {
+    status: &'static str,
+    body: Option<String>,
+}
+
+impl Response {
+    fn empty(status: &'static str) -> Self {
+        Self { status, body: None }
+    }
+}
+
+fn handle(mut stream: TcpStream, rpc: &Rpc, local: IpAddr) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_head(&mut reader)? {
+        Some(head) => respond(&head, &mut reader, rpc, local)?,
+        None => Response::empty("400 Bad Request"),
+    };
+
+    write_response(&mut stream, response)
+}
+
+fn write_response(stream: &mut TcpStream, response: Response) -> io::Result<()> {
+    let body = response.body.unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Read up to the end of the headers, [`None`] if they're too long
+fn read_head(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut head = vec![];
+    while !head.ends_with(b"\r\n\r\n") {
+        if reader.read_until(b'\n', &mut head)? == 0 || head.len() > MAX_HEAD {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(head))
+}
+
+/// The value of a header, [`None`] if it's missing or not UTF-8
+fn header<'a>(request: &httparse::Request<'_, 'a>, name: &str) -> Option<&'a str> {
+    request
+        .headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .and_then(|h| std::str::from_utf8(h.value).ok())
+        .map(str::trim)
+}
+
+/// If `host`, with or without a port, is this machine
+fn is_local(host: &str, local: IpAddr) -> bool {
+    let host = match host.strip_prefix('[') {
+        // an IPv6 address, with or without a port
+        Some(host) => host.split(']').next().unwrap_or_default(),
+        None => match host.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => host,
+            _ => host,
+        },
+    };
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(ip) => ip.is_loopback() || ip == local,
+        Err(_) => false,
+    }
+}
+
+fn respond(head: &[u8], reader: &mut impl Read, rpc: &Rpc, local: IpAddr) -> io::Result<Response> {
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut request = httparse::Request::new(&mut headers);
+    if !matches!(request.parse(head), Ok(httparse::Status::Complete(_))) {
+        return Ok(Response::empty("400 Bad Request"));
+    }
+    match (request.method, request.path) {
+        (Some("POST"), Some("/")) => {}
+        (_, Some("/")) => return Ok(Response::empty("405 Method Not Allowed")),
+        _ => return Ok(Response::empty("404 Not Found")),
+    }
+
+    // a page on another site, or one that resolves its own name to this machine
+    let local_host = matches!(header(&request, "host"), Some(host) if is_local(host, local));
+    let local_origin = match header(&request, "origin") {
+        Some(origin) => {
+            matches!(origin.split_once("://"), Some((_, host)) if is_local(host, local))
+        }
+        None => true,
+    };
+    if !local_host || !local_origin {
+        return Ok(Response::empty("403 Forbidden"));
+    }
+    let json = header(&request, "content-type")
+        .and_then(|ty| ty.split(';').next())
+        .map(str::trim);
+    if !matches!(json, Some(ty) if ty.eq_ignore_ascii_case("application/json")) {
+        return Ok(Response::empty("415 Unsupported Media Type"));
+    }
+
+    let length = header(&request, "content-length").and_then(|h| h.parse::<usize>().ok());
+    let length = match length {
+        Some(length) if length > MAX_BODY => return Ok(Response::empty("413 Payload Too Large")),
+        Some(length) => length,
+        None => return Ok(Response::empty("411 Length Required")),
+    };
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(Response::empty("400 Bad Request"));
+    };
+
+    Ok(match rpc.handle(&body) {
+        Some(body) => Response {
+            status: "200 OK",
+            body: Some(body),
+        },
+        // only notifications, so nothing to say
+        None => Response::empty("204 No Content"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Start a server on a free port, returning its address
+    fn start() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let rpc = Arc::new(Rpc::new(Session::new(), None));
+        thread::spawn(move || serve(listener, rpc));
+        addr
+    }
+
+    /// Send a raw request, returning the status line and body
+    fn send(addr: SocketAddr, request: &str) -> (String, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    fn post(addr: SocketAddr, body: Value) -> Value {
+        let body = body.to_string();
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (status, body) = send(addr, &request);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[test]
+    fn over_http() {
+        let addr = start();
+        let res = post(
+            addr,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "add_system", "params": {
+                "eqStr": "N2O4(g) <-> 2NO2(g)", "idx": 0, "concentrations": [1.0, 0.5]
+            }}),
+        );
+        assert_eq!(res["result"], Value::Null);
+        assert!(res.get("error").is_none());
+
+        let res = post(
+            addr,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "update_system", "params": {
+                "idx": 0, "adjust": {"Volume": 2.0}
+            }}),
+        );
+        assert!(res.get("error").is_none());
+        let res = post(
+            addr,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "get_sys_concentration", "params": {"idx": 0}}),
+        );
+        let concentrations = res["result"].as_array().unwrap();
+        // doubling the volume halves the concentrations, then the system shifts right
+        assert!(concentrations[0].as_f64().unwrap() < 0.5);
+        assert!(concentrations[1].as_f64().unwrap() > 0.25);
+    }
+
+    #[test]
+    fn bad_requests() {
+        let addr = start();
+        let (status, _) = send(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 405 Method Not Allowed");
+        let (status, _) = send(addr, "POST /rpc HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        let (status, _) = send(
+            addr,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\r\n",
+        );
+        assert_eq!(status, "HTTP/1.1 411 Length Required");
+        let (status, _) = send(addr, "nonsense\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+    }
+
+    #[test]
+    fn cross_site_requests() {
+        let addr = start();
+        let body = r#"{"jsonrpc": "2.0", "id": 1, "method": "get_question_count"}"#;
+        let request = |headers: &str| {
+            let request = format!(
+                "POST / HTTP/1.1\r\n{}Content-Length: {}\r\n\r\n{}",
+                headers,
+                body.len(),
+                body
+            );
+            send(addr, &request).0
+        };
+
+        // a form or `fetch` without preflight can only send text/plain
+        assert_eq!(
+            request("Host: localhost\r\nContent-Type: text/plain\r\n"),
+            "HTTP/1.1 415 Unsupported Media Type"
+        );
+        assert_eq!(
+            request("Host: localhost\r\n"),
+            "HTTP/1.1 415 Unsupported Media Type"
+        );
+        assert_eq!(
+            request(
+                "Host: localhost\r\nOrigin: https://example.com\r\nContent-Type: application/json\r\n"
+            ),
+            "HTTP/1.1 403 Forbidden"
+        );
+        assert_eq!(
+            request("Host: example.com\r\nContent-Type: application/json\r\n"),
+            "HTTP/1.1 403 Forbidden"
+        );
+        assert_eq!(
+            request("Content-Type: application/json\r\n"),
+            "HTTP/1.1 403 Forbidden"
+        );
+        assert_eq!(
+            request(&format!(
+                "Host: {}\r\nOrigin: http://localhost:5173\r\nContent-Type: application/json; charset=utf-8\r\n",
+                addr
+            )),
+            "HTTP/1.1 200 OK"
+        );
+    }
+
+    #[test]
+    fn local_hosts() {
+        let local = IpAddr::from([192, 168, 1, 4]);
+        assert!(is_local("localhost", local));
+        assert!(is_local("LOCALHOST:7878", local));
+        assert!(is_local("127.0.0.1:7878", local));
+        assert!(is_local("[::1]:7878", local));
+        assert!(is_local("[::1]", local));
+        assert!(is_local("192.168.1.4", local));
+        assert!(!is_local("192.168.1.5:7878", local));
+        assert!(!is_local("localhost.example.com", local));
+        assert!(!is_local("", local));
+    }
+
+    #[test]
+    fn connections_are_bounded() {
+        let addr = start();
+        let mut stalled = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(addr).unwrap())
+            .collect::<Vec<_>>();
+        for stream in &mut stalled {
+            stream.write_all(b"POST / HTTP/1.1\r\n").unwrap();
+        }
+
+        // too many connections are turned away before their request is read
+        let mut response = String::new();
+        TcpStream::connect(addr)
+            .unwrap()
+            .read_to_string(&mut response)
+            .unwrap();
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    }
+}
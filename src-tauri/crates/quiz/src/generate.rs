@@ -0,0 +1,323 @@
+//! Generating random multiple choice questions
+
+use chatelier::{Direction, InertGas, OwnedAdjustment, System};
+use chem_eq::Equation;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{Check, Choice, Kind, MultipleChoice, Question, QuestionError};
+
+/// Reversible reactions questions are generated from, with ΔH of the forward reaction in kJ
+const EQUATIONS: [(&str, f32); 7] = [
+    ("N2O4(g) <-> 2NO2(g)", 57.2),
+    ("N2(g) + 3H2(g) <-> 2NH3(g)", -92.2),
+    ("2SO2(g) + O2(g) <-> 2SO3(g)", -197.8),
+    ("H2(g) + I2(g) <-> 2HI(g)", 9.5),
+    ("CO(g) + 2H2(g) <-> CH3OH(g)", -90.7),
+    ("PCl5(g) <-> PCl3(g) + Cl2(g)", 87.9),
+    ("CO(g) + H2O(g) <-> CO2(g) + H2(g)", -41.2),
+];
+
+/// Generates random multiple choice questions. The same seed always generates the same
+/// questions.
+///
+/// Each question starts one of several reversible reactions at equilibrium with random
+/// concentrations, and asks either which way an adjustment shifts the system or which of
+/// several adjustments shifts it a given way. Every answer is computed by the simulator and
+/// checked with [`Question::verify`].
+///
+/// ## Examples
+///
+/// ```rust
+/// use quiz::Generator;
+///
+/// let quiz = Generator::new(42).quiz(10).unwrap();
+/// assert_eq!(quiz.len(), 10);
+/// assert!(quiz.iter().all(|q| q.verify().is_ok()));
+///
+/// // the same seed gives the same quiz
+/// assert_eq!(Generator::new(42).quiz(10).unwrap(), quiz);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Generator {
+    rng: ChaCha8Rng,
+    next_id: usize,
+}
+
+/// An adjustment a question can be about, with how it's described and how it shifts the
+/// system
+struct Candidate {
+    adjust: OwnedAdjustment,
+    text: String,
+    shift: Direction,
+    explanation: String,
+}
+
+impl Generator {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            next_id: 1,
+        }
+    }
+
+    /// Generate several questions, numbered from where the generator left off
+    pub fn quiz(&mut self, count: usize) -> Result<Vec<Question>, QuestionError> {
+        (0..count).map(|_| self.question()).collect()
+    }
+
+    /// Generate the next question
+    pub fn question(&mut self) -> Result<Question, QuestionError> {
+        let (equation, delta_h) = *EQUATIONS.choose(&mut self.rng).unwrap();
+        let mut eq = Equation::new(equation)?;
+        let defaults = (0..eq.num_compounds())
+            .map(|_| self.rng.gen_range(5..=25) as f32 / 10.0)
+            .collect::<Vec<_>>();
+        eq.set_concentrations(&defaults)?;
+        eq.set_delta_h(delta_h);
+        let system = System::new(eq)?;
+        let candidates = candidates(&system)?;
+
+        let heat = match delta_h < 0.0 {
+            true => "exothermic",
+            false => "endothermic",
+        };
+        let kind = system
+            .equation()
+            .classify()
+            .map_or_else(String::new, |kind| format!(" {}", kind));
+        let given = format!(
+            "The forward reaction is an {}{} reaction (ΔH = {} kJ).",
+            heat, kind, delta_h
+        );
+        let (prompt, q) = if self.rng.gen_bool(0.5) {
+            self.predict(&candidates, given)
+        } else {
+            self.shift(&candidates, given)
+        };
+
+        let question = Question {
+            id: self.next_id,
+            equation: equation.to_string(),
+            prompt,
+            defaults,
+            delta_h: Some(delta_h),
+            q: Kind::MultipleChoice(q),
+        };
+        question.verify()?;
+        self.next_id += 1;
+
+        Ok(question)
+    }
+
+    /// Ask which way one adjustment shifts the system
+    fn predict(&mut self, candidates: &[Candidate], given: String) -> (String, MultipleChoice) {
+        let candidate = candidates.choose(&mut self.rng).unwrap();
+        let mut shifts = [Direction::Forward, Direction::Reverse, Direction::None];
+        shifts.shuffle(&mut self.rng);
+
+        let choices = shifts
+            .iter()
+            .map(|&shift| Choice {
+                text: shift_text(shift).to_string(),
+                explanation: if shift == candidate.shift {
+                    candidate.explanation.clone()
+                } else {
+                    format!(
+                        "It's not that {}. {}",
+                        shift_text(shift).to_lowercase(),
+                        candidate.explanation
+                    )
+                },
+                adjustment: None,
+                shift: Some(shift),
+                delta_h: None,
+            })
+            .collect();
+        // lowercase the verb, but not the compound names
+        let (verb, rest) = candidate.text.split_at(1);
+        let prompt = format!(
+            "{} What happens if you {}{}?",
+            given,
+            verb.to_lowercase(),
+            rest
+        );
+        let mc = MultipleChoice {
+            check: Check::Predict(candidate.adjust.clone()),
+            choices,
+            correct: shifts.iter().position(|&s| s == candidate.shift).unwrap(),
+        };
+
+        (prompt, mc)
+    }
+
+    /// Ask which of four adjustments shifts the system a given way
+    fn shift(&mut self, candidates: &[Candidate], given: String) -> (String, MultipleChoice) {
+        let goal = *[Direction::Forward, Direction::Reverse]
+            .choose(&mut self.rng)
+            .unwrap();
+        let right = candidates.iter().filter(|c| c.shift == goal);
+        let wrong = candidates.iter().filter(|c| c.shift != goal);
+        // reactants and products can always be added or removed, so there's a right choice
+        // and at least three wrong ones
+        let mut options = right
+            .collect::<Vec<_>>()
+            .choose_multiple(&mut self.rng, 1)
+            .chain(wrong.collect::<Vec<_>>().choose_multiple(&mut self.rng, 3))
+            .copied()
+            .collect::<Vec<_>>();
+        let answer = options[0].text.clone();
+        options.shuffle(&mut self.rng);
+
+        let choices = options
+            .iter()
+            .map(|c| Choice {
+                text: c.text.clone(),
+                explanation: c.explanation.clone(),
+                adjustment: Some(c.adjust.clone()),
+                shift: None,
+                delta_h: None,
+            })
+            .collect();
+        let direction = match goal {
+            Direction::Forward => "right",
+            _ => "left",
+        };
+        let prompt = format!(
+            "{} Which change will cause an equilibrium shift to the {}?",
+            given, direction
+        );
+        let mc = MultipleChoice {
+            check: Check::Shift(goal),
+            choices,
+            correct: options.iter().position(|c| c.text == answer).unwrap(),
+        };
+
+        (prompt, mc)
+    }
+}
+
+/// Every adjustment a question could be about
+fn candidates(system: &System) -> Result<Vec<Candidate>, QuestionError> {
+    let eq = system.equation();
+    let mut adjusts = vec![];
+    for (i, (name, cnc)) in eq.name_and_concentration().enumerate() {
+        // "2NO2(g)" is shown as "NO2(g)"
+        let name = name.trim_start_matches(|c: char| c.is_ascii_digit());
+        let up = (cnc * 150.0).round() / 100.0;
+        let down = (cnc * 50.0).round() / 100.0;
+        adjusts.push((
+            OwnedAdjustment::Concentration(i.into(), up),
+            format!("Increase the concentration of {}", name),
+        ));
+        adjusts.push((
+            OwnedAdjustment::Concentration(i.into(), down),
+            format!("Decrease the concentration of {}", name),
+        ));
+    }
+    adjusts.extend([
+        (OwnedAdjustment::Volume(0.5), "Halve the volume".to_string()),
+        (
+            OwnedAdjustment::Volume(2.0),
+            "Double the volume".to_string(),
+        ),
+        (
+            OwnedAdjustment::Temperature(50.0),
+            "Raise the temperature to 50 °C".to_string(),
+        ),
+        (
+            OwnedAdjustment::Temperature(0.0),
+            "Lower the temperature to 0 °C".to_string(),
+        ),
+        (OwnedAdjustment::Catalyst, "Add a catalyst".to_string()),
+        (
+            OwnedAdjustment::InertGas(InertGas::AtConstantVolume(1.0)),
+            "Add argon at constant volume".to_string(),
+        ),
+    ]);
+
+    adjusts
+        .into_iter()
+        .map(|(adjust, text)| {
+            let explanation = system.explain(adjust.as_adjustment(eq)?)?;
+            Ok(Candidate {
+                shift: explanation.direction,
+                explanation: explanation.to_string(),
+                adjust,
+                text,
+            })
+        })
+        .collect()
+}
+
+fn shift_text(shift: Direction) -> &'static str {
+    match shift {
+        Direction::Forward => "The equilibrium shifts right",
+        Direction::Reverse => "The equilibrium shifts left",
+        Direction::None => "The equilibrium doesn't shift",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn many_seeds_verify() {
+        for seed in 0..50 {
+            for question in Generator::new(seed).quiz(5).unwrap() {
+                assert_eq!(question.verify(), Ok(()), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn seeds_differ() {
+        assert_ne!(
+            Generator::new(1).quiz(5).unwrap(),
+            Generator::new(2).quiz(5).unwrap()
+        );
+    }
+
+    #[test]
+    fn distinct_choices() {
+        for question in Generator::new(7).quiz(20).unwrap() {
+            let mc = match question.q {
+                Kind::MultipleChoice(mc) => mc,
+                Kind::Interactive(_) => panic!("generated an interactive question"),
+            };
+            let mut texts = mc.choices.iter().map(|c| &c.text).collect::<Vec<_>>();
+            texts.sort();
+            texts.dedup();
+            assert_eq!(texts.len(), mc.choices.len());
+            assert!(question.prompt.contains("ΔH"));
+            assert!(question.prompt.contains(" reaction (ΔH"));
+            assert!(!question.prompt.contains("concentration of n"));
+            assert!(!question.prompt.contains("concentration of 2"));
+        }
+    }
+
+    #[test]
+    fn predict_explains_each_choice() {
+        let mut generator = Generator::new(3);
+        let mut predicted = 0;
+        for question in generator.quiz(20).unwrap() {
+            let Kind::MultipleChoice(mc) = question.q else {
+                panic!("generated an interactive question");
+            };
+            if !matches!(mc.check, Check::Predict(_)) {
+                continue;
+            }
+            predicted += 1;
+            let correct = &mc.choices[mc.correct].explanation;
+            for (i, choice) in mc.choices.iter().enumerate() {
+                if i != mc.correct {
+                    assert_ne!(&choice.explanation, correct);
+                    assert!(choice.explanation.ends_with(correct.as_str()));
+                }
+            }
+        }
+        assert!(predicted > 0);
+    }
+}
@@ -0,0 +1,170 @@
+//! # `quiz`
+//!
+//! Le Chatelier's Principle quiz questions, stored as JSON files and checked against the
+//! simulator in [`chatelier`]. Every answer key is verified with [`Question::verify`] when a
+//! [`Bank`] is loaded, so a question with a wrong answer can't reach students.
+//!
+//! The questions shipped with the app are in the `questions` directory of this crate, one file
+//! per question, and are loaded with [`Bank::builtin`]. New questions can also be made from a
+//! seed with a [`Generator`].
+
+use std::{fs, io, path::Path};
+
+use thiserror::Error;
+
+mod generate;
+mod question;
+
+pub use generate::Generator;
+pub use question::{Check, Choice, Interactive, Kind, MultipleChoice, Question, QuestionError};
+
+/// The questions shipped with the app, in order
+const BUILTIN: [&str; 5] = [
+    include_str!("../questions/01-shift-right.json"),
+    include_str!("../questions/02-produce-ammonia.json"),
+    include_str!("../questions/03-increase-ammonia.json"),
+    include_str!("../questions/04-increase-volume.json"),
+    include_str!("../questions/05-exothermic.json"),
+];
+
+/// A set of verified questions, ordered by id
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Bank {
+    questions: Vec<Question>,
+}
+
+impl Bank {
+    /// Build a bank, verifying every question
+    pub fn new(mut questions: Vec<Question>) -> Result<Self, BankError> {
+        questions.sort_by_key(|q| q.id);
+        for pair in questions.windows(2) {
+            if pair[0].id == pair[1].id {
+                return Err(BankError::DuplicateId(pair[0].id));
+            }
+        }
+        for question in &questions {
+            question.verify().map_err(|source| BankError::Question {
+                id: question.id,
+                source,
+            })?;
+        }
+
+        Ok(Self { questions })
+    }
+
+    /// The questions shipped with the app
+    ///
+    /// ## Panics
+    ///
+    /// If a builtin question is invalid, which the tests of this crate check for
+    pub fn builtin() -> Self {
+        let questions = BUILTIN
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<_, _>>()
+            .expect("builtin questions are valid json");
+
+        Self::new(questions).expect("builtin questions are correct")
+    }
+
+    /// Load every `.json` file in a directory, each holding one question
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, BankError> {
+        let mut questions = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension() != Some("json".as_ref()) {
+                continue;
+            }
+            let question =
+                serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| BankError::Json {
+                    file: path.display().to_string(),
+                    message: e.to_string(),
+                })?;
+            questions.push(question);
+        }
+
+        Self::new(questions)
+    }
+
+    /// Get a question by id
+    pub fn get(&self, id: usize) -> Option<&Question> {
+        self.questions.iter().find(|q| q.id == id)
+    }
+
+    /// Iterate over the questions in order
+    pub fn iter(&self) -> impl Iterator<Item = &Question> {
+        self.questions.iter()
+    }
+
+    /// The number of questions
+    pub fn len(&self) -> usize {
+        self.questions.len()
+    }
+
+    /// If there are no questions
+    pub fn is_empty(&self) -> bool {
+        self.questions.is_empty()
+    }
+}
+
+/// An error loading a [`Bank`]
+#[derive(Debug, Error)]
+pub enum BankError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("invalid question in {file}: {message}")]
+    Json { file: String, message: String },
+    #[error("question {0} appears more than once")]
+    DuplicateId(usize),
+    #[error("question {id}: {source}")]
+    Question { id: usize, source: QuestionError },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_answers_are_correct() {
+        for json in BUILTIN {
+            let question: Question = serde_json::from_str(json).unwrap();
+            assert_eq!(question.verify(), Ok(()), "question {}", question.id);
+        }
+
+        let bank = Bank::builtin();
+        assert_eq!(
+            bank.iter().map(|q| q.id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn load_directory() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("questions");
+        assert_eq!(Bank::load(dir).unwrap(), Bank::builtin());
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let question = Bank::builtin().get(1).unwrap().clone();
+        assert!(matches!(
+            Bank::new(vec![question.clone(), question]),
+            Err(BankError::DuplicateId(1))
+        ));
+    }
+
+    #[test]
+    fn wrong_answer_key() {
+        let mut question = Bank::builtin().get(1).unwrap().clone();
+        if let Kind::MultipleChoice(mc) = &mut question.q {
+            mc.correct = 0;
+        }
+        assert!(matches!(
+            Bank::new(vec![question]),
+            Err(BankError::Question {
+                id: 1,
+                source: QuestionError::WrongAnswer { correct: 0, .. }
+            })
+        ));
+    }
+}
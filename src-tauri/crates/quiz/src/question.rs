@@ -0,0 +1,302 @@
+//! The schema of a quiz question, and checking its answer against the simulator
+
+use chatelier::{AdjustError, Direction, OwnedAdjustment, System, SystemError, Target};
+use chem_eq::{
+    error::{ConcentrationError, EquationError},
+    Equation,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A quiz question about a system starting at equilibrium
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Question {
+    /// Position in the quiz, starting from 1
+    pub id: usize,
+    /// The equation of the system, eg. `"N2O4(g) <-> 2NO2(g)"`
+    pub equation: String,
+    /// What the student is asked
+    pub prompt: String,
+    /// Starting concentrations of each compound in M (mol/L), at equilibrium
+    pub defaults: Vec<f32>,
+    /// ΔH of the forward reaction in kJ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_h: Option<f32>,
+    /// How the question is answered
+    pub q: Kind,
+}
+
+/// How a [`Question`] is answered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Kind {
+    /// Pick one of several choices
+    MultipleChoice(MultipleChoice),
+    /// Change a concentration with sliders
+    Interactive(Interactive),
+}
+
+/// A multiple choice question, with one correct choice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultipleChoice {
+    /// How the simulator decides which choice is right
+    pub check: Check,
+    /// The choices, in the order they're shown
+    pub choices: Vec<Choice>,
+    /// The index of the correct choice
+    pub correct: usize,
+}
+
+/// How the simulator decides which choice of a [`MultipleChoice`] is right
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Check {
+    /// Each choice is an [`Choice::adjustment`], the right one shifts the system this way
+    Shift(Direction),
+    /// Each choice is a [`Choice::shift`], the right one is how this adjustment shifts the
+    /// system
+    Predict(OwnedAdjustment),
+    /// Each choice is a [`Choice::delta_h`], the right one makes this adjustment shift the
+    /// system this way
+    DeltaH {
+        adjustment: OwnedAdjustment,
+        shift: Direction,
+    },
+}
+
+/// One choice of a [`MultipleChoice`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Choice {
+    /// What the student sees
+    pub text: String,
+    /// Why the choice is right or wrong
+    pub explanation: String,
+    /// The change this choice makes, [`None`] if it changes nothing. Used by [`Check::Shift`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<OwnedAdjustment>,
+    /// The shift this choice predicts. Used by [`Check::Predict`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shift: Option<Direction>,
+    /// The ΔH in kJ this choice claims. Used by [`Check::DeltaH`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_h: Option<f32>,
+}
+
+/// An interactive question, answered by changing the concentration of any compound so the
+/// system ends up with more or less of one compound
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interactive {
+    /// The compound that should change
+    pub compound: Target,
+    /// If the compound should end up higher than it started
+    pub increase: bool,
+    /// Shown for a correct answer
+    pub correct_msg: String,
+    /// Shown for an incorrect answer
+    pub incorrect_msg: String,
+}
+
+impl Question {
+    /// Build the system the question starts with
+    pub fn system(&self) -> Result<System, QuestionError> {
+        self.system_with_delta_h(self.delta_h)
+    }
+
+    fn system_with_delta_h(&self, delta_h: Option<f32>) -> Result<System, QuestionError> {
+        let mut eq = Equation::new(&self.equation)?;
+        eq.set_concentrations(&self.defaults)?;
+        if let Some(delta_h) = delta_h {
+            eq.set_delta_h(delta_h);
+        }
+
+        Ok(System::new(eq)?)
+    }
+
+    /// Check the question against the simulator. For a multiple choice question, the choices
+    /// the simulator says are right must be exactly [`MultipleChoice::correct`].
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use chatelier::{Direction, OwnedAdjustment};
+    /// use quiz::{Check, Choice, Kind, MultipleChoice, Question, QuestionError};
+    ///
+    /// let choice = |text: &str, cnc| Choice {
+    ///     text: text.to_string(),
+    ///     explanation: String::new(),
+    ///     adjustment: Some(OwnedAdjustment::Concentration(0.into(), cnc)),
+    ///     shift: None,
+    ///     delta_h: None,
+    /// };
+    /// let mut question = Question {
+    ///     id: 1,
+    ///     equation: "N2O4(g) <-> 2NO2(g)".to_string(),
+    ///     prompt: "Which change shifts the system right?".to_string(),
+    ///     defaults: vec![1.0, 0.5],
+    ///     delta_h: None,
+    ///     q: Kind::MultipleChoice(MultipleChoice {
+    ///         check: Check::Shift(Direction::Forward),
+    ///         choices: vec![choice("Add N2O4", 2.0), choice("Remove N2O4", 0.5)],
+    ///         correct: 0,
+    ///     }),
+    /// };
+    /// assert_eq!(question.verify(), Ok(()));
+    ///
+    /// // a wrong answer key is caught
+    /// if let Kind::MultipleChoice(mc) = &mut question.q {
+    ///     mc.correct = 1;
+    /// }
+    /// assert_eq!(
+    ///     question.verify(),
+    ///     Err(QuestionError::WrongAnswer { correct: 1, right: vec![0] })
+    /// );
+    /// ```
+    pub fn verify(&self) -> Result<(), QuestionError> {
+        let system = self.system()?;
+        let mc = match &self.q {
+            Kind::MultipleChoice(mc) => mc,
+            Kind::Interactive(interactive) => {
+                let name = interactive.compound.name(system.equation())?;
+                return match system.equation().compound_names().any(|n| n == name) {
+                    true => Ok(()),
+                    false => Err(QuestionError::CompoundNotFound(name.to_string())),
+                };
+            }
+        };
+
+        if mc.correct >= mc.choices.len() {
+            return Err(QuestionError::CorrectOutOfRange(mc.correct));
+        }
+        let mut right = vec![];
+        for (i, choice) in mc.choices.iter().enumerate() {
+            let is_right = match &mc.check {
+                Check::Shift(dir) => match &choice.adjustment {
+                    Some(adjust) => system.get_owned_shift_direction(adjust)? == *dir,
+                    None => *dir == Direction::None,
+                },
+                Check::Predict(adjust) => {
+                    let shift = choice.shift.ok_or(QuestionError::MissingShift(i))?;
+                    system.get_owned_shift_direction(adjust)? == shift
+                }
+                Check::DeltaH { adjustment, shift } => {
+                    let delta_h = choice.delta_h.ok_or(QuestionError::MissingDeltaH(i))?;
+                    let system = self.system_with_delta_h(Some(delta_h))?;
+                    system.get_owned_shift_direction(adjustment)? == *shift
+                }
+            };
+            if is_right {
+                right.push(i);
+            }
+        }
+
+        if right != [mc.correct] {
+            return Err(QuestionError::WrongAnswer {
+                correct: mc.correct,
+                right,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// An error from [`Question::verify`]
+#[derive(Debug, Error, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QuestionError {
+    #[error("invalid equation: {0}")]
+    Equation(#[from] EquationError),
+    #[error("invalid defaults: {0}")]
+    Concentration(#[from] ConcentrationError),
+    #[error("{0}")]
+    System(#[from] SystemError),
+    #[error("invalid adjustment: {0}")]
+    Adjust(#[from] AdjustError),
+    #[error("compound {0:?} isn't in the equation")]
+    CompoundNotFound(String),
+    #[error("correct choice {0} doesn't exist")]
+    CorrectOutOfRange(usize),
+    #[error("choice {0} doesn't predict a shift")]
+    MissingShift(usize),
+    #[error("choice {0} doesn't give ΔH")]
+    MissingDeltaH(usize),
+    #[error("choice {correct} is marked correct, but the simulator says {right:?} are")]
+    WrongAnswer { correct: usize, right: Vec<usize> },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predict(correct: usize) -> Question {
+        let choice = |shift| Choice {
+            text: format!("{:?}", shift),
+            explanation: String::new(),
+            adjustment: None,
+            shift: Some(shift),
+            delta_h: None,
+        };
+        Question {
+            id: 1,
+            equation: "2NH3(g) <-> N2(g) + 3H2(g)".to_string(),
+            prompt: "What happens if the volume doubles?".to_string(),
+            defaults: vec![2.0, 1.0, 1.5],
+            delta_h: None,
+            q: Kind::MultipleChoice(MultipleChoice {
+                check: Check::Predict(OwnedAdjustment::Volume(2.0)),
+                choices: vec![
+                    choice(Direction::Forward),
+                    choice(Direction::Reverse),
+                    choice(Direction::None),
+                ],
+                correct,
+            }),
+        }
+    }
+
+    #[test]
+    fn predict_shift() {
+        assert_eq!(predict(0).verify(), Ok(()));
+        assert_eq!(
+            predict(2).verify(),
+            Err(QuestionError::WrongAnswer {
+                correct: 2,
+                right: vec![0]
+            })
+        );
+        assert_eq!(
+            predict(3).verify(),
+            Err(QuestionError::CorrectOutOfRange(3))
+        );
+    }
+
+    #[test]
+    fn interactive_compound() {
+        let mut question = predict(0);
+        question.q = Kind::Interactive(Interactive {
+            compound: "NH3(g)".into(),
+            increase: true,
+            correct_msg: String::new(),
+            incorrect_msg: String::new(),
+        });
+        assert_eq!(
+            question.verify(),
+            Err(QuestionError::CompoundNotFound("NH3(g)".to_string()))
+        );
+
+        if let Kind::Interactive(interactive) = &mut question.q {
+            interactive.compound = "2NH3(g)".into();
+        }
+        assert_eq!(question.verify(), Ok(()));
+    }
+
+    #[test]
+    fn serde_shape() {
+        let json = serde_json::to_value(predict(0)).unwrap();
+        assert_eq!(json["q"]["type"], "MultipleChoice");
+        assert_eq!(
+            json["q"]["check"],
+            serde_json::json!({ "Predict": { "Volume": 2.0 } })
+        );
+        assert_eq!(json["q"]["choices"][0]["shift"], "Forward");
+        assert!(json.get("delta_h").is_none());
+    }
+}
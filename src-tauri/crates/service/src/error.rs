@@ -0,0 +1,285 @@
+//! Errors from the service, and how they're sent to the UI
+//!
+//! A [`ServiceError`] serializes as an [`ErrorObject`], `{code, message, details}`. The `code`
+//! is an [`ErrorCode`] and won't change, so the UI can match on it to translate or react to an
+//! error. The `message` is English for logs, and `details` holds more about the error, or is
+//! `null`.
+//!
+//! | code                    | details                                                      |
+//! |-------------------------|--------------------------------------------------------------|
+//! | `PARSE_ERROR`           | `equation`, the `span` `{start, end}` in bytes that couldn't be parsed, and `errors` from the parser, or the `compound` that couldn't be parsed |
+//! | `INVALID_EQUATION`      | `equation`                                                   |
+//! | `UNKNOWN_COMPOUND`      | `null`                                                       |
+//! | `ZERO_CONCENTRATION`    | `reason`                                                     |
+//! | `INVALID_CONCENTRATION` | `reason`                                                     |
+//! | `NOT_REVERSIBLE`        | `null`                                                       |
+//! | `SYSTEM_NOT_FOUND`      | `idx` of the system                                          |
+//! | `SYSTEM_CHANGED`        | `idx` of the system                                          |
+//! | `TOO_MANY_JOBS`         | `max`, the most streamed adjustments running at once         |
+//! | `SOLVER_DIVERGED`       | `reason`                                                     |
+//! | `INVALID_ADJUSTMENT`    | `reason`, or the `samples` asked for and the `max`            |
+//! | `CANCELLED`             | `null`                                                       |
+//! | `INVALID_QUESTION`      | `reason`, the question's error                               |
+//! | `SESSION_ERROR`         | `null`                                                       |
+//! | `EXPORT_ERROR`          | `null`                                                       |
+//!
+//! A `reason` is the name of the error from [`chem_eq`] or [`chatelier`], like
+//! `"DidNotConverge"`.
+
+use chatelier::{AdjustError, SystemError, MAX_SAMPLES};
+use chem_eq::error::{CompoundError, ConcentrationError, ConcentrationNameError, EquationError};
+use quiz::QuestionError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::stream::MAX_JOBS;
+
+/// An error from a [`SimulationService`](crate::SimulationService), sent as an
+/// [`ErrorObject`]
+#[derive(Debug, Error, Clone, PartialEq, Serialize)]
+#[serde(into = "ErrorObject")]
+pub enum ServiceError {
+    #[error("{source}")]
+    Equation {
+        /// The equation that was given
+        equation: String,
+        source: EquationError,
+    },
+    #[error("{source}")]
+    Compound {
+        /// The compound that was given
+        compound: String,
+        source: CompoundError,
+    },
+    #[error("{0}")]
+    System(#[from] SystemError),
+    #[error("{0}")]
+    Adjust(#[from] AdjustError),
+    #[error("{0}")]
+    ConcentrationSet(#[from] ConcentrationError),
+    #[error("{0}")]
+    Question(#[from] QuestionError),
+    #[error("system {idx} not found")]
+    SystemNotFound { idx: usize },
+    #[error("system {idx} changed while it was being adjusted")]
+    SystemChanged { idx: usize },
+    #[error("too many adjustments are streaming, the most is {}", MAX_JOBS)]
+    TooManyJobs,
+    #[error("can't record {samples} samples, the most is {}", MAX_SAMPLES)]
+    TooManySamples { samples: usize },
+    #[error("couldn't save session: {0}")]
+    Session(String),
+    #[error("couldn't export history: {0}")]
+    Export(String),
+}
+
+/// What went wrong, for the UI to match on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The equation couldn't be parsed
+    ParseError,
+    /// The equation parsed, but isn't valid, eg. an element is only on one side
+    InvalidEquation,
+    /// A compound isn't in the equation
+    UnknownCompound,
+    /// A concentration is zero, so the system can't be at equilibrium
+    ZeroConcentration,
+    /// The wrong number of concentrations, or one that's negative or not a number
+    InvalidConcentration,
+    /// The equation isn't reversible, so it has no equilibrium
+    NotReversible,
+    /// No system has the index
+    SystemNotFound,
+    /// A system was changed while a streamed adjustment was solving it, so the stream's
+    /// result was thrown away
+    SystemChanged,
+    /// Too many streamed adjustments are running, wait for one to finish
+    TooManyJobs,
+    /// The system couldn't reach equilibrium, or K or Q became too large
+    SolverDiverged,
+    /// An adjustment was out of range, like a negative volume
+    InvalidAdjustment,
+    /// A streamed adjustment was cancelled
+    Cancelled,
+    /// A quiz question couldn't be made or checked
+    InvalidQuestion,
+    /// The session couldn't be saved
+    SessionError,
+    /// A system's history couldn't be written
+    ExportError,
+}
+
+/// A [`ServiceError`] as it's sent to the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorObject {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Value,
+}
+
+impl ServiceError {
+    /// The code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Equation { source, .. } => match source {
+                EquationError::IncorrectEquation => ErrorCode::InvalidEquation,
+                EquationError::ParsingError(_) | EquationError::TooMuchInput(_) => {
+                    ErrorCode::ParseError
+                }
+            },
+            Self::Compound { .. } => ErrorCode::ParseError,
+            Self::System(e) => system_code(*e),
+            Self::Adjust(e) => match e {
+                AdjustError::CompoundNotFound(ConcentrationNameError::NotFound) => {
+                    ErrorCode::UnknownCompound
+                }
+                AdjustError::CompoundNotFound(_) => ErrorCode::InvalidConcentration,
+                AdjustError::ZeroConcentration => ErrorCode::ZeroConcentration,
+                AdjustError::InvalidInertGas
+                | AdjustError::InvalidVolume
+                | AdjustError::InvalidTemperature
+                | AdjustError::Batch => ErrorCode::InvalidAdjustment,
+                AdjustError::System(e) => system_code(*e),
+            },
+            Self::ConcentrationSet(_) => ErrorCode::InvalidConcentration,
+            Self::Question(_) => ErrorCode::InvalidQuestion,
+            Self::SystemNotFound { .. } => ErrorCode::SystemNotFound,
+            Self::SystemChanged { .. } => ErrorCode::SystemChanged,
+            Self::TooManyJobs => ErrorCode::TooManyJobs,
+            Self::TooManySamples { .. } => ErrorCode::InvalidAdjustment,
+            Self::Session(_) => ErrorCode::SessionError,
+            Self::Export(_) => ErrorCode::ExportError,
+        }
+    }
+
+    /// More about the error, see the [module docs](self)
+    pub fn details(&self) -> Value {
+        match self {
+            Self::Equation { equation, source } => match source.span(equation) {
+                Some(span) => json!({
+                    "equation": equation,
+                    "span": { "start": span.start, "end": span.end },
+                    "errors": source,
+                }),
+                None => json!({ "equation": equation }),
+            },
+            Self::Compound { compound, .. } => json!({ "compound": compound }),
+            Self::SystemNotFound { idx } | Self::SystemChanged { idx } => json!({ "idx": idx }),
+            Self::TooManyJobs => json!({ "max": MAX_JOBS }),
+            Self::TooManySamples { samples } => json!({ "samples": samples, "max": MAX_SAMPLES }),
+            Self::Session(_) | Self::Export(_) => Value::Null,
+            _ => match self.code() {
+                ErrorCode::UnknownCompound | ErrorCode::NotReversible | ErrorCode::Cancelled => {
+                    Value::Null
+                }
+                _ => json!({ "reason": self.reason() }),
+            },
+        }
+    }
+
+    /// The underlying error
+    fn reason(&self) -> Value {
+        let reason = match self {
+            Self::System(e) => serde_json::to_value(e),
+            Self::Adjust(AdjustError::System(e)) => serde_json::to_value(e),
+            Self::Adjust(AdjustError::CompoundNotFound(e)) => serde_json::to_value(e),
+            Self::Adjust(e) => serde_json::to_value(e),
+            Self::ConcentrationSet(e) => serde_json::to_value(e),
+            Self::Question(e) => serde_json::to_value(e),
+            Self::Equation { .. }
+            | Self::Compound { .. }
+            | Self::SystemNotFound { .. }
+            | Self::SystemChanged { .. }
+            | Self::TooManyJobs
+            | Self::TooManySamples { .. }
+            | Self::Session(_)
+            | Self::Export(_) => Ok(Value::Null),
+        };
+
+        reason.unwrap_or(Value::Null)
+    }
+}
+
+fn system_code(e: SystemError) -> ErrorCode {
+    match e {
+        SystemError::NotReversible => ErrorCode::NotReversible,
+        SystemError::ConcentrationIsZero | SystemError::BothSidesZero => {
+            ErrorCode::ZeroConcentration
+        }
+        SystemError::InvalidK | SystemError::InvalidQ | SystemError::DidNotConverge => {
+            ErrorCode::SolverDiverged
+        }
+        SystemError::Cancelled => ErrorCode::Cancelled,
+    }
+}
+
+impl From<ServiceError> for ErrorObject {
+    fn from(e: ServiceError) -> Self {
+        Self {
+            code: e.code(),
+            message: e.to_string(),
+            details: e.details(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chem_eq::Equation;
+
+    use super::*;
+
+    fn object(e: impl Into<ServiceError>) -> Value {
+        serde_json::to_value(e.into()).unwrap()
+    }
+
+    #[test]
+    fn parse_error_span() {
+        let equation = "N2O4(g) <-> 2Xx(g)".to_string();
+        let source = Equation::new(&equation).unwrap_err();
+        let json = object(ServiceError::Equation { equation, source });
+
+        assert_eq!(json["code"], "PARSE_ERROR");
+        assert_eq!(json["details"]["span"], json!({"start": 13, "end": 18}));
+        assert_eq!(json["details"]["equation"], "N2O4(g) <-> 2Xx(g)");
+        assert!(json["details"]["errors"]["ParsingError"].is_array());
+    }
+
+    #[test]
+    fn codes() {
+        let code = |e: ServiceError| e.code();
+        assert_eq!(
+            code(SystemError::NotReversible.into()),
+            ErrorCode::NotReversible
+        );
+        assert_eq!(
+            code(AdjustError::System(SystemError::DidNotConverge).into()),
+            ErrorCode::SolverDiverged
+        );
+        assert_eq!(
+            code(AdjustError::CompoundNotFound(ConcentrationNameError::NotFound).into()),
+            ErrorCode::UnknownCompound
+        );
+        assert_eq!(
+            code(AdjustError::ZeroConcentration.into()),
+            ErrorCode::ZeroConcentration
+        );
+
+        let json = object(ServiceError::SystemNotFound { idx: 3 });
+        assert_eq!(
+            json,
+            json!({
+                "code": "SYSTEM_NOT_FOUND",
+                "message": "system 3 not found",
+                "details": {"idx": 3},
+            })
+        );
+        let json = object(ServiceError::TooManyJobs);
+        assert_eq!(json["code"], "TOO_MANY_JOBS");
+        assert_eq!(json["details"], json!({ "max": MAX_JOBS }));
+        let json = object(SystemError::DidNotConverge);
+        assert_eq!(json["details"], json!({"reason": "DidNotConverge"}));
+    }
+}
@@ -0,0 +1,190 @@
+//! A system's history as CSV or JSON, to open in a spreadsheet or another program
+//!
+//! An [`Export`] has a row for the system before its oldest remembered adjustment, then one
+//! for each adjustment after the system reacted. CSV has a header row, then one column each
+//! for the equation, the step, the adjustment as JSON, K, the volume, the temperature and the
+//! concentration of each compound. A volume or temperature that was never set is left empty.
+
+use std::{fmt::Write, path::Path};
+
+use chatelier::{Step, System};
+use serde::{Deserialize, Serialize};
+
+/// How to write an [`Export`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// The format of a file by its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// The extension of a file in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// The history of a system
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Export {
+    pub equation: String,
+    /// Each compound as it's written in the equation
+    pub compounds: Vec<String>,
+    /// ΔH of the forward reaction in kJ
+    pub delta_h: f32,
+    pub steps: Vec<Step>,
+}
+
+impl Export {
+    /// Export the history of a system, see [`System::timeline`]
+    pub fn of(system: &System) -> Self {
+        let eq = system.equation();
+        Self {
+            equation: eq.equation().to_string(),
+            compounds: eq.compound_names().map(ToString::to_string).collect(),
+            delta_h: eq.delta_h(),
+            steps: system.timeline(),
+        }
+    }
+
+    /// Write the export in `format`
+    pub fn write(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(self).expect("an export is always valid json")
+            }
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header = [
+            "equation",
+            "step",
+            "adjustment",
+            "k",
+            "volume",
+            "temperature",
+        ]
+        .into_iter()
+        .map(ToString::to_string)
+        .chain(self.compounds.iter().map(|c| field(c)));
+        csv.push_str(&header.collect::<Vec<_>>().join(","));
+        csv.push_str("\r\n");
+
+        let optional = |n: Option<f32>| n.map_or_else(String::new, |n| n.to_string());
+        for (i, step) in self.steps.iter().enumerate() {
+            let adjustment = step.adjustment.as_ref().map_or_else(String::new, |a| {
+                serde_json::to_string(a).expect("an adjustment is always valid json")
+            });
+            write!(
+                csv,
+                "{},{},{},{},{},{}",
+                field(&self.equation),
+                i,
+                field(&adjustment),
+                step.k,
+                optional(step.volume),
+                optional(step.temperature),
+            )
+            .unwrap();
+            for concentration in &step.concentrations {
+                write!(csv, ",{}", concentration).unwrap();
+            }
+            csv.push_str("\r\n");
+        }
+
+        csv
+    }
+}
+
+/// Quote a CSV field if it needs to be
+fn field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chatelier::{Adjustment, OwnedAdjustment, Target};
+
+    use super::*;
+    use crate::fixtures::dinitrogen_tetroxide;
+
+    fn export() -> Export {
+        let mut system = dinitrogen_tetroxide();
+        system.adjust(Adjustment::Volume(2.0)).unwrap();
+        system
+            .adjust_owned(&OwnedAdjustment::Concentration(
+                Target::Name("N2O4(g)".to_string()),
+                1.0,
+            ))
+            .unwrap();
+
+        Export::of(&system)
+    }
+
+    #[test]
+    fn csv() {
+        let export = export();
+        let csv = export.write(ExportFormat::Csv);
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "equation,step,adjustment,k,volume,temperature,N2O4(g),2NO2(g)"
+        );
+        assert_eq!(lines[1], "N2O4(g) <-> 2NO2(g),0,,0.25,,,1,0.5");
+        assert!(lines[2].starts_with("N2O4(g) <-> 2NO2(g),1,\"{\"\"Volume\"\":2.0}\",0.25,2,,"));
+        assert!(lines[3].starts_with(
+            "N2O4(g) <-> 2NO2(g),2,\"{\"\"Concentration\"\":[\"\"N2O4(g)\"\",1.0]}\""
+        ));
+    }
+
+    #[test]
+    fn json() {
+        let export = export();
+        let json: serde_json::Value =
+            serde_json::from_str(&export.write(ExportFormat::Json)).unwrap();
+
+        assert_eq!(json["equation"], "N2O4(g) <-> 2NO2(g)");
+        assert_eq!(json["steps"].as_array().unwrap().len(), 3);
+        assert_eq!(json["steps"][0]["adjustment"], serde_json::Value::Null);
+        assert_eq!(
+            json["steps"][1]["adjustment"],
+            serde_json::json!({"Volume": 2.0})
+        );
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("history.CSV")),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a/history.json")),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("history")), None);
+        assert_eq!(field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
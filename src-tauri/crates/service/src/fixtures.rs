@@ -0,0 +1,11 @@
+//! Systems shared by the tests of each module
+
+use chatelier::System;
+use chem_eq::Equation;
+
+/// N2O4 and NO2 at equilibrium, with no volume or ΔH set
+pub(crate) fn dinitrogen_tetroxide() -> System {
+    let mut eq = Equation::new("N2O4(g) <-> 2NO2(g)").unwrap();
+    eq.set_concentrations(&[1.0, 0.5]).unwrap();
+    System::new(eq).unwrap()
+}
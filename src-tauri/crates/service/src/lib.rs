@@ -0,0 +1,661 @@
+//! # `lcp-service`
+//!
+//! The simulator behind the lcp-sim app, without the app. A [`SimulationService`] has one
+//! method for each command the front end calls, taking and returning plain Rust types, so the
+//! Tauri commands only forward to it and it can be tested without a webview.
+//!
+//! Changes to a system are reported as [`Event`]s to a listener, which the app sends on to the
+//! webview. The state kept between runs is a [`Session`](session::Session).
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use chatelier::{
+    Direction, Explanation, OwnedAdjustment, Recorder, System, Trajectory, MAX_SAMPLES,
+};
+use chem_eq::{
+    classify::ReactionType,
+    properties::{BondType, ElementSummary},
+    Compound, Direction as EqDirection, Equation, ReactionQuotient, State,
+};
+use export::{Export, ExportFormat};
+use quiz::{Bank, Generator, Question};
+use serde::{Deserialize, Serialize};
+use session::{Answer, QuizProgress, Session};
+use stream::{Finished, Frame, Jobs};
+
+pub use error::{ErrorCode, ErrorObject, ServiceError};
+use tracing::{debug, info};
+
+mod error;
+pub mod export;
+pub mod rpc;
+pub mod session;
+pub mod stream;
+
+#[cfg(test)]
+mod fixtures;
+
+/// The systems of each quiz question and the sandbox, by index
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct QuestionSystems {
+    systems: HashMap<usize, System>,
+    /// How many times each system was changed by the service, so a streamed adjustment can
+    /// tell if its system changed while it was solving
+    #[serde(skip)]
+    generations: HashMap<usize, u64>,
+}
+
+impl QuestionSystems {
+    /// How many times a system was changed
+    fn generation(&self, idx: usize) -> u64 {
+        self.generations.get(&idx).copied().unwrap_or_default()
+    }
+
+    /// Note that a system was changed
+    fn touch(&mut self, idx: usize) {
+        *self.generations.entry(idx).or_default() += 1;
+    }
+
+    /// Replace or add a system
+    fn replace(&mut self, idx: usize, system: System) {
+        *self.generations.entry(idx).or_default() += 1;
+        self.systems.insert(idx, system);
+    }
+}
+
+impl std::ops::Deref for QuestionSystems {
+    type Target = HashMap<usize, System>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.systems
+    }
+}
+
+impl std::ops::DerefMut for QuestionSystems {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.systems
+    }
+}
+
+/// Something that happened to a system, for the UI to show. Serializes as the event's
+/// payload alone.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Event {
+    /// New concentrations of a system
+    Frame(Frame),
+    /// A streamed adjustment is done
+    Finished(Finished),
+}
+
+impl Event {
+    /// The name the event is sent to the webview as
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Frame(_) => "system-frame",
+            Self::Finished(_) => "job-finished",
+        }
+    }
+}
+
+/// Receives every [`Event`]
+type Listener = dyn Fn(Event) + Send + Sync;
+
+/// An [`Explanation`] along with its plain-English text
+#[derive(Debug, Clone, Serialize)]
+pub struct ShiftExplanation {
+    #[serde(flatten)]
+    pub explanation: Explanation,
+    pub text: String,
+}
+
+/// A compound in a system, in the order it's written in the equation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompoundState {
+    /// The compound as it's written in the equation, eg. `2NO2(g)`
+    pub name: String,
+    /// The compound without its coefficient or state, eg. `NO2`
+    pub formula: String,
+    pub state: Option<State>,
+    pub coefficient: usize,
+    /// Concentration in M
+    pub concentration: f32,
+    /// Amount in mol, the concentration times the volume
+    pub moles: f32,
+}
+
+/// Everything about a system at one moment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SystemState {
+    /// The equation as it was given
+    pub equation: String,
+    pub compounds: Vec<CompoundState>,
+    /// The equilibrium constant at this temperature
+    pub k: f32,
+    /// The reaction quotient, equal to K at equilibrium
+    pub q: ReactionQuotient,
+    /// Volume in L, 1 L unless it was set
+    pub volume: f32,
+    /// Temperature in °C, 25 °C unless it was set
+    pub temperature: f32,
+    /// ΔH of the forward reaction in kJ
+    pub delta_h: f32,
+    /// Whether the reaction goes both ways
+    pub reversible: bool,
+    /// The kind of reaction, if it's a kind that's known
+    pub reaction_type: Option<ReactionType>,
+}
+
+impl SystemState {
+    fn of(system: &System) -> Self {
+        let eq = system.equation();
+        let volume = eq.volume().unwrap_or(1.0);
+        let compounds = eq
+            .name_and_concentration()
+            .zip(eq.iter_compounds())
+            .map(|((name, &concentration), cmp)| CompoundState {
+                name: name.to_string(),
+                formula: cmp.formula(),
+                state: cmp.state.clone(),
+                coefficient: cmp.coefficient,
+                concentration,
+                moles: concentration * volume,
+            })
+            .collect();
+
+        Self {
+            equation: eq.equation().to_string(),
+            compounds,
+            k: system.get_k_expr(),
+            q: eq.reaction_quotient(),
+            volume,
+            temperature: eq.temperature().unwrap_or(25.0),
+            delta_h: eq.delta_h(),
+            reversible: *eq.direction() == EqDirection::Reversible,
+            reaction_type: eq.classify(),
+        }
+    }
+}
+
+/// The conditions a system is under, the part of a [`SystemState`] returned by
+/// [`get_sys_state`](SimulationService::get_sys_state)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Conditions {
+    /// Temperature in °C, 25 °C unless it was set
+    pub temperature: f32,
+    /// Volume in L, 1 L unless it was set
+    pub volume: f32,
+    /// The equilibrium constant at this temperature
+    pub k: f32,
+    /// The reaction quotient, equal to K at equilibrium
+    pub q: ReactionQuotient,
+    /// ΔH of the forward reaction in kJ
+    pub delta_h: f32,
+}
+
+impl From<SystemState> for Conditions {
+    fn from(state: SystemState) -> Self {
+        Self {
+            temperature: state.temperature,
+            volume: state.volume,
+            k: state.k,
+            q: state.q,
+            delta_h: state.delta_h,
+        }
+    }
+}
+
+/// How a compound is bonded and the elements it's made of, for the learn more page
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompoundInfo {
+    /// The compound as it was given
+    pub compound: String,
+    pub bond_type: BondType,
+    /// Each element once, in the order they're written
+    pub elements: Vec<ElementSummary>,
+}
+
+/// The systems, quiz and jobs of one run of the app
+///
+/// Every method takes `&self`, so a service can be shared between threads. Systems are kept
+/// behind a lock that is only held while a method runs, and streamed adjustments are solved
+/// on their own threads.
+pub struct SimulationService {
+    systems: Arc<Mutex<QuestionSystems>>,
+    quiz: Mutex<QuizProgress>,
+    bank: Bank,
+    jobs: Arc<Jobs>,
+    listener: Arc<Listener>,
+    /// The session file couldn't be loaded, so it mustn't be saved over
+    read_only: bool,
+}
+
+impl std::fmt::Debug for SimulationService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SimulationService")
+            .field("systems", &self.systems)
+            .field("quiz", &self.quiz)
+            .field("bank", &self.bank)
+            .field("jobs", &self.jobs)
+            .field("read_only", &self.read_only)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SimulationService {
+    /// Start a service from a saved session, with the built in questions and no listener
+    pub fn new(session: Session) -> Self {
+        Self {
+            systems: Arc::new(Mutex::new(session.systems)),
+            quiz: Mutex::new(session.quiz),
+            bank: Bank::builtin(),
+            jobs: Arc::default(),
+            listener: Arc::new(|_| {}),
+            read_only: session.read_only,
+        }
+    }
+
+    /// Use a different question bank
+    pub fn with_bank(mut self, bank: Bank) -> Self {
+        self.bank = bank;
+        self
+    }
+
+    /// Send every [`Event`] to `listener`
+    pub fn with_listener(mut self, listener: impl Fn(Event) + Send + Sync + 'static) -> Self {
+        self.listener = Arc::new(listener);
+        self
+    }
+
+    /// The state to save, see [`Session::save`]
+    pub fn session(&self) -> Session {
+        Session {
+            systems: self.systems.lock().unwrap().clone(),
+            quiz: self.quiz.lock().unwrap().clone(),
+            read_only: self.read_only,
+            ..Session::new()
+        }
+    }
+
+    /// Save the state to a file
+    pub fn save(&self, path: &Path) -> Result<(), ServiceError> {
+        info!("Saving session to {}", path.display());
+
+        self.session()
+            .save(path)
+            .map_err(|e| ServiceError::Session(e.to_string()))
+    }
+
+    /// Start a system at `idx`, with ΔH of the forward reaction in kJ if it's known. An
+    /// existing system is only replaced if `reset` is set.
+    pub fn add_system(
+        &self,
+        eq_str: &str,
+        idx: usize,
+        concentrations: &[f32],
+        delta_h: Option<f32>,
+        reset: bool,
+    ) -> Result<(), ServiceError> {
+        let frame = {
+            // checked and replaced under one lock, so two calls can't both start the system
+            let mut lock = self.systems.lock().unwrap();
+            let exists = lock.get(&idx).is_some();
+            if exists && !reset {
+                info!("System {} already exists, doing nothing", idx);
+                return Ok(());
+            } else if exists {
+                info!("Resetting system {}", idx);
+            } else {
+                info!("Initializing '{}'", eq_str);
+            }
+            let mut eq = Equation::new(eq_str).map_err(|source| ServiceError::Equation {
+                equation: eq_str.to_string(),
+                source,
+            })?;
+            eq.set_concentrations(concentrations)?;
+            if let Some(delta_h) = delta_h {
+                eq.set_delta_h(delta_h);
+            }
+            let system = System::new(eq)?;
+
+            info!("Starting system {}", idx);
+            let frame = Self::frame(idx, &system);
+            lock.replace(idx, system);
+            frame
+        };
+        self.emit(frame);
+
+        Ok(())
+    }
+
+    /// The concentrations of a system, [`None`] if it doesn't exist
+    pub fn get_sys_concentration(&self, idx: usize) -> Option<Vec<f32>> {
+        self.systems
+            .lock()
+            .unwrap()
+            .get(&idx)
+            .map(|s| s.equation().get_concentrations())
+    }
+
+    /// Set the concentrations of a system without reacting
+    pub fn set_sys_concentration(
+        &self,
+        idx: usize,
+        concentrations: &[f32],
+    ) -> Result<(), ServiceError> {
+        info!("Setting concentrations for {}: {:?}", idx, concentrations);
+
+        self.with_system(idx, |system| {
+            system.equation_mut().set_concentrations(concentrations)?;
+            Ok(())
+        })
+    }
+
+    /// Make an adjustment and react until the system is at equilibrium
+    pub fn update_system(&self, idx: usize, adjust: &OwnedAdjustment) -> Result<(), ServiceError> {
+        info!("Updating system {} with {:?}...", idx, adjust);
+
+        self.with_system(idx, |system| Ok(system.adjust_owned(adjust)?))?;
+        debug!("Finished adjusting system");
+
+        Ok(())
+    }
+
+    /// Make an adjustment on another thread, sending [`Event::Frame`]s as the system reacts
+    /// and [`Event::Finished`] when done. Returns the job's id, for
+    /// [`cancel_job`](Self::cancel_job).
+    ///
+    /// Cancelling before the system is solved leaves it unchanged. Once solved, the adjustment
+    /// is kept and cancelling only stops the frames. If the system is changed some other way
+    /// while solving, including by another stream, the job fails with
+    /// [`ServiceError::SystemChanged`] and the other change is kept. At most
+    /// [`MAX_JOBS`](stream::MAX_JOBS) jobs run at once, past that this returns
+    /// [`ServiceError::TooManyJobs`].
+    pub fn stream_adjustment(
+        &self,
+        idx: usize,
+        adjust: OwnedAdjustment,
+    ) -> Result<u64, ServiceError> {
+        let (system, generation) = {
+            let lock = self.systems.lock().unwrap();
+            let system = lock.get(&idx).ok_or(ServiceError::SystemNotFound { idx })?;
+            (system.clone(), lock.generation(idx))
+        };
+        let (job, cancel) = self.jobs.start().ok_or(ServiceError::TooManyJobs)?;
+        info!("Streaming system {} with {:?} as job {}", idx, adjust, job);
+
+        let systems = Arc::clone(&self.systems);
+        let jobs = Arc::clone(&self.jobs);
+        let listener = Arc::clone(&self.listener);
+        thread::spawn(move || {
+            let (played, error) = match stream::solve(&system, &adjust, &cancel) {
+                Ok(Some((system, trajectory))) => {
+                    let replaced = {
+                        let mut lock = systems.lock().unwrap();
+                        let unchanged = lock.generation(idx) == generation;
+                        if unchanged {
+                            lock.replace(idx, system);
+                        }
+                        unchanged
+                    };
+                    // the lock is released, so the listener can call back into the service
+                    if replaced {
+                        let played = stream::play(&trajectory, idx, job, &cancel, |frame| {
+                            listener(Event::Frame(frame))
+                        });
+                        (played, None)
+                    } else {
+                        (true, Some(ServiceError::SystemChanged { idx }))
+                    }
+                }
+                Ok(None) => (false, None),
+                Err(e) => (true, Some(e.into())),
+            };
+
+            jobs.finish(job);
+            let finished = Finished {
+                job,
+                idx,
+                cancelled: !played,
+                error,
+            };
+            debug!("Finished job {}: {:?}", job, finished);
+            listener(Event::Finished(finished));
+        });
+
+        Ok(job)
+    }
+
+    /// Cancel a streamed adjustment, returning `false` if it already finished
+    pub fn cancel_job(&self, id: u64) -> bool {
+        info!("Cancelling job {}", id);
+        self.jobs.cancel(id)
+    }
+
+    /// Make an adjustment, returning `samples` points, at most [`MAX_SAMPLES`], of the
+    /// system's modelled approach to equilibrium
+    pub fn record_adjustment(
+        &self,
+        idx: usize,
+        adjust: &OwnedAdjustment,
+        samples: usize,
+    ) -> Result<Trajectory, ServiceError> {
+        info!("Recording system {} with {:?}...", idx, adjust);
+        if samples > MAX_SAMPLES {
+            return Err(ServiceError::TooManySamples { samples });
+        }
+
+        let trajectory = self.with_system(idx, |system| {
+            system.set_recorder(Some(Recorder::Samples(samples)));
+            let res = system.adjust_owned(adjust);
+            let trajectory = system.take_trajectory();
+            system.set_recorder(None);
+            res?;
+            Ok(trajectory.unwrap_or_default())
+        })?;
+        debug!("Finished recording system");
+
+        Ok(trajectory)
+    }
+
+    /// The concentrations a system would have after an adjustment, without changing it
+    pub fn test_adjustment(
+        &self,
+        idx: usize,
+        adjust: &OwnedAdjustment,
+    ) -> Result<Vec<f32>, ServiceError> {
+        info!("Testing system {} with {:?}...", idx, adjust);
+        let results = self
+            .systems
+            .lock()
+            .unwrap()
+            .get(&idx)
+            .ok_or(ServiceError::SystemNotFound { idx })?
+            .simulate(adjust)?
+            .equation()
+            .get_concentrations();
+
+        debug!("Finished testing system");
+
+        Ok(results)
+    }
+
+    /// The history of a system in `format`
+    pub fn export_history(&self, idx: usize, format: ExportFormat) -> Result<String, ServiceError> {
+        let lock = self.systems.lock().unwrap();
+        let system = lock.get(&idx).ok_or(ServiceError::SystemNotFound { idx })?;
+
+        Ok(Export::of(system).write(format))
+    }
+
+    /// Write the history of a system to a file in `format`
+    pub fn save_history(
+        &self,
+        idx: usize,
+        path: &Path,
+        format: ExportFormat,
+    ) -> Result<(), ServiceError> {
+        info!("Exporting history of {} to {}", idx, path.display());
+
+        let export = self.export_history(idx, format)?;
+        std::fs::write(path, export).map_err(|e| ServiceError::Export(e.to_string()))
+    }
+
+    /// Undo the last adjustment to a system, returning it
+    pub fn undo_adjustment(&self, idx: usize) -> Result<Option<OwnedAdjustment>, ServiceError> {
+        info!("Undoing last adjustment to system {}", idx);
+
+        self.with_system(idx, |system| Ok(system.undo()))
+    }
+
+    /// Redo the last undone adjustment to a system, returning it
+    pub fn redo_adjustment(&self, idx: usize) -> Result<Option<OwnedAdjustment>, ServiceError> {
+        info!("Redoing last adjustment to system {}", idx);
+
+        self.with_system(idx, |system| Ok(system.redo()))
+    }
+
+    /// Which way an adjustment would shift a system
+    pub fn get_shift_direction(
+        &self,
+        idx: usize,
+        adjust: &OwnedAdjustment,
+    ) -> Result<Direction, ServiceError> {
+        let res = self
+            .systems
+            .lock()
+            .unwrap()
+            .get(&idx)
+            .ok_or(ServiceError::SystemNotFound { idx })?
+            .get_owned_shift_direction(adjust)?;
+
+        Ok(res)
+    }
+
+    /// Why an adjustment would shift a system the way it does
+    pub fn explain_shift(
+        &self,
+        idx: usize,
+        adjust: &OwnedAdjustment,
+    ) -> Result<ShiftExplanation, ServiceError> {
+        let lock = self.systems.lock().unwrap();
+        let system = lock.get(&idx).ok_or(ServiceError::SystemNotFound { idx })?;
+        let explanation = system.explain(adjust.as_adjustment(system.equation())?)?;
+        let text = explanation.to_string();
+
+        Ok(ShiftExplanation { explanation, text })
+    }
+
+    /// Heat or cool a system to `temperature` in °C and react. K changes with ΔH of the
+    /// reaction, so setting the same temperature twice changes nothing.
+    pub fn set_temperature(&self, idx: usize, temperature: f32) -> Result<(), ServiceError> {
+        info!("Setting temperature of {} to {} °C", idx, temperature);
+
+        self.with_system(idx, |system| {
+            Ok(system.adjust_owned(&OwnedAdjustment::Temperature(temperature))?)
+        })
+    }
+
+    /// The compounds, K, Q and conditions of a system
+    pub fn get_system_state(&self, idx: usize) -> Result<SystemState, ServiceError> {
+        let lock = self.systems.lock().unwrap();
+        let system = lock.get(&idx).ok_or(ServiceError::SystemNotFound { idx })?;
+
+        Ok(SystemState::of(system))
+    }
+
+    /// The temperature, volume, K and Q of a system. Kept for callers from before
+    /// [`get_system_state`](Self::get_system_state), which returns these and more.
+    pub fn get_sys_state(&self, idx: usize) -> Result<Conditions, ServiceError> {
+        self.get_system_state(idx).map(Conditions::from)
+    }
+
+    /// How a compound like `Fe2O3` is bonded, and about each of its elements
+    pub fn describe_compound(&self, compound: &str) -> Result<CompoundInfo, ServiceError> {
+        let cmp = Compound::parse(compound).map_err(|source| ServiceError::Compound {
+            compound: compound.to_string(),
+            source,
+        })?;
+        let mut elements: Vec<ElementSummary> = vec![];
+        for el in &cmp.elements {
+            if !elements.iter().any(|e| e.symbol == el.symbol()) {
+                elements.push(el.summary());
+            }
+        }
+
+        Ok(CompoundInfo {
+            compound: compound.to_string(),
+            bond_type: cmp.bond_type(),
+            elements,
+        })
+    }
+
+    /// A question from the bank
+    pub fn get_question(&self, id: usize) -> Option<Question> {
+        self.bank.get(id).cloned()
+    }
+
+    /// How many questions are in the bank
+    pub fn get_question_count(&self) -> usize {
+        self.bank.len()
+    }
+
+    /// Generate new questions from a seed
+    pub fn generate_quiz(&self, seed: u64, count: usize) -> Result<Vec<Question>, ServiceError> {
+        Ok(Generator::new(seed).quiz(count)?)
+    }
+
+    /// Keep the answer to a question, replacing any earlier one
+    pub fn record_answer(&self, id: usize, answer: Answer) {
+        self.quiz.lock().unwrap().answers.insert(id, answer);
+    }
+
+    /// Every answer given so far
+    pub fn get_quiz_progress(&self) -> QuizProgress {
+        self.quiz.lock().unwrap().clone()
+    }
+
+    /// Change a system, then send its new concentrations once the lock is released, so the
+    /// listener can call back into the service
+    fn with_system<T>(
+        &self,
+        idx: usize,
+        f: impl FnOnce(&mut System) -> Result<T, ServiceError>,
+    ) -> Result<T, ServiceError> {
+        let (res, frame) = {
+            let mut lock = self.systems.lock().unwrap();
+            let system = lock
+                .get_mut(&idx)
+                .ok_or(ServiceError::SystemNotFound { idx })?;
+            let res = f(system)?;
+            let frame = Self::frame(idx, system);
+            // only a change that went through counts, so a failed one doesn't fail a stream
+            lock.touch(idx);
+            (res, frame)
+        };
+        self.emit(frame);
+
+        Ok(res)
+    }
+
+    /// The concentrations of a system that changed without animating
+    fn frame(idx: usize, system: &System) -> Frame {
+        Frame {
+            job: None,
+            idx,
+            time: 0.0,
+            concentrations: system.equation().get_concentrations(),
+        }
+    }
+
+    /// Send a frame to the listener. Never call this with the systems locked.
+    fn emit(&self, frame: Frame) {
+        (self.listener)(Event::Frame(frame));
+    }
+}
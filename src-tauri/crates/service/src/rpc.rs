@@ -0,0 +1,445 @@
+//! The [`SimulationService`] over [JSON-RPC 2.0](https://www.jsonrpc.org/specification)
+//!
+//! Each command of the app is a method with the same name, taking the same parameters the
+//! front end passes to `invoke`, eg.
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "id": 1, "method": "add_system",
+//!  "params": {"eqStr": "N2O4(g) <-> 2NO2(g)", "idx": 0, "concentrations": [1, 0.5], "reset": false}}
+//! ```
+//!
+//! There's nowhere to push events to, so they're kept until fetched with `poll_events`, and the
+//! session is saved with `save_session`. [`Rpc`] only turns request text into response text,
+//! serving it is up to the caller.
+
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use chatelier::OwnedAdjustment;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    export::ExportFormat,
+    session::{Answer, Session},
+    Event, ServiceError, SimulationService,
+};
+
+/// Most events kept for `poll_events`, older ones are dropped
+pub const MAX_EVENTS: usize = 1024;
+
+/// The JSON-RPC error codes. The [`ErrorObject`](crate::ErrorObject) of a
+/// [`SERVICE_ERROR`](code::SERVICE_ERROR) is its `data`.
+mod code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// The method ran and failed, the error is in `data`
+    pub const SERVICE_ERROR: i64 = -32000;
+}
+
+/// A method and its parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum Call {
+    #[serde(rename_all = "camelCase")]
+    AddSystem {
+        eq_str: String,
+        idx: usize,
+        concentrations: Vec<f32>,
+        #[serde(default)]
+        delta_h: Option<f32>,
+        #[serde(default)]
+        reset: bool,
+    },
+    GetSysConcentration {
+        idx: usize,
+    },
+    SetSysConcentration {
+        idx: usize,
+        concentrations: Vec<f32>,
+    },
+    UpdateSystem {
+        idx: usize,
+        adjust: OwnedAdjustment,
+    },
+    StreamAdjustment {
+        idx: usize,
+        adjust: OwnedAdjustment,
+    },
+    CancelJob {
+        id: u64,
+    },
+    RecordAdjustment {
+        idx: usize,
+        adjust: OwnedAdjustment,
+        samples: usize,
+    },
+    TestAdjustment {
+        idx: usize,
+        adjust: OwnedAdjustment,
+    },
+    UndoAdjustment {
+        idx: usize,
+    },
+    RedoAdjustment {
+        idx: usize,
+    },
+    ExportHistory {
+        idx: usize,
+        format: ExportFormat,
+    },
+    GetShiftDirection {
+        idx: usize,
+        adjust: OwnedAdjustment,
+    },
+    ExplainShift {
+        idx: usize,
+        adjust: OwnedAdjustment,
+    },
+    SetTemperature {
+        idx: usize,
+        temperature: f32,
+    },
+    GetSysState {
+        idx: usize,
+    },
+    GetSystemState {
+        idx: usize,
+    },
+    DescribeCompound {
+        compound: String,
+    },
+    GetQuestion {
+        id: usize,
+    },
+    GetQuestionCount {},
+    GenerateQuiz {
+        seed: u64,
+        count: usize,
+    },
+    RecordAnswer {
+        id: usize,
+        answer: Answer,
+    },
+    GetQuizProgress {},
+    SaveSession {},
+    PollEvents {},
+}
+
+/// The envelope of a request, before its method is known
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Option<Value>,
+    /// Missing for notifications, which get no response
+    #[serde(default)]
+    id: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    id: Value,
+}
+
+/// A JSON-RPC error object
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+}
+
+impl From<ServiceError> for RpcError {
+    fn from(e: ServiceError) -> Self {
+        Self {
+            code: code::SERVICE_ERROR,
+            message: e.to_string(),
+            data: serde_json::to_value(e).ok(),
+        }
+    }
+}
+
+/// A [`SimulationService`] answering JSON-RPC requests
+#[derive(Debug)]
+pub struct Rpc {
+    service: SimulationService,
+    events: Arc<Mutex<VecDeque<Event>>>,
+    session_path: Option<PathBuf>,
+}
+
+impl Rpc {
+    /// Serve a session, saving it to `session_path` on `save_session`
+    pub fn new(session: Session, session_path: Option<PathBuf>) -> Self {
+        let events = Arc::new(Mutex::new(VecDeque::new()));
+        let queue = Arc::clone(&events);
+        let service = SimulationService::new(session).with_listener(move |event| {
+            let mut queue = queue.lock().unwrap();
+            if queue.len() == MAX_EVENTS {
+                queue.pop_front();
+            }
+            queue.push_back(event);
+        });
+
+        Self {
+            service,
+            events,
+            session_path,
+        }
+    }
+
+    /// The service being served
+    pub fn service(&self) -> &SimulationService {
+        &self.service
+    }
+
+    /// Answer a request or a batch of requests. Returns [`None`] if there's nothing to send
+    /// back, because every request was a notification.
+    pub fn handle(&self, body: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(body) {
+            Ok(Value::Array(batch)) if batch.is_empty() => Some(error_response(RpcError::new(
+                code::INVALID_REQUEST,
+                "empty batch",
+            ))),
+            Ok(Value::Array(batch)) => {
+                let responses = batch
+                    .into_iter()
+                    .filter_map(|request| self.handle_one(request))
+                    .collect::<Vec<_>>();
+                (!responses.is_empty()).then(|| json!(responses))
+            }
+            Ok(request) => self.handle_one(request),
+            Err(e) => Some(error_response(RpcError::new(code::PARSE_ERROR, e))),
+        };
+
+        response.map(|r| r.to_string())
+    }
+
+    /// Answer one request, [`None`] if it's a notification
+    fn handle_one(&self, request: Value) -> Option<Value> {
+        let request: Request = match serde_json::from_value(request) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(RpcError::new(code::INVALID_REQUEST, e))),
+        };
+        let res = if request.jsonrpc != "2.0" {
+            Err(RpcError::new(
+                code::INVALID_REQUEST,
+                "jsonrpc must be \"2.0\"",
+            ))
+        } else {
+            self.parse_call(request.method, request.params)
+                .and_then(|call| self.call(call).map_err(RpcError::from))
+        };
+
+        let id = request.id?;
+        let (result, error) = match res {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        Some(json!(Response {
+            jsonrpc: "2.0",
+            result,
+            error,
+            id,
+        }))
+    }
+
+    fn parse_call(&self, method: String, params: Option<Value>) -> Result<Call, RpcError> {
+        let params = params.unwrap_or_else(|| json!({}));
+        serde_json::from_value(json!({ "method": method, "params": params })).map_err(|e| {
+            if e.to_string().starts_with("unknown variant") {
+                RpcError::new(code::METHOD_NOT_FOUND, format!("no method {:?}", method))
+            } else {
+                RpcError::new(code::INVALID_PARAMS, e)
+            }
+        })
+    }
+
+    /// Run a method, returning its result as JSON
+    pub fn call(&self, call: Call) -> Result<Value, ServiceError> {
+        let s = &self.service;
+        let result = match call {
+            Call::AddSystem {
+                eq_str,
+                idx,
+                concentrations,
+                delta_h,
+                reset,
+            } => json!(s.add_system(&eq_str, idx, &concentrations, delta_h, reset)?),
+            Call::GetSysConcentration { idx } => json!(s.get_sys_concentration(idx)),
+            Call::SetSysConcentration {
+                idx,
+                concentrations,
+            } => json!(s.set_sys_concentration(idx, &concentrations)?),
+            Call::UpdateSystem { idx, adjust } => json!(s.update_system(idx, &adjust)?),
+            Call::StreamAdjustment { idx, adjust } => json!(s.stream_adjustment(idx, adjust)?),
+            Call::CancelJob { id } => json!(s.cancel_job(id)),
+            Call::RecordAdjustment {
+                idx,
+                adjust,
+                samples,
+            } => json!(s.record_adjustment(idx, &adjust, samples)?),
+            Call::TestAdjustment { idx, adjust } => json!(s.test_adjustment(idx, &adjust)?),
+            Call::UndoAdjustment { idx } => json!(s.undo_adjustment(idx)?),
+            Call::RedoAdjustment { idx } => json!(s.redo_adjustment(idx)?),
+            Call::ExportHistory { idx, format } => json!(s.export_history(idx, format)?),
+            Call::GetShiftDirection { idx, adjust } => {
+                json!(s.get_shift_direction(idx, &adjust)?)
+            }
+            Call::ExplainShift { idx, adjust } => json!(s.explain_shift(idx, &adjust)?),
+            Call::SetTemperature { idx, temperature } => {
+                json!(s.set_temperature(idx, temperature)?)
+            }
+            Call::GetSysState { idx } => json!(s.get_sys_state(idx)?),
+            Call::GetSystemState { idx } => json!(s.get_system_state(idx)?),
+            Call::DescribeCompound { compound } => json!(s.describe_compound(&compound)?),
+            Call::GetQuestion { id } => json!(s.get_question(id)),
+            Call::GetQuestionCount {} => json!(s.get_question_count()),
+            Call::GenerateQuiz { seed, count } => json!(s.generate_quiz(seed, count)?),
+            Call::RecordAnswer { id, answer } => json!(s.record_answer(id, answer)),
+            Call::GetQuizProgress {} => json!(s.get_quiz_progress()),
+            Call::SaveSession {} => match &self.session_path {
+                Some(path) => json!(s.save(path)?),
+                None => json!(null),
+            },
+            Call::PollEvents {} => {
+                json!(self.events.lock().unwrap().drain(..).collect::<Vec<_>>())
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+/// A response to a request that couldn't be read, so has no id
+fn error_response(error: RpcError) -> Value {
+    json!(Response {
+        jsonrpc: "2.0",
+        result: None,
+        error: Some(error),
+        id: Value::Null,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc() -> Rpc {
+        let rpc = Rpc::new(Session::new(), None);
+        let add = r#"{"jsonrpc": "2.0", "id": 0, "method": "add_system",
+            "params": {"eqStr": "N2O4(g) <-> 2NO2(g)", "idx": 0, "concentrations": [1, 0.5]}}"#;
+        rpc.handle(add).unwrap();
+        rpc
+    }
+
+    fn request(rpc: &Rpc, body: &str) -> Value {
+        serde_json::from_str(&rpc.handle(body).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn methods() {
+        let rpc = rpc();
+        let res = request(
+            &rpc,
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "get_sys_concentration", "params": {"idx": 0}}"#,
+        );
+        assert_eq!(
+            res,
+            json!({"jsonrpc": "2.0", "result": [1.0, 0.5], "id": 1})
+        );
+
+        let res = request(
+            &rpc,
+            r#"{"jsonrpc": "2.0", "id": "a", "method": "get_shift_direction",
+                "params": {"idx": 0, "adjust": {"Volume": 2.0}}}"#,
+        );
+        assert_eq!(res["result"], "Forward");
+        assert_eq!(res["id"], "a");
+
+        let res = request(
+            &rpc,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "get_sys_state", "params": {"idx": 0}}"#,
+        );
+        assert_eq!(res["result"]["volume"], 1.0);
+        assert!(res["result"].get("compounds").is_none());
+
+        let res = request(
+            &rpc,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "get_question_count"}"#,
+        );
+        assert_eq!(res["result"], 5);
+    }
+
+    #[test]
+    fn errors() {
+        let rpc = rpc();
+        let code_of = |body| request(&rpc, body)["error"]["code"].clone();
+        assert_eq!(code_of("{"), code::PARSE_ERROR);
+        assert_eq!(code_of("[]"), code::INVALID_REQUEST);
+        assert_eq!(
+            code_of(r#"{"jsonrpc": "1.0", "id": 1, "method": "get_question_count"}"#),
+            code::INVALID_REQUEST
+        );
+        assert_eq!(
+            code_of(r#"{"jsonrpc": "2.0", "id": 1, "method": "explode"}"#),
+            code::METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            code_of(
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "set_temperature", "params": {"idx": 0}}"#
+            ),
+            code::INVALID_PARAMS
+        );
+
+        let res = request(
+            &rpc,
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "undo_adjustment", "params": {"idx": 4}}"#,
+        );
+        assert_eq!(res["error"]["code"], code::SERVICE_ERROR);
+        assert_eq!(res["error"]["message"], "system 4 not found");
+        assert_eq!(res["error"]["data"]["code"], "SYSTEM_NOT_FOUND");
+        assert_eq!(res["error"]["data"]["details"]["idx"], 4);
+    }
+
+    #[test]
+    fn batches_and_notifications() {
+        let rpc = rpc();
+        let notify = r#"{"jsonrpc": "2.0", "method": "set_sys_concentration",
+            "params": {"idx": 0, "concentrations": [2, 1]}}"#;
+        assert_eq!(rpc.handle(notify), None);
+
+        let batch = format!(
+            r#"[{}, {{"jsonrpc": "2.0", "id": 1, "method": "poll_events"}}]"#,
+            notify
+        );
+        let res = request(&rpc, &batch);
+        let events = res[0]["result"].as_array().unwrap();
+        // adding the system, then setting its concentrations twice
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2]["concentrations"], json!([2.0, 1.0]));
+    }
+}
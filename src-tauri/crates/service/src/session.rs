@@ -0,0 +1,301 @@
+//! Saving the app's state between runs
+//!
+//! The session is a JSON file in the app data directory, holding every system, the quiz
+//! answers and a `version`. Files from older versions are migrated when loaded. An invalid
+//! file is moved aside to `session.json.corrupt-<time>` and a fresh session is started, so a
+//! bad file never stops the app from opening. A file that can't be read, or that was written by
+//! a newer version of the app, is left alone and the session isn't saved over it.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::QuestionSystems;
+
+/// The current version of the session format
+pub const VERSION: u64 = 1;
+
+/// Name of the session file in the app data directory
+pub const FILE_NAME: &str = "session.json";
+
+/// Migrations from each old version to the next, `MIGRATIONS[n]` upgrades version `n + 1`.
+/// Version 1 is the first, so there are none yet.
+const MIGRATIONS: [fn(Value) -> Result<Value, SessionError>; VERSION as usize - 1] = [];
+
+/// Everything saved between runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    /// The version of the format, always [`VERSION`] once loaded
+    pub version: u64,
+    /// The systems of each quiz question and the sandbox
+    pub systems: QuestionSystems,
+    /// Answers to quiz questions
+    pub quiz: QuizProgress,
+    /// The file this was loaded from couldn't be read or is from a newer app, so saving would
+    /// lose what's in it
+    #[serde(skip)]
+    pub read_only: bool,
+}
+
+/// The answers given in the quiz
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuizProgress {
+    /// The last answer to each question, by question id
+    pub answers: BTreeMap<usize, Answer>,
+}
+
+/// An answer to a quiz question
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Answer {
+    /// The choice picked for a multiple choice question
+    pub selected: Option<usize>,
+    /// The concentrations set for an interactive question
+    pub changes: Option<Vec<f32>>,
+    /// If the answer was right
+    pub correct: bool,
+}
+
+impl Session {
+    /// Create an empty session
+    pub fn new() -> Self {
+        Self {
+            version: VERSION,
+            ..Default::default()
+        }
+    }
+
+    /// Parse a session, migrating it from an older version
+    pub fn from_json(json: &str) -> Result<Self, SessionError> {
+        let mut value: Value = serde_json::from_str(json)?;
+        let mut version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or(SessionError::InvalidVersion)?;
+        if version == 0 {
+            return Err(SessionError::InvalidVersion);
+        }
+        if version > VERSION {
+            return Err(SessionError::UnsupportedVersion(version));
+        }
+        while version < VERSION {
+            value = MIGRATIONS[version as usize - 1](value)?;
+            version += 1;
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Load the session from a file. A missing file gives an empty session. An invalid file is
+    /// moved aside so the next save doesn't overwrite it. A file that can't be read or is from
+    /// a newer app is left in place, and the session is [`read_only`](Self::read_only).
+    pub fn load(path: &Path) -> Self {
+        let read_only = Self {
+            read_only: true,
+            ..Self::new()
+        };
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::new(),
+            Err(e) => {
+                warn!(
+                    "Couldn't read session {}, it won't be saved over: {}",
+                    path.display(),
+                    e
+                );
+                return read_only;
+            }
+        };
+
+        match Self::from_json(&json) {
+            Ok(session) => session,
+            Err(e @ SessionError::UnsupportedVersion(_)) => {
+                warn!("{}, {} won't be saved over", e, path.display());
+                read_only
+            }
+            Err(e) => {
+                let backup = corrupt_path(path);
+                warn!(
+                    "Session {} is invalid ({}), moving it to {}",
+                    path.display(),
+                    e,
+                    backup.display()
+                );
+                if let Err(e) = fs::rename(path, &backup) {
+                    warn!("Couldn't move invalid session: {}", e);
+                }
+                Self::new()
+            }
+        }
+    }
+
+    /// Save the session to a file, replacing it in one step so a crash can't leave half a file.
+    /// Fails with [`SessionError::ReadOnly`] if the session is read only.
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        if self.read_only {
+            return Err(SessionError::ReadOnly);
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(tmp, path)?;
+
+        Ok(())
+    }
+}
+
+/// Where an invalid session file is moved, named for the time so earlier ones are kept
+fn corrupt_path(path: &Path) -> PathBuf {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut backup = path.with_file_name(format!("{}.corrupt-{}", name, time));
+    let mut n = 1;
+    while backup.exists() {
+        backup = path.with_file_name(format!("{}.corrupt-{}-{}", name, time, n));
+        n += 1;
+    }
+
+    backup
+}
+
+/// An error reading or writing a [`Session`]
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("session has an invalid version")]
+    InvalidVersion,
+    #[error("session version {0} is newer than this app supports")]
+    UnsupportedVersion(u64),
+    #[error("the session file couldn't be loaded, so it won't be saved over")]
+    ReadOnly,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::dinitrogen_tetroxide;
+
+    fn session() -> Session {
+        let mut session = Session::new();
+        session.systems.insert(9, dinitrogen_tetroxide());
+        session.quiz.answers.insert(
+            1,
+            Answer {
+                selected: Some(2),
+                changes: None,
+                correct: true,
+            },
+        );
+        session
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lcp-sim-session-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(FILE_NAME)
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = temp_path("round-trip");
+        session().save(&path).unwrap();
+
+        let loaded = Session::load(&path);
+        assert_eq!(loaded.version, VERSION);
+        assert_eq!(*loaded.systems, *session().systems);
+        assert_eq!(loaded.quiz, session().quiz);
+    }
+
+    #[test]
+    fn needs_a_version() {
+        // any other JSON object isn't a session
+        let systems = serde_json::to_string(&session().systems).unwrap();
+        for json in [
+            systems.as_str(),
+            "{}",
+            "{\"version\": 0}",
+            "{\"version\": \"1\"}",
+        ] {
+            assert!(matches!(
+                Session::from_json(json),
+                Err(SessionError::InvalidVersion)
+            ));
+        }
+    }
+
+    /// The contents of every backup of an invalid session next to `path`
+    fn backups(path: &Path) -> Vec<String> {
+        let mut backups = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|p| p.to_string_lossy().contains(".corrupt-"))
+            .map(|p| fs::read_to_string(p).unwrap())
+            .collect::<Vec<_>>();
+        backups.sort();
+        backups
+    }
+
+    #[test]
+    fn corrupt_file_is_kept() {
+        let path = temp_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        for json in ["{\"version\": 1, \"systems\": [", "not json"] {
+            fs::write(&path, json).unwrap();
+            let loaded = Session::load(&path);
+            assert!(loaded.systems.is_empty());
+            assert!(!loaded.read_only);
+            assert!(!path.exists());
+        }
+
+        // the second backup doesn't replace the first
+        assert_eq!(
+            backups(&path),
+            vec!["not json", "{\"version\": 1, \"systems\": ["]
+        );
+    }
+
+    #[test]
+    fn newer_version() {
+        assert!(matches!(
+            Session::from_json("{\"version\": 99}"),
+            Err(SessionError::UnsupportedVersion(99))
+        ));
+        assert!(Session::load(&temp_path("missing")).systems.is_empty());
+
+        // a newer app's file is left alone and never saved over
+        let path = temp_path("newer");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{\"version\": 99}").unwrap();
+        let loaded = Session::load(&path);
+        assert!(loaded.read_only);
+        assert!(matches!(loaded.save(&path), Err(SessionError::ReadOnly)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"version\": 99}");
+        assert!(backups(&path).is_empty());
+    }
+
+    #[test]
+    fn unreadable_file() {
+        // a directory can't be read as a file
+        let path = temp_path("unreadable");
+        fs::create_dir_all(&path).unwrap();
+        let loaded = Session::load(&path);
+        assert!(loaded.read_only);
+        assert!(matches!(loaded.save(&path), Err(SessionError::ReadOnly)));
+        assert!(path.is_dir());
+    }
+}
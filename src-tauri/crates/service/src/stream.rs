@@ -0,0 +1,238 @@
+//! Streaming concentrations to the UI while a system equilibrates
+//!
+//! An adjustment is solved on a background thread as a job. The frames of its [`Trajectory`]
+//! are then played back at up to [`FRAME_RATE`] frames a second, so charts can animate the
+//! shift. Jobs can be cancelled by id with [`Jobs::cancel`].
+
+use std::{
+    collections::HashMap,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use chatelier::{AdjustError, OwnedAdjustment, Recorder, System, SystemError, Trajectory};
+use serde::Serialize;
+
+use crate::ServiceError;
+
+/// Most frames sent a second
+pub const FRAME_RATE: f32 = 30.0;
+
+/// Seconds an uncatalysed system takes to reach equilibrium on screen
+pub const PLAYBACK_SECONDS: f32 = 1.0;
+
+/// Most streamed adjustments running at once, more are turned away
+pub const MAX_JOBS: usize = 16;
+
+/// Points of the modelled approach to equilibrium, before throttling
+const SAMPLES: usize = 61;
+
+/// Jobs that are still running, by id
+#[derive(Debug, Default)]
+pub struct Jobs {
+    next: AtomicU64,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+impl Jobs {
+    /// Start a job, returning its id and a flag set when it's cancelled, [`None`] if there are
+    /// already [`MAX_JOBS`] running
+    pub fn start(&self) -> Option<(u64, Arc<AtomicBool>)> {
+        let mut running = self.running.lock().unwrap();
+        if running.len() >= MAX_JOBS {
+            return None;
+        }
+        let id = self.next.fetch_add(1, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        running.insert(id, Arc::clone(&cancel));
+
+        Some((id, cancel))
+    }
+
+    /// Cancel a job, returning `false` if it isn't running
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.running.lock().unwrap().get(&id) {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget a job once it's done
+    pub fn finish(&self, id: u64) {
+        self.running.lock().unwrap().remove(&id);
+    }
+}
+
+/// The concentrations of a system at one point, sent as the `system-frame` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Frame {
+    /// The job that made the frame, [`None`] for changes that don't animate
+    pub job: Option<u64>,
+    /// The system the frame is of
+    pub idx: usize,
+    /// Seconds since the adjustment
+    pub time: f32,
+    /// Concentration of each compound in M (mol/L)
+    pub concentrations: Vec<f32>,
+}
+
+/// How a job ended, sent as the `job-finished` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finished {
+    /// The job
+    pub job: u64,
+    /// The system the job adjusted
+    pub idx: usize,
+    /// If the job was cancelled
+    pub cancelled: bool,
+    /// The error, if the adjustment failed
+    pub error: Option<ServiceError>,
+}
+
+/// Make an adjustment on a copy of `system`, returning it with its modelled approach to
+/// equilibrium, or [`None`] if the job was cancelled first
+pub fn solve(
+    system: &System,
+    adjust: &OwnedAdjustment,
+    cancel: &AtomicBool,
+) -> Result<Option<(System, Trajectory)>, AdjustError> {
+    let mut sys = system.clone();
+    sys.set_recorder(Some(Recorder::Samples(SAMPLES)));
+    let res = sys.adjust_observed(adjust, |_| match cancel.load(Ordering::Relaxed) {
+        true => ControlFlow::Break(()),
+        false => ControlFlow::Continue(()),
+    });
+    match res {
+        Ok(()) => {}
+        Err(AdjustError::System(SystemError::Cancelled)) => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let trajectory = sys.take_trajectory().unwrap_or_default();
+    sys.set_recorder(system.recorder());
+
+    Ok(Some((sys, trajectory)))
+}
+
+/// Indices of the frames to send, at most [`FRAME_RATE`] a second, always keeping the first
+/// and last
+pub fn throttle(time: &[f32]) -> Vec<usize> {
+    // a little under a frame, so rounding doesn't skip evenly spaced samples
+    let interval = FRAME_RATE.recip() / PLAYBACK_SECONDS * 0.99;
+    let mut keep: Vec<usize> = vec![];
+    for (i, &t) in time.iter().enumerate() {
+        let due = match keep.last() {
+            Some(&last) => t - time[last] >= interval,
+            None => true,
+        };
+        if due || i + 1 == time.len() {
+            keep.push(i);
+        }
+    }
+
+    keep
+}
+
+/// Send the frames of a trajectory in real time, returning `false` if the job was cancelled
+/// part way
+pub fn play(
+    trajectory: &Trajectory,
+    idx: usize,
+    job: u64,
+    cancel: &AtomicBool,
+    mut emit: impl FnMut(Frame),
+) -> bool {
+    let start = Instant::now();
+    for i in throttle(&trajectory.time) {
+        let time = trajectory.time[i] * PLAYBACK_SECONDS;
+        if let Some(wait) = Duration::from_secs_f32(time).checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+        if cancel.load(Ordering::Relaxed) {
+            return false;
+        }
+        emit(Frame {
+            job: Some(job),
+            idx,
+            time,
+            concentrations: trajectory
+                .series
+                .iter()
+                .map(|s| s.concentrations[i])
+                .collect(),
+        });
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::dinitrogen_tetroxide;
+
+    #[test]
+    fn solve_and_play() {
+        let mut system = dinitrogen_tetroxide();
+        system.adjust_owned(&OwnedAdjustment::Catalyst).unwrap();
+        let adjust = OwnedAdjustment::Volume(0.5);
+        let cancel = AtomicBool::new(false);
+        let (sys, trajectory) = solve(&system, &adjust, &cancel).unwrap().unwrap();
+        assert_eq!(sys.recorder(), None);
+
+        let mut frames = vec![];
+        assert!(play(&trajectory, 3, 7, &cancel, |f| frames.push(f)));
+        // a catalysed system takes a tenth of the time, so only a few frames are sent
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].job, Some(7));
+        assert_eq!(
+            frames.last().unwrap().concentrations,
+            sys.equation().get_concentrations()
+        );
+    }
+
+    #[test]
+    fn cancelled() {
+        let system = dinitrogen_tetroxide();
+        let adjust = OwnedAdjustment::Volume(0.5);
+        let cancel = AtomicBool::new(true);
+        assert_eq!(solve(&system, &adjust, &cancel), Ok(None));
+
+        let jobs = Jobs::default();
+        let (id, cancel) = jobs.start().unwrap();
+        assert!(jobs.cancel(id));
+        assert!(cancel.load(Ordering::Relaxed));
+        jobs.finish(id);
+        assert!(!jobs.cancel(id));
+    }
+
+    #[test]
+    fn jobs_are_bounded() {
+        let jobs = Jobs::default();
+        let ids = (0..MAX_JOBS)
+            .map(|_| jobs.start().unwrap().0)
+            .collect::<Vec<_>>();
+        assert!(jobs.start().is_none());
+
+        // a finished job makes room for another
+        jobs.finish(ids[0]);
+        assert!(jobs.start().is_some());
+    }
+
+    #[test]
+    fn throttle_frames() {
+        let time = (0..=60).map(|i| i as f32 / 60.0).collect::<Vec<_>>();
+        let keep = throttle(&time);
+        assert_eq!(keep.len(), 31);
+        assert_eq!(keep[1], 2);
+        assert_eq!(*keep.last().unwrap(), 60);
+    }
+}
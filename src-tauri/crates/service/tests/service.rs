@@ -0,0 +1,479 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+use chatelier::{Direction, OwnedAdjustment};
+use chem_eq::{classify::ReactionType, properties::BondType, ReactionQuotient, State};
+use lcp_service::{
+    export::ExportFormat,
+    session::{Answer, Session},
+    ErrorCode, Event, ServiceError, SimulationService,
+};
+
+const N2O4: &str = "N2O4(g) <-> 2NO2(g)";
+
+fn service() -> SimulationService {
+    let service = SimulationService::new(Session::new());
+    service
+        .add_system(N2O4, 0, &[1.0, 0.5], None, false)
+        .unwrap();
+    service
+}
+
+/// A service that sends its events down a channel
+fn listening() -> (SimulationService, mpsc::Receiver<Event>) {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let service = SimulationService::new(Session::new())
+        .with_listener(move |event| tx.lock().unwrap().send(event).unwrap());
+    service
+        .add_system(N2O4, 0, &[1.0, 0.5], None, false)
+        .unwrap();
+
+    (service, rx)
+}
+
+#[test]
+fn add_and_reset() {
+    let service = service();
+    assert_eq!(service.get_sys_concentration(0), Some(vec![1.0, 0.5]));
+
+    // adding again keeps the system unless it's reset
+    service
+        .add_system(N2O4, 0, &[2.0, 2.0], None, false)
+        .unwrap();
+    assert_eq!(service.get_sys_concentration(0), Some(vec![1.0, 0.5]));
+    service
+        .add_system(N2O4, 0, &[2.0, 2.0], None, true)
+        .unwrap();
+    assert_eq!(service.get_sys_concentration(0), Some(vec![2.0, 2.0]));
+
+    assert!(matches!(
+        service.add_system("N2O4(g) <-> ", 1, &[], None, false),
+        Err(ServiceError::Equation { .. })
+    ));
+    assert!(matches!(
+        service.add_system(N2O4, 1, &[1.0], None, false),
+        Err(ServiceError::ConcentrationSet(_))
+    ));
+    assert_eq!(service.get_sys_concentration(1), None);
+}
+
+#[test]
+fn add_once() {
+    // only one of several threads adding the same system starts it
+    let (tx, events) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let service = SimulationService::new(Session::new())
+        .with_listener(move |event| tx.lock().unwrap().send(event).unwrap());
+    std::thread::scope(|scope| {
+        for i in 0..8 {
+            let service = &service;
+            scope.spawn(move || {
+                service
+                    .add_system(N2O4, 0, &[1.0 + i as f32, 0.5], None, false)
+                    .unwrap();
+            });
+        }
+    });
+
+    assert_eq!(events.try_iter().count(), 1);
+}
+
+#[test]
+fn structured_errors() {
+    let service = service();
+    let err = service
+        .add_system("N2O4(g) <-> 2NO2(g) + ?", 1, &[1.0, 0.5], None, false)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ParseError);
+    let json = serde_json::to_value(&err).unwrap();
+    assert_eq!(json["details"]["span"]["start"], 22);
+
+    let err = service
+        .update_system(0, &OwnedAdjustment::Volume(-1.0))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InvalidAdjustment);
+    let err = service
+        .add_system("H2 + O2 -> H2O", 2, &[1.0, 1.0, 1.0], None, false)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::NotReversible);
+}
+
+#[test]
+fn adjust_undo_redo() {
+    let service = service();
+    let adjust = OwnedAdjustment::Volume(2.0);
+    assert_eq!(
+        service.get_shift_direction(0, &adjust).unwrap(),
+        Direction::Forward
+    );
+
+    let tested = service.test_adjustment(0, &adjust).unwrap();
+    assert_eq!(service.get_sys_concentration(0), Some(vec![1.0, 0.5]));
+    service.update_system(0, &adjust).unwrap();
+    let adjusted = service.get_sys_concentration(0).unwrap();
+    assert_eq!(adjusted, tested);
+
+    assert_eq!(service.undo_adjustment(0).unwrap(), Some(adjust.clone()));
+    assert_eq!(service.get_sys_concentration(0), Some(vec![1.0, 0.5]));
+    assert_eq!(service.redo_adjustment(0).unwrap(), Some(adjust));
+    assert_eq!(service.get_sys_concentration(0), Some(adjusted));
+}
+
+#[test]
+fn export_history() {
+    let service = service();
+    service
+        .update_system(0, &OwnedAdjustment::Volume(2.0))
+        .unwrap();
+    service
+        .update_system(0, &OwnedAdjustment::Catalyst)
+        .unwrap();
+    service.undo_adjustment(0).unwrap();
+
+    let csv = service.export_history(0, ExportFormat::Csv).unwrap();
+    // a header, the start and the adjustment that wasn't undone
+    assert_eq!(csv.lines().count(), 3);
+
+    let path = std::env::temp_dir()
+        .join("lcp-service-test")
+        .join("history.json");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    service.save_history(0, &path, ExportFormat::Json).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["equation"], N2O4);
+    assert_eq!(json["compounds"], serde_json::json!(["N2O4(g)", "2NO2(g)"]));
+    let concentrations: Vec<f32> =
+        serde_json::from_value(json["steps"][1]["concentrations"].clone()).unwrap();
+    assert_eq!(concentrations, service.get_sys_concentration(0).unwrap());
+
+    let err = service
+        .save_history(0, &path.join("not-a-dir"), ExportFormat::Csv)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ExportError);
+}
+
+#[test]
+fn missing_system() {
+    let service = service();
+    let adjust = OwnedAdjustment::Catalyst;
+    assert!(matches!(
+        service.update_system(5, &adjust),
+        Err(ServiceError::SystemNotFound { idx: 5 })
+    ));
+    assert!(matches!(
+        service.explain_shift(5, &adjust),
+        Err(ServiceError::SystemNotFound { idx: 5 })
+    ));
+    assert!(matches!(
+        service.set_temperature(5, 50.0),
+        Err(ServiceError::SystemNotFound { idx: 5 })
+    ));
+    assert!(matches!(
+        service.stream_adjustment(5, adjust),
+        Err(ServiceError::SystemNotFound { idx: 5 })
+    ));
+}
+
+#[test]
+fn record_and_explain() {
+    let service = service();
+    let adjust = OwnedAdjustment::Volume(0.5);
+    let explanation = service.explain_shift(0, &adjust).unwrap();
+    assert_eq!(explanation.explanation.direction, Direction::Reverse);
+    assert!(!explanation.text.is_empty());
+
+    let trajectory = service.record_adjustment(0, &adjust, 11).unwrap();
+    assert_eq!(trajectory.time.len(), 11);
+    assert_eq!(
+        trajectory
+            .series
+            .iter()
+            .map(|s| s.concentrations[10])
+            .collect::<Vec<_>>(),
+        service.get_sys_concentration(0).unwrap()
+    );
+
+    // too many samples is an error, and leaves the service usable
+    let err = service
+        .record_adjustment(0, &adjust, usize::MAX)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InvalidAdjustment);
+    assert!(service.get_sys_concentration(0).is_some());
+}
+
+#[test]
+fn system_state() {
+    let service = service();
+    service
+        .update_system(0, &OwnedAdjustment::Volume(2.0))
+        .unwrap();
+    let state = service.get_system_state(0).unwrap();
+    assert_eq!(state.equation, N2O4);
+    assert!(state.reversible);
+    assert_eq!(state.reaction_type, Some(ReactionType::Decomposition));
+    assert_eq!(state.volume, 2.0);
+    assert_eq!(
+        state
+            .compounds
+            .iter()
+            .map(|c| (c.name.as_str(), c.formula.as_str(), c.coefficient))
+            .collect::<Vec<_>>(),
+        vec![("N2O4(g)", "N2O4", 1), ("2NO2(g)", "NO2", 2)]
+    );
+    assert!(state.compounds.iter().all(|c| c.state == Some(State::Gas)));
+
+    let concentrations = service.get_sys_concentration(0).unwrap();
+    for (compound, concentration) in state.compounds.iter().zip(concentrations) {
+        assert_eq!(compound.concentration, concentration);
+        assert_eq!(compound.moles, concentration * 2.0);
+    }
+    let ReactionQuotient::Val(q) = state.q else {
+        panic!("no reaction quotient");
+    };
+    assert!((q - state.k).abs() / state.k < 0.01);
+
+    // the older command returns only the conditions
+    let conditions = service.get_sys_state(0).unwrap();
+    assert_eq!(conditions.volume, 2.0);
+    assert_eq!(conditions.k, state.k);
+    assert_eq!(conditions.q, state.q);
+    assert_eq!(
+        service.get_sys_state(5),
+        Err(ServiceError::SystemNotFound { idx: 5 })
+    );
+}
+
+#[test]
+fn temperature() {
+    let service = SimulationService::new(Session::new());
+    // exothermic, so heating it makes less product
+    let eq = "N2(g) + 3H2(g) <-> 2NH3(g)";
+    service
+        .add_system(eq, 0, &[1.0, 1.0, 1.0], Some(-92.2), false)
+        .unwrap();
+    let cold = service.get_system_state(0).unwrap();
+    assert_eq!(cold.temperature, 25.0);
+    assert_eq!(cold.volume, 1.0);
+    assert_eq!(cold.q, ReactionQuotient::Val(cold.k));
+
+    service.set_temperature(0, 100.0).unwrap();
+    let hot = service.get_system_state(0).unwrap();
+    assert_eq!(hot.temperature, 100.0);
+    assert!(hot.k < cold.k);
+    let ReactionQuotient::Val(q) = hot.q else {
+        panic!("no reaction quotient");
+    };
+    assert!((q - hot.k).abs() / hot.k < 0.01);
+
+    // the temperature is state, so setting it again changes nothing
+    service.set_temperature(0, 100.0).unwrap();
+    assert_eq!(service.get_system_state(0).unwrap().k, hot.k);
+    service.set_temperature(0, 25.0).unwrap();
+    assert!((service.get_system_state(0).unwrap().k - cold.k).abs() / cold.k < 1e-4);
+}
+
+#[test]
+fn events() {
+    let (service, events) = listening();
+    assert!(matches!(events.try_recv(), Ok(Event::Frame(f)) if f.job.is_none() && f.idx == 0));
+
+    service.set_sys_concentration(0, &[2.0, 1.0]).unwrap();
+    let Ok(Event::Frame(frame)) = events.try_recv() else {
+        panic!("no frame after setting concentrations");
+    };
+    assert_eq!(frame.concentrations, vec![2.0, 1.0]);
+    // the webview gets the frame alone
+    let json = serde_json::to_value(Event::Frame(frame)).unwrap();
+    assert_eq!(json["concentrations"], serde_json::json!([2.0, 1.0]));
+
+    // nothing is sent for a change that fails
+    assert!(service.set_sys_concentration(0, &[1.0]).is_err());
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn listener_can_call_back() {
+    // a listener that looks up the system it was told about, which needs the lock
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let service = Arc::new_cyclic(|weak: &std::sync::Weak<SimulationService>| {
+        let weak = weak.clone();
+        SimulationService::new(Session::new()).with_listener(move |event| {
+            if let (Event::Frame(frame), Some(service)) = (event, weak.upgrade()) {
+                let concentrations = service.get_sys_concentration(frame.idx);
+                tx.lock().unwrap().send(concentrations).unwrap();
+            }
+        })
+    });
+
+    service
+        .add_system(N2O4, 0, &[1.0, 0.5], None, false)
+        .unwrap();
+    assert_eq!(
+        rx.recv_timeout(Duration::from_secs(10)),
+        Ok(Some(vec![1.0, 0.5]))
+    );
+    service.set_sys_concentration(0, &[2.0, 1.0]).unwrap();
+    assert_eq!(
+        rx.recv_timeout(Duration::from_secs(10)),
+        Ok(Some(vec![2.0, 1.0]))
+    );
+    service
+        .stream_adjustment(0, OwnedAdjustment::Volume(2.0))
+        .unwrap();
+    assert!(rx.recv_timeout(Duration::from_secs(10)).unwrap().is_some());
+}
+
+#[test]
+fn failed_change_keeps_stream() {
+    // a change that fails doesn't change the system, so a stream solving it is still kept
+    let (service, events) = listening();
+    service
+        .stream_adjustment(0, OwnedAdjustment::Volume(2.0))
+        .unwrap();
+    assert!(service.set_sys_concentration(0, &[1.0]).is_err());
+
+    let finished = loop {
+        if let Event::Finished(finished) = events.recv_timeout(Duration::from_secs(10)).unwrap() {
+            break finished;
+        }
+    };
+    assert_eq!(finished.error, None);
+}
+
+#[test]
+fn stream_to_finish() {
+    let (service, events) = listening();
+    let _ = events.try_recv();
+    let job = service
+        .stream_adjustment(0, OwnedAdjustment::Volume(2.0))
+        .unwrap();
+
+    let mut frames = vec![];
+    let finished = loop {
+        match events.recv_timeout(Duration::from_secs(10)).unwrap() {
+            Event::Frame(frame) => frames.push(frame),
+            Event::Finished(finished) => break finished,
+        }
+    };
+    assert_eq!(finished.job, job);
+    assert!(!finished.cancelled);
+    assert_eq!(finished.error, None);
+    assert!(frames.len() > 2);
+    assert!(frames.iter().all(|f| f.job == Some(job)));
+    assert!(frames.windows(2).all(|w| w[0].time < w[1].time));
+    assert_eq!(
+        frames.last().unwrap().concentrations,
+        service.get_sys_concentration(0).unwrap()
+    );
+    // the job is forgotten once done
+    assert!(!service.cancel_job(job));
+}
+
+#[test]
+fn cancel_stream() {
+    let (service, events) = listening();
+    let _ = events.try_recv();
+    let job = service
+        .stream_adjustment(0, OwnedAdjustment::Volume(2.0))
+        .unwrap();
+    assert!(service.cancel_job(job));
+
+    let finished = loop {
+        if let Event::Finished(finished) = events.recv_timeout(Duration::from_secs(10)).unwrap() {
+            break finished;
+        }
+    };
+    assert!(finished.cancelled);
+}
+
+#[test]
+fn overlapping_streams() {
+    // both streams can start from the same state, then only the first to finish is kept
+    for _ in 0..3 {
+        let (service, events) = listening();
+        let a = OwnedAdjustment::Concentration("N2O4(g)".into(), 2.0);
+        service.stream_adjustment(0, a).unwrap();
+        service
+            .stream_adjustment(0, OwnedAdjustment::Volume(0.5))
+            .unwrap();
+
+        let mut kept = 0;
+        let mut finished = 0;
+        while finished < 2 {
+            if let Event::Finished(f) = events.recv_timeout(Duration::from_secs(10)).unwrap() {
+                finished += 1;
+                match f.error {
+                    None => kept += 1,
+                    Some(e) => assert_eq!(e, ServiceError::SystemChanged { idx: 0 }),
+                }
+            }
+        }
+
+        // every adjustment that was kept is in the history, none were lost
+        let mut undone = 0;
+        while service.undo_adjustment(0).unwrap().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, kept);
+        assert_eq!(service.get_sys_concentration(0), Some(vec![1.0, 0.5]));
+    }
+}
+
+#[test]
+fn describe_compound() {
+    let service = service();
+    let info = service.describe_compound("Fe2O3").unwrap();
+    assert_eq!(info.bond_type, BondType::Ionic);
+    assert_eq!(
+        info.elements.iter().map(|e| e.symbol).collect::<Vec<_>>(),
+        vec!["Fe", "O"]
+    );
+    assert_eq!(info.elements[1].group, Some(16));
+
+    // each element is described once
+    let info = service.describe_compound("CH3COOH").unwrap();
+    assert_eq!(info.elements.len(), 3);
+
+    let err = service.describe_compound("Fe2O3 +").unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ParseError);
+    assert_eq!(
+        serde_json::to_value(&err).unwrap()["details"],
+        serde_json::json!({"compound": "Fe2O3 +"})
+    );
+}
+
+#[test]
+fn quiz_and_session() {
+    let service = service();
+    assert_eq!(service.get_question_count(), 5);
+    assert_eq!(service.get_question(1).unwrap().id, 1);
+    assert_eq!(service.generate_quiz(3, 4).unwrap().len(), 4);
+
+    let answer = Answer {
+        selected: Some(1),
+        changes: None,
+        correct: false,
+    };
+    service.record_answer(2, answer.clone());
+    assert_eq!(service.get_quiz_progress().answers[&2], answer);
+
+    // a service started from a saved session picks up where it left off
+    let path = std::env::temp_dir()
+        .join("lcp-service-test")
+        .join(lcp_service::session::FILE_NAME);
+    service
+        .update_system(0, &OwnedAdjustment::Catalyst)
+        .unwrap();
+    service.save(&path).unwrap();
+    let restored = SimulationService::new(Session::load(&path));
+    assert_eq!(
+        restored.get_sys_concentration(0),
+        service.get_sys_concentration(0)
+    );
+    assert_eq!(restored.get_quiz_progress(), service.get_quiz_progress());
+}
@@ -3,56 +3,49 @@
     windows_subsystem = "windows"
 )]
 
-use std::{collections::HashMap, sync::Mutex};
+use std::path::PathBuf;
 
-use chatelier::{AdjustError, Adjustment, Direction, System, SystemError};
-use chem_eq::{
-    error::{ConcentrationError, EquationError},
-    Equation,
+use chatelier::{Direction, OwnedAdjustment, Trajectory};
+use lcp_service::{
+    export::ExportFormat,
+    session::{self, Answer, QuizProgress, Session},
+    CompoundInfo, Conditions, ServiceError, ShiftExplanation, SimulationService, SystemState,
 };
-use serde::{Deserialize, Serialize};
-use tauri::Manager;
-use thiserror::Error;
-use tracing::{debug, info, instrument};
+use quiz::Question;
+use tauri::{api::dialog::blocking::FileDialogBuilder, Manager, RunEvent};
+use tracing::{info, instrument, warn};
 
-#[derive(Debug, Clone, Default)]
-pub struct QuestionSystems(HashMap<usize, System>);
-
-impl std::ops::Deref for QuestionSystems {
-    type Target = HashMap<usize, System>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl std::ops::DerefMut for QuestionSystems {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
-}
-
-#[derive(Debug, Error, Clone, Serialize, Deserialize)]
-enum AppError {
-    #[error("{0}")]
-    Equation(#[from] EquationError),
-    #[error("{0}")]
-    System(#[from] SystemError),
-    #[error("{0}")]
-    Adjust(#[from] AdjustError),
-    #[error("{0}")]
-    ConcentrationSet(#[from] ConcentrationError),
-    #[error("system not found")]
-    SystemNotFound,
-}
+/// Where the session is saved, [`None`] if there's no app data directory
+struct SessionPath(Option<PathBuf>);
 
 fn main() {
     tauri::Builder::default()
-        .setup(|_app| {
+        .setup(|app| {
             tracing_subscriber::fmt().without_time().init();
+
+            let path = app
+                .path_resolver()
+                .app_data_dir()
+                .map(|dir| dir.join(session::FILE_NAME));
+            let session = match &path {
+                Some(path) => Session::load(path),
+                None => {
+                    warn!("No app data directory, the session won't be saved");
+                    Session::new()
+                }
+            };
+            info!("Restored {} systems", session.systems.len());
+            let handle = app.handle();
+            let service = SimulationService::new(session).with_listener(move |event| {
+                if let Err(e) = handle.emit_all(event.name(), event) {
+                    warn!("Couldn't send event: {}", e);
+                }
+            });
+            app.manage(service);
+            app.manage(SessionPath(path));
+
             Ok(())
         })
-        .manage(Mutex::new(QuestionSystems::default()))
         .invoke_handler(tauri::generate_handler![
             quit,
             close_splashscreen,
@@ -60,17 +53,52 @@ fn main() {
             get_sys_concentration,
             set_sys_concentration,
             update_system,
+            stream_adjustment,
+            cancel_job,
+            record_adjustment,
             test_adjustment,
+            undo_adjustment,
+            redo_adjustment,
+            export_history,
             get_shift_direction,
-            toggle_heat,
+            explain_shift,
+            set_temperature,
+            get_sys_state,
+            get_system_state,
+            describe_compound,
+            get_question,
+            get_question_count,
+            generate_quiz,
+            record_answer,
+            get_quiz_progress,
+            save_session,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let RunEvent::Exit = event {
+                if let Err(e) = save(app_handle) {
+                    warn!("{}", e);
+                }
+            }
+        });
+}
+
+/// Save every system and the quiz progress
+fn save(app_handle: &tauri::AppHandle) -> Result<(), ServiceError> {
+    match &app_handle.state::<SessionPath>().0 {
+        Some(path) => app_handle.state::<SimulationService>().save(path),
+        None => Ok(()),
+    }
 }
 
 #[tauri::command]
 #[instrument(skip(app_handle))]
 fn quit(app_handle: tauri::AppHandle, code: i32) {
+    // exiting here skips `RunEvent::Exit`
+    if let Err(e) = save(&app_handle) {
+        warn!("{}", e);
+    }
     app_handle.exit(code)
 }
 
@@ -86,149 +114,221 @@ fn close_splashscreen(window: tauri::Window) {
 }
 
 #[tauri::command]
-#[instrument(skip(state, concentrations, eq_str))]
+#[instrument(skip(service, concentrations, eq_str))]
 fn add_system(
-    state: tauri::State<Mutex<QuestionSystems>>,
+    service: tauri::State<SimulationService>,
     eq_str: &str,
     idx: usize,
     concentrations: Vec<f32>,
+    delta_h: Option<f32>,
     reset: bool,
-) -> Result<(), AppError> {
-    let exists = state.lock().unwrap().get(&idx).is_some();
-    if exists && !reset {
-        info!("System {} already exists, doing nothing", idx);
-        return Ok(());
-    } else if exists {
-        info!("Resetting system {}", idx);
-    } else {
-        info!("Initializing '{}'", eq_str);
-    }
-    let mut eq = Equation::new(eq_str)?;
-    eq.set_concentrations(concentrations.as_slice())?;
-    let system = System::new(eq)?;
-
-    info!("Starting system {}", idx);
-    state.lock().unwrap().insert(idx, system);
-
-    Ok(())
+) -> Result<(), ServiceError> {
+    service.add_system(eq_str, idx, &concentrations, delta_h, reset)
 }
 
 #[tauri::command]
-#[instrument(skip(state))]
-fn get_sys_concentration(
-    state: tauri::State<Mutex<QuestionSystems>>,
-    idx: usize,
-) -> Option<Vec<f32>> {
-    state
-        .lock()
-        .unwrap()
-        .get(&idx)
-        .map(|s| s.equation().get_concentrations())
+#[instrument(skip(service))]
+fn get_sys_concentration(service: tauri::State<SimulationService>, idx: usize) -> Option<Vec<f32>> {
+    service.get_sys_concentration(idx)
 }
 
 #[tauri::command]
-#[instrument(skip(state, concentrations))]
+#[instrument(skip(service, concentrations))]
 fn set_sys_concentration(
-    state: tauri::State<Mutex<QuestionSystems>>,
+    service: tauri::State<SimulationService>,
     idx: usize,
     concentrations: Vec<f32>,
-) -> Result<(), AppError> {
-    info!("Setting concentrations for {}: {:?}", idx, concentrations);
-
-    state
-        .lock()
-        .unwrap()
-        .get_mut(&idx)
-        .ok_or(AppError::SystemNotFound)?
-        .equation_mut()
-        .set_concentrations(concentrations.as_slice())?;
-
-    Ok(())
+) -> Result<(), ServiceError> {
+    service.set_sys_concentration(idx, &concentrations)
 }
 
 #[tauri::command]
-#[instrument(skip(state, adjust))]
+#[instrument(skip(service, adjust))]
 fn update_system(
-    state: tauri::State<Mutex<QuestionSystems>>,
+    service: tauri::State<SimulationService>,
     idx: usize,
-    adjust: Adjustment,
-) -> Result<(), AppError> {
-    info!("Updating system {} with {:?}...", idx, adjust);
+    adjust: OwnedAdjustment,
+) -> Result<(), ServiceError> {
+    service.update_system(idx, &adjust)
+}
 
-    state
-        .lock()
-        .unwrap()
-        .get_mut(&idx)
-        .ok_or(AppError::SystemNotFound)?
-        .adjust(adjust)?;
+#[tauri::command]
+#[instrument(skip(service, adjust))]
+fn stream_adjustment(
+    service: tauri::State<SimulationService>,
+    idx: usize,
+    adjust: OwnedAdjustment,
+) -> Result<u64, ServiceError> {
+    service.stream_adjustment(idx, adjust)
+}
 
-    debug!("Finished adjusting system");
+#[tauri::command]
+#[instrument(skip(service))]
+fn cancel_job(service: tauri::State<SimulationService>, id: u64) -> bool {
+    service.cancel_job(id)
+}
 
-    Ok(())
+#[tauri::command]
+#[instrument(skip(service, adjust))]
+fn record_adjustment(
+    service: tauri::State<SimulationService>,
+    idx: usize,
+    adjust: OwnedAdjustment,
+    samples: usize,
+) -> Result<Trajectory, ServiceError> {
+    service.record_adjustment(idx, &adjust, samples)
 }
 
 #[tauri::command]
-#[instrument(skip(state, adjust))]
+#[instrument(skip(service, adjust))]
 fn test_adjustment(
-    state: tauri::State<Mutex<QuestionSystems>>,
+    service: tauri::State<SimulationService>,
     idx: usize,
-    adjust: Adjustment,
-) -> Result<Vec<f32>, AppError> {
-    info!("Testing system {} with {:?}...", idx, adjust);
-    let mut lock = state.lock().unwrap();
-    let system = lock.get_mut(&idx).ok_or(AppError::SystemNotFound)?;
-    let concentrations = system.equation().get_concentrations();
+    adjust: OwnedAdjustment,
+) -> Result<Vec<f32>, ServiceError> {
+    service.test_adjustment(idx, &adjust)
+}
 
-    debug!("Adjusting system");
-    system.adjust(adjust)?;
+#[tauri::command]
+#[instrument(skip(service))]
+fn undo_adjustment(
+    service: tauri::State<SimulationService>,
+    idx: usize,
+) -> Result<Option<OwnedAdjustment>, ServiceError> {
+    service.undo_adjustment(idx)
+}
 
-    let results = system.equation().get_concentrations();
+#[tauri::command]
+#[instrument(skip(service))]
+fn redo_adjustment(
+    service: tauri::State<SimulationService>,
+    idx: usize,
+) -> Result<Option<OwnedAdjustment>, ServiceError> {
+    service.redo_adjustment(idx)
+}
 
-    debug!("Reverting system");
-    system.equation_mut().set_concentrations(&concentrations)?;
+/// Ask where to save a system's history, returning where it was saved or [`None`] if the dialog
+/// was closed
+#[tauri::command]
+#[instrument(skip(service))]
+async fn export_history(
+    service: tauri::State<'_, SimulationService>,
+    idx: usize,
+    format: ExportFormat,
+) -> Result<Option<PathBuf>, ServiceError> {
+    let extension = format.extension();
+    let path = FileDialogBuilder::new()
+        .set_title("Export history")
+        .set_file_name(&format!("history.{}", extension))
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file();
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    service.save_history(idx, &path, format)?;
+
+    Ok(Some(path))
+}
+
+#[tauri::command]
+#[instrument(skip(service))]
+fn describe_compound(
+    service: tauri::State<SimulationService>,
+    compound: String,
+) -> Result<CompoundInfo, ServiceError> {
+    service.describe_compound(&compound)
+}
+
+#[tauri::command]
+#[instrument(skip(service))]
+fn get_question(service: tauri::State<SimulationService>, id: usize) -> Option<Question> {
+    service.get_question(id)
+}
 
-    debug!("Finished testing system");
+#[tauri::command]
+#[instrument(skip(service))]
+fn get_question_count(service: tauri::State<SimulationService>) -> usize {
+    service.get_question_count()
+}
 
-    Ok(results)
+#[tauri::command]
+#[instrument(skip(service))]
+fn generate_quiz(
+    service: tauri::State<SimulationService>,
+    seed: u64,
+    count: usize,
+) -> Result<Vec<Question>, ServiceError> {
+    service.generate_quiz(seed, count)
 }
 
 #[tauri::command]
-#[instrument(skip(state, adjust))]
+#[instrument(skip(app_handle, service))]
+fn record_answer(
+    app_handle: tauri::AppHandle,
+    service: tauri::State<SimulationService>,
+    id: usize,
+    answer: Answer,
+) -> Result<(), ServiceError> {
+    service.record_answer(id, answer);
+    save(&app_handle)
+}
+
+#[tauri::command]
+#[instrument(skip(service))]
+fn get_quiz_progress(service: tauri::State<SimulationService>) -> QuizProgress {
+    service.get_quiz_progress()
+}
+
+#[tauri::command]
+#[instrument(skip(app_handle))]
+fn save_session(app_handle: tauri::AppHandle) -> Result<(), ServiceError> {
+    save(&app_handle)
+}
+
+#[tauri::command]
+#[instrument(skip(service, adjust))]
 fn get_shift_direction(
-    state: tauri::State<Mutex<QuestionSystems>>,
+    service: tauri::State<SimulationService>,
     idx: usize,
-    adjust: Adjustment,
-) -> Result<Direction, AppError> {
-    let res = state
-        .lock()
-        .unwrap()
-        .get(&idx)
-        .ok_or(AppError::SystemNotFound)?
-        .get_shift_direction(adjust)?;
-
-    Ok(res)
+    adjust: OwnedAdjustment,
+) -> Result<Direction, ServiceError> {
+    service.get_shift_direction(idx, &adjust)
 }
 
 #[tauri::command]
-#[instrument(skip(state))]
-fn toggle_heat(
-    state: tauri::State<Mutex<QuestionSystems>>,
+#[instrument(skip(service, adjust))]
+fn explain_shift(
+    service: tauri::State<SimulationService>,
     idx: usize,
-    heat: bool,
-) -> Result<(), AppError> {
-    let addend = if heat {
-        info!("turning on heat");
-        2.0
-    } else {
-        info!("turning off heat");
-        0.5
-    };
-    let mut state = state.lock().unwrap();
+    adjust: OwnedAdjustment,
+) -> Result<ShiftExplanation, ServiceError> {
+    service.explain_shift(idx, &adjust)
+}
 
-    let sys = state.get_mut(&idx).ok_or(AppError::SystemNotFound)?;
+#[tauri::command]
+#[instrument(skip(service))]
+fn set_temperature(
+    service: tauri::State<SimulationService>,
+    idx: usize,
+    temperature: f32,
+) -> Result<(), ServiceError> {
+    service.set_temperature(idx, temperature)
+}
 
-    sys.mul_k_expr(addend);
-    sys.update();
+#[tauri::command]
+#[instrument(skip(service))]
+fn get_sys_state(
+    service: tauri::State<SimulationService>,
+    idx: usize,
+) -> Result<Conditions, ServiceError> {
+    service.get_sys_state(idx)
+}
 
-    Ok(())
+#[tauri::command]
+#[instrument(skip(service))]
+fn get_system_state(
+    service: tauri::State<SimulationService>,
+    idx: usize,
+) -> Result<SystemState, ServiceError> {
+    service.get_system_state(idx)
 }
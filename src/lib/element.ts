@@ -0,0 +1,44 @@
+import { invoke } from '@tauri-apps/api/tauri';
+
+export type BondType = 'NonpolarCovalent' | 'PolarCovalent' | 'Ionic' | 'Metallic';
+
+// where an element is on the periodic table and how it bonds
+export type ElementSummary = {
+	symbol: string;
+	name: string;
+	atomic_number: number;
+	// null for the lanthanides and actinides
+	group: number | null;
+	period: number;
+	block: 'S' | 'P' | 'D' | 'F';
+	metallicity: 'Metal' | 'Metalloid' | 'Nonmetal';
+	// pauling electronegativity, null if it isn't known
+	electronegativity: number | null;
+	// the most common oxidation states, lowest first
+	oxidation_states: number[];
+};
+
+// how a compound is bonded and each of its elements
+export type CompoundInfo = {
+	compound: string;
+	bond_type: BondType;
+	elements: ElementSummary[];
+};
+
+export async function describeCompound(compound: string): Promise<CompoundInfo> {
+	return await invoke('describe_compound', { compound });
+}
+
+// a bond type as it's written in a sentence
+export function bondName(bond: BondType): string {
+	switch (bond) {
+		case 'NonpolarCovalent':
+			return 'nonpolar covalent';
+		case 'PolarCovalent':
+			return 'polar covalent';
+		case 'Ionic':
+			return 'ionic';
+		case 'Metallic':
+			return 'metallic';
+	}
+}
@@ -0,0 +1,45 @@
+// every failure from the backend, see `lcp_service::ErrorCode`
+export type ErrorCode =
+	| 'PARSE_ERROR'
+	| 'INVALID_EQUATION'
+	| 'UNKNOWN_COMPOUND'
+	| 'ZERO_CONCENTRATION'
+	| 'INVALID_CONCENTRATION'
+	| 'NOT_REVERSIBLE'
+	| 'SYSTEM_NOT_FOUND'
+	| 'SYSTEM_CHANGED'
+	| 'TOO_MANY_JOBS'
+	| 'SOLVER_DIVERGED'
+	| 'INVALID_ADJUSTMENT'
+	| 'CANCELLED'
+	| 'INVALID_QUESTION'
+	| 'SESSION_ERROR'
+	| 'EXPORT_ERROR';
+
+// an error returned by a command
+export type AppError = {
+	// what went wrong, this doesn't change between versions
+	code: ErrorCode;
+	// an english description, for logs
+	message: string;
+	// more about the error, depending on the code
+	details: ErrorDetails | null;
+};
+
+export type ErrorDetails = {
+	// the equation that was given, for `PARSE_ERROR` and `INVALID_EQUATION`
+	equation?: string;
+	// where in the equation parsing failed, in bytes
+	span?: { start: number; end: number };
+	// the system that wasn't found, or that changed during a stream
+	idx?: number;
+	// the most allowed, for `TOO_MANY_JOBS` and too many samples
+	max?: number;
+	// the name of the underlying error, eg. 'DidNotConverge'
+	reason?: unknown;
+};
+
+// if a rejected command gave an `AppError`
+export function isAppError(e: unknown): e is AppError {
+	return typeof e === 'object' && e !== null && 'code' in e && 'message' in e;
+}
@@ -1,63 +1,92 @@
-// the generic question type
+import { invoke } from '@tauri-apps/api/tauri';
+
+// a quiz question, served from the question bank by the backend
 export type Question = {
-	// number out of 10
+	// position in the quiz, starting from 1
 	id: number;
-	// the equation of the problem
+	// the equation of the problem, eg. 'N2O4(g) <-> 2NO2(g)'
 	equation: string;
 	// the prompt
 	prompt: string;
 	// the original concentrations for resetting
 	defaults: number[];
-	// the answer type of the question... might be better to convert to
-	// generics over an interface...
+	// ΔH of the forward reaction in kJ
+	delta_h?: number;
+	// the answer type of the question
 	q: MultipleChoiceQuestion | InteractiveQuestion;
 };
 
 export enum QuestionType {
-	MultipleChoice,
-	Interactive
+	MultipleChoice = 'MultipleChoice',
+	Interactive = 'Interactive'
 }
 
-// a multiple choice question
+// a multiple choice question, checked against the simulator by the backend
 export type MultipleChoiceQuestion = {
 	// the type of question
 	type: QuestionType.MultipleChoice;
+	// how the simulator decides which choice is right
+	check: Check;
 	// the choices
-	options: [string, string, string, string];
-	// the change to the system for each option
-	actions: [Adjust, Adjust, Adjust, Adjust];
+	choices: Choice[];
 	// the index of the correct answer
 	correct: number;
-	// the explanation for each option
-	explanations: [string, string, string, string];
-	// if the question has hardcoded actions
-	isHardcoded: boolean;
-	// the hardcoded new concentrations for each mc answer
-	hardcoded: [number[], number[], number[], number[]];
+};
+
+// how the right choice of a multiple choice question is decided
+export type Check =
+	| { Shift: Direction }
+	| { Predict: Adjust }
+	| { DeltaH: { adjustment: Adjust; shift: Direction } };
+
+// which way the equilibrium shifts
+export type Direction = 'Forward' | 'Reverse' | 'None';
+
+// one choice of a multiple choice question
+export type Choice = {
+	// what the student sees
+	text: string;
+	// why the choice is right or wrong
+	explanation: string;
+	// the change this choice makes, for `Shift` questions
+	adjustment?: Adjust;
+	// the shift this choice predicts, for `Predict` questions
+	shift?: Direction;
+	// the ΔH this choice claims, for `DeltaH` questions
+	delta_h?: number;
 };
 
 // an interactive question with sliders
 export type InteractiveQuestion = {
 	// the type of question
 	type: QuestionType.Interactive;
-	// function to return if the question is correct, assumes the changes have been applied
-	isRight: (change: number[]) => boolean;
+	// the compound that should change, by name or index
+	compound: string | number;
+	// if the compound should end up higher than it started
+	increase: boolean;
 	// message to display for a correct answer
-	correctMsg: string;
+	correct_msg: string;
 	// message to display for a incorrect answer
-	incorrectMsg: string;
+	incorrect_msg: string;
 };
 
 // An adjustment to the system
-export type Adjust = { Concentration: [string, number] };
-
-function defaultActions(): [Adjust, Adjust, Adjust, Adjust] {
-	return [
-		{ Concentration: ['a', 1] },
-		{ Concentration: ['b', 2] },
-		{ Concentration: ['c', 3] },
-		{ Concentration: ['d', 4] }
-	];
+export type Adjust =
+	| ConcentrationAdjust
+	| 'Catalyst'
+	| { InertGas: InertGas }
+	| { Temperature: number }
+	| { Volume: number }
+	| { Batch: Adjust[] };
+
+// A change in concentration of one compound, by name or index
+export type ConcentrationAdjust = { Concentration: [string | number, number] };
+
+// How inert gas is added, and how much in mol/L
+export type InertGas = { AtConstantVolume: number } | { AtConstantPressure: number };
+
+export function isConcentration(adjust: Adjust): adjust is ConcentrationAdjust {
+	return typeof adjust === 'object' && 'Concentration' in adjust;
 }
 
 export function findChange(
@@ -94,126 +123,69 @@ export function increaseAndCompound(
 	return ['', ''];
 }
 
-export const questions: Question[] = [
-	{
-		id: 1,
-		equation: 'SO2 + NO2 ↔ NO + SO3',
-		prompt: 'Which change will cause an equilibrium shift to the right?',
-		defaults: [2.0, 2.0, 2.0, 2.0],
-		q: {
-			correct: 2,
-			type: QuestionType.MultipleChoice,
-			options: [
-				'Increase concentration of NO',
-				'Increase concentration of SO3',
-				'Decrease concentration of NO',
-				'Decrease concentration of SO2'
-			],
-			actions: [
-				{ Concentration: ['NO', 2.5] },
-				{ Concentration: ['SO3', 2.5] },
-				{ Concentration: ['NO', 1.5] },
-				{ Concentration: ['SO2', 1.5] }
-			],
-			explanations: [
-				'Increasing the concentration of NO increases concentration of the products causing a shift to the left.',
-				'Increasing the concentration of SO3 adds to the products so to counteract this the equilibrium will shift left.',
-				'Decreasing NO will decrease the concentration of the products so the equilibrium will shift to replace it, and shift right.',
-				'Decreasing SO2 will decrease the concentration of the reactants so the equilibrium will shift left to replace it.'
-			],
-			isHardcoded: false,
-			hardcoded: [[], [], [], []]
-		}
-	},
-	{
-		id: 2,
-		equation: '2NH3(g) ↔ N2(g) + 3H2(g)',
-		prompt: 'Modify the system to produce more ammonia',
-		defaults: [2.0, 1.0, 1.5],
-		q: {
-			isRight: function (change: number[]): boolean {
-				return change[0] > 2.0;
-			},
-			type: QuestionType.Interactive,
-			correctMsg:
-				'You caused ammonia to be increased by adding ammonia or adding nitrogen or hydrogen. Adding nitrogen or hydrogen caused an equilibrium shift to the left, producing ammonia.',
-			incorrectMsg:
-				'You caused ammonia to be decreased by removing ammonia or removing nitrogen or hydrogen. Removing nitrogen or hydrogen caused an equilibrium shift to the right, consuming ammonia.'
-		}
-	},
-	{
-		id: 3,
-		equation: '2NH3(g) ↔ N2(g) + 3H2(g)',
-		prompt: 'What would increase the concentration of NH3?',
-		defaults: [2.0, 1.0, 1.5],
-		q: {
-			correct: 1,
-			type: QuestionType.MultipleChoice,
-			options: [
-				'Increase the volume',
-				'Increase the pressure',
-				'Add a catalyst',
-				'Increase the surface area'
-			],
-			actions: defaultActions(),
-			explanations: [
-				'The molar ratio is 2:4, so increasing volume will shift right, decreasing ammonia',
-				'The molar ratio is 2:4 so increasing pressure will shift to the lesser side, the right, increasing ammonia',
-				'Adding a catalyst will not cause a shift in equilibrium',
-				'Increasing surface area will not cause a shift in equilibrium'
-			],
-			isHardcoded: true,
-			hardcoded: [
-				[1.9, 1.05, 1.65],
-				[2.2, 0.9, 1.3],
-				[2.0, 1.0, 1.5],
-				[2.0, 1.0, 1.5]
-			]
-		}
-	},
-	{
-		id: 4,
-		equation: '2NH3(g) ↔ N2(g) + 3H2(g)',
-		prompt: 'What would occur if you increased volume?',
-		defaults: [2.0, 1.0, 1.5],
-		q: {
-			correct: 1,
-			type: QuestionType.MultipleChoice,
-			options: ['Increase NH3', 'Increase N2', 'Decrease N2', 'Decrease H2'],
-			actions: defaultActions(),
-			explanations: [
-				'The molar ratio is 2:4 so the equilibrium will shift to the right, decreasing NH3',
-				'The equilibrium will shift right, increasing N2',
-				'The equilibrium will shift right, increasing N2',
-				'The equilibrium will shift right, increasing H2'
-			],
-			isHardcoded: true,
-			hardcoded: [
-				[1.8, 1.15, 1.75],
-				[1.8, 1.15, 1.75],
-				[1.8, 1.15, 1.75],
-				[1.8, 1.15, 1.75]
-			]
-		}
-	},
-	{
-		id: 5,
-		equation: 'CH3COOH + H2O ↔ CH3COO + H3O',
-		prompt: 'Temperature is increased at t<sub>1</sub>, what is this reaction?',
-		defaults: [2.0],
-		q: {
-			type: QuestionType.MultipleChoice,
-			correct: 1,
-			options: ['Endothermic', 'Exothermic', '', ''],
-			actions: defaultActions(),
-			explanations: [
-				'An endothermic reaction consumes energy. At t<sub>1</sub> temperature is increased and H<sub>3</sub>O<sup>+</sup> is decreased indicating a shift to the left. Since energy is a reactant in endothermic reactions, this reaction is not endothermic.',
-				'An exothermic reaction produces energy. At t<sub>1</sub> temperature is increased and H<sub>3</sub>O<sup>+</sup> is decreased indicating a shift to the left. This means energy was a product which makes the reaction exothermic.',
-				'',
-				''
-			],
-			isHardcoded: false,
-			hardcoded: [[], [], [], []]
-		}
+// get a question from the backend's question bank
+export async function getQuestion(id: number): Promise<Question> {
+	const question: Question | null = await invoke('get_question', { id });
+	if (question === null) {
+		throw new Error(`question ${id} not found`);
+	}
+	return question;
+}
+
+// the names of each compound of an equation
+export function compoundsOf(equation: string): string[] {
+	return equation.split(' ').filter((x) => x !== '+' && x !== '<->');
+}
+
+// the equation as shown to the student
+export function displayEquation(equation: string): string {
+	return equation.replace('<->', '↔');
+}
+
+// the change to make to the system for a multiple choice answer, null if nothing changes
+export function choiceAction(mc: MultipleChoiceQuestion, idx: number): Adjust | null {
+	if ('Predict' in mc.check) {
+		return mc.check.Predict;
 	}
-];
+	if ('Shift' in mc.check) {
+		return mc.choices[idx].adjustment ?? null;
+	}
+	return null;
+}
+
+// if the concentrations after a change answer an interactive question
+export function isRight(
+	q: InteractiveQuestion,
+	concentrations: number[],
+	defaults: number[],
+	compounds: string[]
+): boolean {
+	const idx = typeof q.compound === 'number' ? q.compound : compounds.indexOf(q.compound);
+	return q.increase ? concentrations[idx] > defaults[idx] : concentrations[idx] < defaults[idx];
+}
+
+// generate random questions from a seed, the same seed always gives the same questions
+export async function generateQuiz(seed: number, count: number): Promise<Question[]> {
+	return await invoke('generate_quiz', { seed, count });
+}
+
+// an answer to a quiz question, saved between runs
+export type Answer = {
+	// the choice picked for a multiple choice question
+	selected: number | null;
+	// the concentrations set for an interactive question
+	changes: number[] | null;
+	// if the answer was right
+	correct: boolean;
+};
+
+// save the answer to a question
+export async function recordAnswer(id: number, answer: Answer): Promise<void> {
+	await invoke('record_answer', { id, answer });
+}
+
+// get the last answer to a question, if it's been answered
+export async function getAnswer(id: number): Promise<Answer | null> {
+	const progress: { answers: Record<string, Answer> } = await invoke('get_quiz_progress');
+	return progress.answers[id] ?? null;
+}
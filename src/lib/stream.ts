@@ -0,0 +1,82 @@
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/tauri';
+import type { AppError } from './error';
+import type { Adjust } from './question';
+
+// the concentrations of a system at one point while it reacts
+export type Frame = {
+	// the job that sent the frame, null for changes that don't animate
+	job: number | null;
+	// the system the frame is of
+	idx: number;
+	// seconds since the adjustment
+	time: number;
+	// concentration of each compound in M (mol/L)
+	concentrations: number[];
+};
+
+// how a streamed adjustment ended
+export type Finished = {
+	job: number;
+	idx: number;
+	// if the job was cancelled before it finished
+	cancelled: boolean;
+	// the error if the adjustment failed
+	error: AppError | null;
+};
+
+// call `onFrame` with every frame of a system, returning a function to stop listening
+export async function onFrames(idx: number, onFrame: (frame: Frame) => void): Promise<UnlistenFn> {
+	return await listen<Frame>('system-frame', (event) => {
+		if (event.payload.idx === idx) {
+			onFrame(event.payload);
+		}
+	});
+}
+
+// make an adjustment in the background, resolving once its last frame is sent
+export async function streamAdjustment(
+	idx: number,
+	adjust: Adjust,
+	onFrame: (frame: Frame) => void
+): Promise<Finished> {
+	let job: number | null = null;
+	// events can arrive before the job's id does, so hold them until then
+	let early: Frame[] = [];
+	let done: Finished[] = [];
+	let resolve: (finished: Finished) => void = () => undefined;
+	const finished = new Promise<Finished>((r) => (resolve = r));
+
+	// listen before starting, so no events are missed
+	const unlistenFrames = await listen<Frame>('system-frame', (event) => {
+		if (job === null) {
+			early.push(event.payload);
+		} else if (event.payload.job === job) {
+			onFrame(event.payload);
+		}
+	});
+	const unlistenFinished = await listen<Finished>('job-finished', (event) => {
+		if (job === null) {
+			done.push(event.payload);
+		} else if (event.payload.job === job) {
+			resolve(event.payload);
+		}
+	});
+
+	try {
+		job = (await invoke('stream_adjustment', { idx, adjust })) as number;
+		early.filter((frame) => frame.job === job).forEach(onFrame);
+		done.filter((f) => f.job === job).forEach(resolve);
+		early = [];
+		done = [];
+		return await finished;
+	} finally {
+		unlistenFrames();
+		unlistenFinished();
+	}
+}
+
+// stop a streamed adjustment, returning false if it had already finished
+export async function cancelJob(id: number): Promise<boolean> {
+	return await invoke('cancel_job', { id });
+}
@@ -0,0 +1,74 @@
+import { invoke } from '@tauri-apps/api/tauri';
+
+// the reaction quotient, or which side of it was zero
+export type ReactionQuotient = { Val: number } | 'LeftZero' | 'RightZero' | 'BothSidesZero';
+
+export type State = 'Solid' | 'Liquid' | 'Gas' | 'Aqueous';
+
+export type ReactionType =
+	| 'Synthesis'
+	| 'Decomposition'
+	| 'SingleReplacement'
+	| 'DoubleReplacement'
+	| 'Combustion'
+	| 'Neutralization'
+	| 'Precipitation'
+	| 'Redox';
+
+// a compound in a system, in the order it's written in the equation
+export type CompoundState = {
+	// as written in the equation, eg. 2NO2(g)
+	name: string;
+	// without the coefficient or state, eg. NO2
+	formula: string;
+	state: State | null;
+	coefficient: number;
+	// concentration in M
+	concentration: number;
+	// amount in mol
+	moles: number;
+};
+
+// everything about a system at one moment
+export type SystemState = {
+	equation: string;
+	compounds: CompoundState[];
+	// the equilibrium constant at this temperature
+	k: number;
+	// the reaction quotient, equal to k at equilibrium
+	q: ReactionQuotient;
+	// volume in L
+	volume: number;
+	// temperature in °C
+	temperature: number;
+	// ΔH of the forward reaction in kJ
+	delta_h: number;
+	reversible: boolean;
+	// the kind of reaction, null if it isn't a known kind
+	reaction_type: ReactionType | null;
+};
+
+export function reactionTypeName(kind: ReactionType): string {
+	switch (kind) {
+		case 'SingleReplacement':
+			return 'single replacement';
+		case 'DoubleReplacement':
+			return 'double replacement';
+		case 'Neutralization':
+			return 'acid-base neutralization';
+		default:
+			return kind.toLowerCase();
+	}
+}
+
+export async function getSystemState(idx: number): Promise<SystemState> {
+	return await invoke('get_system_state', { idx });
+}
+
+export type ExportFormat = 'csv' | 'json';
+
+// ask where to save the history of a system, resolving to where it was saved, or null if the
+// dialog was closed
+export async function exportHistory(idx: number, format: ExportFormat): Promise<string | null> {
+	return await invoke('export_history', { idx, format });
+}